@@ -38,6 +38,9 @@ pub enum Error {
     #[error("Invalid secp256k1 secret key")]
     InvalidSecpSecret,
 
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+
     #[error("Search derived address failed")]
     SearchDerivedAddrFailed,
 
@@ -0,0 +1,147 @@
+use bitcoin::hashes::{hmac, sha256, sha512, Hash, HashEngine};
+use rand::Rng;
+
+use super::Error;
+
+/// The BIP-39 English wordlist (sorted)
+const ENGLISH_WORDLIST: &str = include_str!("bip39_english.txt");
+const PBKDF2_ROUNDS: u32 = 2048;
+
+/// BIP-39 mnemonic phrase, only the English wordlist is supported
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mnemonic {
+    words: Vec<&'static str>,
+}
+
+fn wordlist() -> Vec<&'static str> {
+    ENGLISH_WORDLIST.lines().collect()
+}
+
+impl Mnemonic {
+    /// Generate a new mnemonic with 12, 15, 18, 21 or 24 words
+    pub fn generate(word_count: usize) -> Result<Mnemonic, Error> {
+        if ![12, 15, 18, 21, 24].contains(&word_count) {
+            return Err(Error::InvalidMnemonic(format!(
+                "invalid word count: {}, expected: 12, 15, 18, 21 or 24",
+                word_count
+            )));
+        }
+        let entropy: [u8; 32] = rand::thread_rng().gen();
+        Self::from_entropy(&entropy[0..word_count / 3 * 4])
+    }
+
+    pub fn from_entropy(entropy: &[u8]) -> Result<Mnemonic, Error> {
+        if entropy.len() < 16 || entropy.len() > 32 || entropy.len() % 4 != 0 {
+            return Err(Error::InvalidMnemonic(format!(
+                "invalid entropy length: {}",
+                entropy.len()
+            )));
+        }
+        let checksum = sha256::Hash::hash(entropy).into_inner();
+        let total_bits = entropy.len() * 8 + entropy.len() / 4;
+        let get_bit = |index: usize| {
+            let byte = if index < entropy.len() * 8 {
+                entropy[index / 8]
+            } else {
+                checksum[(index - entropy.len() * 8) / 8]
+            };
+            (byte >> (7 - index % 8)) & 1 == 1
+        };
+        let wordlist = wordlist();
+        let words = (0..total_bits / 11)
+            .map(|word_index| {
+                let index = (0..11).fold(0usize, |acc, bit| {
+                    (acc << 1) | get_bit(word_index * 11 + bit) as usize
+                });
+                wordlist[index]
+            })
+            .collect();
+        Ok(Mnemonic { words })
+    }
+
+    /// Parse the phrase and verify the checksum, the words are case insensitive and separated by
+    /// whitespaces.
+    pub fn from_phrase(phrase: &str) -> Result<Mnemonic, Error> {
+        let wordlist = wordlist();
+        let mut indexes = Vec::new();
+        let mut words = Vec::new();
+        for word in phrase.split_whitespace() {
+            let word = word.to_lowercase();
+            let index = wordlist
+                .binary_search(&word.as_str())
+                .map_err(|_| Error::InvalidMnemonic(format!("unknown word: {}", word)))?;
+            indexes.push(index);
+            words.push(wordlist[index]);
+        }
+        if ![12, 15, 18, 21, 24].contains(&words.len()) {
+            return Err(Error::InvalidMnemonic(format!(
+                "invalid word count: {}, expected: 12, 15, 18, 21 or 24",
+                words.len()
+            )));
+        }
+        let mut bits = Vec::with_capacity(words.len() * 11);
+        for index in indexes {
+            for bit in (0..11).rev() {
+                bits.push((index >> bit) & 1 == 1);
+            }
+        }
+        let entropy_len = words.len() / 3 * 4;
+        let entropy = bits[0..entropy_len * 8]
+            .chunks(8)
+            .map(|byte_bits| {
+                byte_bits
+                    .iter()
+                    .fold(0u8, |acc, bit| (acc << 1) | *bit as u8)
+            })
+            .collect::<Vec<_>>();
+        let mnemonic = Self::from_entropy(&entropy)?;
+        if mnemonic.words != words {
+            return Err(Error::InvalidMnemonic("checksum mismatch".to_string()));
+        }
+        Ok(mnemonic)
+    }
+
+    pub fn words(&self) -> &[&'static str] {
+        &self.words
+    }
+
+    pub fn phrase(&self) -> String {
+        self.words.join(" ")
+    }
+
+    /// The 64 bytes BIP-39 seed: PBKDF2-HMAC-SHA512(phrase, "mnemonic" + passphrase, 2048)
+    ///
+    /// The words are ascii, the passphrase must also be ascii since the unicode normalization
+    /// (NFKD) is not supported.
+    pub fn to_seed(&self, passphrase: &str) -> Result<[u8; 64], Error> {
+        if !passphrase.is_ascii() {
+            return Err(Error::InvalidMnemonic(
+                "only ascii passphrase is supported".to_string(),
+            ));
+        }
+        let salt = format!("mnemonic{}", passphrase);
+        Ok(pbkdf2_hmac_sha512(
+            self.phrase().as_bytes(),
+            salt.as_bytes(),
+            PBKDF2_ROUNDS,
+        ))
+    }
+}
+
+// The output length is the same as the hash length, so only one block is computed.
+fn pbkdf2_hmac_sha512(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 64] {
+    let mut engine = hmac::HmacEngine::<sha512::Hash>::new(password);
+    engine.input(salt);
+    engine.input(&1u32.to_be_bytes());
+    let mut block = hmac::Hmac::<sha512::Hash>::from_engine(engine).into_inner();
+    let mut result = block;
+    for _ in 1..rounds {
+        let mut engine = hmac::HmacEngine::<sha512::Hash>::new(password);
+        engine.input(&block);
+        block = hmac::Hmac::<sha512::Hash>::from_engine(engine).into_inner();
+        for (byte, block_byte) in result.iter_mut().zip(block.iter()) {
+            *byte ^= block_byte;
+        }
+    }
+    result
+}
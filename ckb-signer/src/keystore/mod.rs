@@ -1,4 +1,5 @@
 mod error;
+mod mnemonic;
 mod passphrase;
 pub(crate) mod signer;
 mod util;
@@ -18,6 +19,7 @@ use rand::Rng;
 use secp256k1::ecdsa::{RecoverableSignature, Signature};
 use uuid::Uuid;
 
+use bitcoin::hashes::{hmac, sha512, Hash, HashEngine};
 use bitcoin::util::bip32::{
     ChainCode, ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey,
 };
@@ -27,6 +29,7 @@ use ckb_sdk::util::{zeroize_privkey, zeroize_slice};
 use ckb_types::{H160, H256};
 
 pub use error::Error;
+pub use mnemonic::Mnemonic;
 pub use passphrase::{CipherParams, Crypto, KdfParams, ScryptParams, ScryptType};
 
 const KEYSTORE_VERSION: u32 = 3;
@@ -80,6 +83,9 @@ impl KeyStore {
         self.ckb_roots.insert(hash160.clone(), key.ckb_root());
         Ok(hash160)
     }
+    pub fn keys_dir(&self) -> &Path {
+        &self.keys_dir
+    }
     pub fn get_accounts(&mut self) -> &HashMap<H160, PathBuf> {
         self.refresh_dir().ok();
         &self.files
@@ -136,6 +142,20 @@ impl KeyStore {
             .get(hash160)
             .map(|timed_key| timed_key.timeout)
     }
+    /// Lock the accounts whose unlock time is over, the master private keys are zeroized when
+    /// dropped. Returns the locked accounts.
+    pub fn lock_expired(&mut self) -> Vec<H160> {
+        let mut expired = Vec::new();
+        self.unlocked_keys.retain(|hash160, timed_key| {
+            if timed_key.is_expired() {
+                expired.push(hash160.clone());
+                false
+            } else {
+                true
+            }
+        });
+        expired
+    }
 
     pub fn import(
         &mut self,
@@ -827,6 +847,22 @@ impl MasterPrivKey {
         })
     }
 
+    /// The BIP-32 master key of the seed
+    pub fn from_seed(seed: &[u8]) -> Result<MasterPrivKey, Error> {
+        let mut engine = hmac::HmacEngine::<sha512::Hash>::new(b"Bitcoin seed");
+        engine.input(seed);
+        let data = hmac::Hmac::<sha512::Hash>::from_engine(engine).into_inner();
+        MasterPrivKey::from_bytes(data)
+    }
+
+    /// The master key of the BIP-39 mnemonic, derived keys are the same as Neuron wallet
+    pub fn from_mnemonic(mnemonic: &Mnemonic, passphrase: &str) -> Result<MasterPrivKey, Error> {
+        let mut seed = mnemonic.to_seed(passphrase)?;
+        let result = MasterPrivKey::from_seed(&seed);
+        zeroize_slice(&mut seed);
+        result
+    }
+
     pub fn to_bytes(&self) -> [u8; 64] {
         let mut bytes = [0u8; 64];
         bytes[0..32].copy_from_slice(&self.secp_secret_key[..]);
@@ -961,4 +997,69 @@ mod tests {
         assert_eq!(key_set, key_set_by_index);
         assert_eq!(key_set, expected_key_set);
     }
+
+    #[test]
+    fn test_mnemonic() {
+        // Test vectors from BIP-39 (passphrase: "TREZOR")
+        let vectors = [
+            (
+                "00000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+                "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+            ),
+        ];
+        for (entropy_hex, phrase, seed_hex) in vectors {
+            let mut entropy = vec![0u8; entropy_hex.len() / 2];
+            hex_decode(entropy_hex.as_bytes(), &mut entropy).unwrap();
+            let mnemonic = Mnemonic::from_entropy(&entropy).unwrap();
+            assert_eq!(mnemonic.phrase(), phrase);
+            assert_eq!(Mnemonic::from_phrase(phrase).unwrap(), mnemonic);
+            let seed = mnemonic.to_seed("TREZOR").unwrap();
+            assert_eq!(hex_string(&seed), seed_hex);
+        }
+
+        // Same derived addresses as Neuron wallet
+        let mnemonic = Mnemonic::from_phrase(
+            "Abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon  about",
+        )
+        .unwrap();
+        let master_privkey = MasterPrivKey::from_mnemonic(&mnemonic, "").unwrap();
+        assert_eq!(
+            hex_string(&master_privkey.to_bytes()),
+            "1837c1be8e2995ec11cda2b066151be2cfb48adf9e47b151d46adab3a21cdf677923408dadd3c7b56eed15567707ae5e5dca089de972e07f3b860450e2a3b70e"
+        );
+        let key_set = master_privkey
+            .ckb_root()
+            .derived_key_set_by_index(0, 1, 0, 0);
+        assert_eq!(
+            key_set.external[0].1,
+            h160!("0x196f6c1f21f7dbf0df814539b840059facbafc24")
+        );
+        let key = Key::new(master_privkey);
+        assert_eq!(
+            key.hash160(),
+            &h160!("0xc439c37837473519841d67c4a147505760eda082")
+        );
+
+        // Checksum mismatch
+        assert!(Mnemonic::from_phrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon"
+        )
+        .is_err());
+        assert!(Mnemonic::from_phrase("abandon about").is_err());
+        assert!(Mnemonic::from_phrase(&"abandon ".repeat(11).replace("abandon", "xyz")).is_err());
+        assert_eq!(Mnemonic::generate(24).unwrap().words().len(), 24);
+        assert!(Mnemonic::generate(13).is_err());
+        assert!(mnemonic.to_seed("密码").is_err());
+    }
 }
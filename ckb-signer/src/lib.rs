@@ -3,5 +3,5 @@ mod keystore;
 pub use keystore::signer::FileSystemKeystoreSigner;
 pub use keystore::{
     CipherParams, Crypto, DerivedKeySet, Error as KeyStoreError, KdfParams, Key, KeyChain,
-    KeyStore, KeyTimeout, MasterPrivKey, ScryptParams, ScryptType, CKB_ROOT_PATH,
+    KeyStore, KeyTimeout, MasterPrivKey, Mnemonic, ScryptParams, ScryptType, CKB_ROOT_PATH,
 };
@@ -1,5 +1,7 @@
+use std::cmp::Ordering;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use ansi_term::Colour::Green;
@@ -13,24 +15,27 @@ use ckb_signer::KeyStore;
 
 use crate::plugin::PluginManager;
 use crate::subcommands::{
-    AccountSubCommand, CliSubCommand, DAOSubCommand, DeploySubCommand, MockTxSubCommand,
-    MoleculeSubCommand, PluginSubCommand, RpcSubCommand, SudtSubCommand, TxSubCommand,
-    UtilSubCommand, WalletSubCommand,
+    AccountSubCommand, AddressBookSubCommand, CliSubCommand, DAOSubCommand, DeploySubCommand,
+    MockTxSubCommand, MoleculeSubCommand, Output, PluginSubCommand, ProfileSubCommand,
+    RpcSubCommand, SudtSubCommand, TxSubCommand, UtilSubCommand, WalletSubCommand,
 };
 use crate::utils::{
-    completer::CkbCompleter,
+    completer::{CkbCompleter, CompletionValues},
     config::GlobalConfig,
     genesis_info::GenesisInfo,
     other::{check_alerts, get_genesis_info, get_network_type},
-    printer::{ColorWhen, OutputFormat, Printable},
+    printer::{ColorWhen, OutputFormat, OutputQuery, Printable},
     rpc::{HttpRpcClient, RawHttpRpcClient},
 };
 
 const ENV_PATTERN: &str = r"\$\{\s*(?P<key>\S+)\s*\}";
+/// `source` can be nested in scripts, limit the depth to avoid infinite recursion
+const MAX_SCRIPT_DEPTH: usize = 16;
 
 /// Interactive command line
 pub struct InteractiveEnv {
     config: GlobalConfig,
+    ckb_cli_dir: PathBuf,
     config_file: PathBuf,
     history_file: PathBuf,
     parser: clap::App<'static>,
@@ -39,6 +44,8 @@ pub struct InteractiveEnv {
     rpc_client: HttpRpcClient,
     raw_rpc_client: RawHttpRpcClient,
     genesis_info: Option<GenesisInfo>,
+    script_depth: usize,
+    completion_values: Arc<Mutex<CompletionValues>>,
 }
 
 impl InteractiveEnv {
@@ -56,7 +63,7 @@ impl InteractiveEnv {
         let mut config_file = ckb_cli_dir.clone();
         config_file.push("config");
 
-        let mut env_file = ckb_cli_dir;
+        let mut env_file = ckb_cli_dir.clone();
         env_file.push("env_vars");
         if env_file.as_path().exists() {
             let file = fs::File::open(&env_file).map_err(|err| err.to_string())?;
@@ -67,11 +74,18 @@ impl InteractiveEnv {
             }
         }
 
+        // Also covers the profile restored from the config file
+        if let Some(profile) = config.active_profile() {
+            profile.check_network(config.network())?;
+        }
+
         let parser = crate::build_interactive();
-        let rpc_client = HttpRpcClient::new(config.get_url().to_string());
+        let rpc_client = config.rpc_client();
         let raw_rpc_client = RawHttpRpcClient::new(config.get_url());
+        let completion_values = Arc::new(Mutex::new(CompletionValues::new(ckb_cli_dir.clone())));
         Ok(InteractiveEnv {
             config,
+            ckb_cli_dir,
             config_file,
             history_file,
             parser,
@@ -80,6 +94,8 @@ impl InteractiveEnv {
             rpc_client,
             raw_rpc_client,
             genesis_info: None,
+            script_depth: 0,
+            completion_values,
         })
     }
 
@@ -88,17 +104,6 @@ impl InteractiveEnv {
         self.config.print(true);
 
         let env_regex = Regex::new(ENV_PATTERN).unwrap();
-        let prompt = {
-            #[cfg(unix)]
-            {
-                use ansi_term::Colour::Blue;
-                Blue.bold().paint("CKB> ").to_string()
-            }
-            #[cfg(not(unix))]
-            {
-                "CKB> ".to_string()
-            }
-        };
 
         let rl_mode = |rl: &mut Editor<CkbCompleter>, is_list: bool, is_emacs: bool| {
             if is_list {
@@ -134,7 +139,7 @@ impl InteractiveEnv {
             .max_history_size(1000)
             .build();
         let mut rl = Editor::with_config(rl_config);
-        let helper = CkbCompleter::new(parser.clone());
+        let helper = CkbCompleter::new(parser.clone(), Arc::clone(&self.completion_values));
         rl.set_helper(Some(helper));
         rl.bind_sequence(KeyEvent::alt('n'), Cmd::HistorySearchForward);
         rl.bind_sequence(KeyEvent::alt('p'), Cmd::HistorySearchBackward);
@@ -149,7 +154,7 @@ impl InteractiveEnv {
                 self.config.completion_style(),
                 self.config.edit_style(),
             );
-            match rl.readline(&prompt) {
+            match rl.readline(&self.prompt()) {
                 Ok(line) => {
                     match self.handle_command(&parser, line.as_str(), &env_regex) {
                         Ok(true) => {
@@ -208,23 +213,135 @@ impl InteractiveEnv {
         );
     }
 
+    /// Show the active profile in the prompt: `CKB (testnet)> `
+    fn prompt(&self) -> String {
+        let prompt = match self.config.profile() {
+            Some(name) => format!("CKB ({})> ", name),
+            None => "CKB> ".to_string(),
+        };
+        #[cfg(unix)]
+        {
+            use ansi_term::Colour::Blue;
+            Blue.bold().paint(prompt).to_string()
+        }
+        #[cfg(not(unix))]
+        {
+            prompt
+        }
+    }
+
+    /// Reconnect after the url or the profile is changed
+    fn reset_rpc_client(&mut self) {
+        self.rpc_client = self.config.rpc_client();
+        self.raw_rpc_client = RawHttpRpcClient::new(self.config.get_url());
+        self.config
+            .set_network(get_network_type(&mut self.rpc_client).ok());
+        self.genesis_info = None;
+    }
+
     fn genesis_info(&mut self) -> Result<GenesisInfo, String> {
         self.genesis_info = Some(get_genesis_info(&self.genesis_info, &mut self.rpc_client)?);
         Ok(self.genesis_info.clone().unwrap())
     }
 
+    /// Run the commands of a script file (stop at the first error), the interactive shell is
+    /// not started.
+    pub fn run_script(&mut self, path: &Path) -> Result<(), String> {
+        let parser = self.parser.clone();
+        let env_regex = Regex::new(ENV_PATTERN).unwrap();
+        self.source(&parser, path, &env_regex).map(|_| ())
+    }
+
+    /// Returns true if the script runs `exit`
+    fn source(
+        &mut self,
+        parser: &clap::App,
+        path: &Path,
+        env_regex: &Regex,
+    ) -> Result<bool, String> {
+        if self.script_depth >= MAX_SCRIPT_DEPTH {
+            return Err(format!(
+                "Too many nested scripts (max: {}): {}",
+                MAX_SCRIPT_DEPTH,
+                path.display()
+            ));
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("Read script {} failed: {}", path.display(), err))?;
+        self.script_depth += 1;
+        let result = self.run_lines(parser, path, &content, env_regex);
+        self.script_depth -= 1;
+        result
+    }
+
+    fn run_lines(
+        &mut self,
+        parser: &clap::App,
+        path: &Path,
+        content: &str,
+        env_regex: &Regex,
+    ) -> Result<bool, String> {
+        let mut command = String::new();
+        let mut command_line_number = 0;
+        // The empty line at the end ends the last command (even if it ends with `\`)
+        for (idx, line) in content.lines().chain(std::iter::once("")).enumerate() {
+            if command.is_empty() {
+                command_line_number = idx + 1;
+            }
+            // A trailing backslash continues the command on the next line
+            if let Some(part) = line.strip_suffix('\\') {
+                command.push_str(part);
+                command.push(' ');
+                continue;
+            }
+            command.push_str(line);
+            let current = std::mem::take(&mut command);
+            let current = current.trim();
+            if current.is_empty() || current.starts_with('#') {
+                continue;
+            }
+            match self.handle_command(parser, current, env_regex) {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(err) => {
+                    return Err(format!(
+                        "{}:{}: `{}` failed: {}",
+                        path.display(),
+                        command_line_number,
+                        current,
+                        err
+                    ))
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns true if the command is `exit`
     fn handle_command(
         &mut self,
         parser: &clap::App,
         line: &str,
         env_regex: &Regex,
     ) -> Result<bool, String> {
-        let args = match shell_words::split(self.config.replace_cmd(env_regex, line).as_str()) {
+        let line = self.config.replace_cmd(env_regex, line);
+        // `let <name> = <command>` saves the output of the command into a variable
+        let (var_name, line) = match parse_let(&line)? {
+            Some((name, command)) => (Some(name), command),
+            None => (None, line.trim_start()),
+        };
+        let args = match shell_words::split(line) {
             Ok(args) => args,
             Err(e) => return Err(e.to_string()),
         };
         if args.is_empty() {
-            return Ok(false);
+            return match var_name {
+                Some(name) => Err(format!("Missing command for variable: {}", name)),
+                None => Ok(false),
+            };
+        }
+        if args[0] == "assert" && var_name.is_none() {
+            return check_assert(&args[1..]).map(|_| false);
         }
 
         let format = self.config.output_format();
@@ -232,30 +349,86 @@ impl InteractiveEnv {
         let debug = self.config.debug();
 
         let current_cmd_name = &args[0];
-        if self
+        let (output, query) = if self
             .plugin_mgr
             .sub_commands()
             .contains_key(current_cmd_name.as_str())
         {
-            let rest_args = line[current_cmd_name.len()..].to_string();
+            // The output arguments are handled here, the plugin only gets the rest
+            let (query, plugin_args) = take_query_args(&args[1..])?;
+            let rest_args = if plugin_args.len() + 1 == args.len() {
+                line.trim_start()[current_cmd_name.len()..].to_string()
+            } else {
+                plugin_args
+                    .iter()
+                    .map(|arg| format!(" {}", quote_arg(arg)))
+                    .collect()
+            };
             log::debug!("[call sub command]: {} {}", current_cmd_name, rest_args);
             let resp = self
                 .plugin_mgr
                 .sub_command(current_cmd_name.as_str(), rest_args)?;
-            println!("{}", resp.render(format, color));
-            return Ok(false);
+            (Some(Output::new_output(resp)), query)
+        } else {
+            match self.run_command(parser, args, env_regex, debug)? {
+                CommandResult::Exit => return Ok(true),
+                CommandResult::Output(output, query) => (output, query),
+            }
+        };
+
+        if let Ok(mut values) = self.completion_values.lock() {
+            values.clear_cache();
+            if let Some(value) = output.as_ref().and_then(Output::stdout) {
+                values.record_output(value);
+            }
+        }
+        match (output, var_name) {
+            (Some(output), Some(name)) => {
+                let value = output
+                    .stdout()
+                    .map(|value| query.select(value))
+                    .transpose()?
+                    .ok_or_else(|| format!("No output to save into variable: {}", name))?;
+                self.config.set(name.to_string(), value);
+            }
+            (Some(output), None) => output.print(format, color, &query)?,
+            (None, Some(name)) => {
+                return Err(format!("No output to save into variable: {}", name));
+            }
+            (None, None) => {}
         }
+        Ok(false)
+    }
 
-        match parser.clone().try_get_matches_from(args) {
-            Ok(matches) => match matches.subcommand() {
+    fn run_command(
+        &mut self,
+        parser: &clap::App,
+        args: Vec<String>,
+        env_regex: &Regex,
+        debug: bool,
+    ) -> Result<CommandResult, String> {
+        let format = self.config.output_format();
+        let color = ColorWhen::new(self.config.color()).color();
+        let parsed = parser
+            .clone()
+            .try_get_matches_from(args)
+            .map_err(|err| err.to_string())
+            .and_then(|matches| OutputQuery::from_matches(&matches).map(|query| (matches, query)));
+        match parsed {
+            Ok((matches, query)) => match matches.subcommand() {
                 ("config", Some(m)) => {
+                    if let ("profile", Some(profile_matches)) = m.subcommand() {
+                        let output = ProfileSubCommand::new(&mut self.config, &self.config_file)
+                            .process(profile_matches, debug)?;
+                        if profile_matches.subcommand_name() == Some("use") {
+                            self.reset_rpc_client();
+                        }
+                        return Ok(CommandResult::Output(Some(output), query));
+                    }
                     if let Some(url) = m.value_of("url") {
                         self.config.set_url(url.to_string());
-                        self.rpc_client = HttpRpcClient::new(self.config.get_url().to_string());
-                        self.raw_rpc_client = RawHttpRpcClient::new(self.config.get_url());
-                        self.config
-                            .set_network(get_network_type(&mut self.rpc_client).ok());
-                        self.genesis_info = None;
+                        self.config.clear_profile();
+                        self.reset_rpc_client();
                     };
                     if m.is_present("color") {
                         self.config.switch_color();
@@ -283,35 +456,40 @@ impl InteractiveEnv {
                     self.config
                         .save(self.config_file.as_path())
                         .map_err(|err| format!("save config file failed: {:?}", err))?;
-                    Ok(())
+                    Ok(None)
                 }
                 ("set", Some(m)) => {
                     let key = m.value_of("key").unwrap().to_owned();
                     let value = m.value_of("value").unwrap().to_owned();
                     self.config.set(key, serde_json::Value::String(value));
-                    Ok(())
+                    Ok(None)
                 }
                 ("get", Some(m)) => {
                     let key = m.value_of("key");
                     println!("{}", self.config.get(key).render(format, color));
-                    Ok(())
+                    Ok(None)
                 }
                 ("info", _) => {
                     self.config.print(false);
-                    Ok(())
+                    Ok(None)
+                }
+                ("source", Some(m)) => {
+                    let path = PathBuf::from(m.value_of("file").unwrap());
+                    if self.source(parser, &path, env_regex)? {
+                        return Ok(CommandResult::Exit);
+                    }
+                    Ok(None)
                 }
                 ("rpc", Some(sub_matches)) => {
                     check_alerts(&mut self.rpc_client);
                     let output = RpcSubCommand::new(&mut self.rpc_client, &mut self.raw_rpc_client)
                         .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("account", Some(sub_matches)) => {
                     let output = AccountSubCommand::new(&mut self.plugin_mgr, &mut self.key_store)
                         .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("mock-tx", Some(sub_matches)) => {
                     let genesis_info = self.genesis_info().ok();
@@ -321,33 +499,28 @@ impl InteractiveEnv {
                         genesis_info,
                     )
                     .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("tx", Some(sub_matches)) => {
                     let genesis_info = self.genesis_info().ok();
                     let output =
                         TxSubCommand::new(&mut self.rpc_client, &mut self.plugin_mgr, genesis_info)
                             .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("util", Some(sub_matches)) => {
                     let output = UtilSubCommand::new(&mut self.rpc_client, &mut self.plugin_mgr)
                         .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("plugin", Some(sub_matches)) => {
                     let output =
                         PluginSubCommand::new(&mut self.plugin_mgr).process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("molecule", Some(sub_matches)) => {
                     let output = MoleculeSubCommand::new().process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("wallet", Some(sub_matches)) => {
                     let genesis_info = self.genesis_info()?;
@@ -357,8 +530,7 @@ impl InteractiveEnv {
                         Some(genesis_info),
                     )
                     .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("dao", Some(sub_matches)) => {
                     let genesis_info = self.genesis_info()?;
@@ -368,8 +540,7 @@ impl InteractiveEnv {
                         genesis_info,
                     )
                     .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("sudt", Some(sub_matches)) => {
                     let genesis_info = self.genesis_info()?;
@@ -377,10 +548,10 @@ impl InteractiveEnv {
                         &mut self.rpc_client,
                         &mut self.plugin_mgr,
                         genesis_info,
+                        self.ckb_cli_dir.clone(),
                     )
                     .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
                 }
                 ("deploy", Some(sub_matches)) => {
                     let genesis_info = self.genesis_info()?;
@@ -390,16 +561,212 @@ impl InteractiveEnv {
                         genesis_info,
                     )
                     .process(sub_matches, debug)?;
-                    output.print(format, color);
-                    Ok(())
+                    Ok(Some(output))
+                }
+                ("addressbook", Some(sub_matches)) => {
+                    let output =
+                        AddressBookSubCommand::new(self.ckb_cli_dir.clone(), self.config.network())
+                            .process(sub_matches, debug)?;
+                    Ok(Some(output))
                 }
                 ("exit", _) => {
-                    return Ok(true);
+                    return Ok(CommandResult::Exit);
                 }
-                _ => Ok(()),
-            },
-            Err(err) => Err(err.to_string()),
+                _ => Ok(None),
+            }
+            .map(|output| CommandResult::Output(output, query)),
+            Err(err) => Err(err),
         }
-        .map(|_| false)
+    }
+}
+
+enum CommandResult {
+    Output(Option<Output>, OutputQuery),
+    Exit,
+}
+
+/// Remove the output arguments (`--query <path>`, `--columns <columns>` and `--raw`) from the
+/// arguments of a plugin sub command
+fn take_query_args(args: &[String]) -> Result<(OutputQuery, Vec<String>), String> {
+    let mut path = None;
+    let mut columns = None;
+    let mut raw = false;
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) if name == "--query" || name == "--columns" => {
+                (name, Some(value.to_string()))
+            }
+            _ => (arg.as_str(), None),
+        };
+        let target = match name {
+            "--raw" => {
+                raw = true;
+                continue;
+            }
+            "--query" => &mut path,
+            "--columns" => &mut columns,
+            _ => {
+                rest.push(arg.clone());
+                continue;
+            }
+        };
+        let value = match value {
+            Some(value) => value,
+            None => iter
+                .next()
+                .ok_or_else(|| format!("Missing value of argument: {}", name))?
+                .clone(),
+        };
+        *target = Some(value);
+    }
+    OutputQuery::new(path.as_deref(), raw)
+        .map(|query| query.with_columns(columns.as_deref()))
+        .map(|query| (query, rest))
+}
+
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,@".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Parse `let <name> = <command>`, return the variable name and the command
+fn parse_let(line: &str) -> Result<Option<(&str, &str)>, String> {
+    let rest = match line.trim_start().strip_prefix("let ") {
+        Some(rest) => rest,
+        None => return Ok(None),
+    };
+    let (name, command) = rest
+        .split_once('=')
+        .ok_or_else(|| "Invalid let statement, expected: let <name> = <command>".to_string())?;
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("Invalid variable name: {:?}", name));
+    }
+    Ok(Some((name, command.trim())))
+}
+
+/// `assert <value>` checks the value is not empty, false or 0
+/// `assert <left> <op> <right>` compares two values, op: == != < <= > >=
+fn check_assert(args: &[String]) -> Result<(), String> {
+    let passed =
+        match args {
+            [value] => !value.is_empty() && value != "false" && value != "0",
+            [left, op, right] => {
+                let ordering = compare_values(left, right);
+                match op.as_str() {
+                    "==" => ordering == Ordering::Equal,
+                    "!=" => ordering != Ordering::Equal,
+                    "<" => ordering == Ordering::Less,
+                    "<=" => ordering != Ordering::Greater,
+                    ">" => ordering == Ordering::Greater,
+                    ">=" => ordering != Ordering::Less,
+                    _ => return Err(format!("Invalid assert operator: {}", op)),
+                }
+            }
+            _ => return Err(
+                "Invalid assert statement, expected: assert <value> or assert <left> <op> <right>"
+                    .to_string(),
+            ),
+        };
+    if passed {
+        Ok(())
+    } else {
+        Err(format!("Assertion failed: {}", args.join(" ")))
+    }
+}
+
+/// Compare as numbers if both values are numbers (the unit suffix like `100.0 (CKB)` is
+/// ignored), otherwise compare as strings.
+fn compare_values(left: &str, right: &str) -> Ordering {
+    let number = |value: &str| {
+        value
+            .split_whitespace()
+            .next()
+            .and_then(|value| value.parse::<f64>().ok())
+    };
+    if let (Ok(left), Ok(right)) = (left.parse::<i128>(), right.parse::<i128>()) {
+        return left.cmp(&right);
+    }
+    match (number(left), number(right)) {
+        (Some(left), Some(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+        _ => left.cmp(right),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_statements() {
+        assert_eq!(
+            parse_let("let tx = wallet transfer --capacity 100"),
+            Ok(Some(("tx", "wallet transfer --capacity 100")))
+        );
+        assert_eq!(parse_let("wallet transfer"), Ok(None));
+        assert!(parse_let("let tx wallet transfer").is_err());
+        assert!(parse_let("let t x = info").is_err());
+
+        let args = |line: &str| shell_words::split(line).unwrap();
+        assert!(check_assert(&args("100.5 == 100.5")).is_ok());
+        assert!(check_assert(&args("'100.5 (CKB)' > 99")).is_ok());
+        assert!(check_assert(&args("18446744073709551615 > 18446744073709551614")).is_ok());
+        assert!(check_assert(&args("abc != abd")).is_ok());
+        assert!(check_assert(&args("1 == 2")).is_err());
+        assert!(check_assert(&args("''")).is_err());
+        assert!(check_assert(&args("true")).is_ok());
+        assert!(check_assert(&args("1 ~ 2")).is_err());
+
+        let mut config = GlobalConfig::new(None);
+        config.set(
+            "tx".to_string(),
+            serde_json::json!({"committed": true, "dropped": false, "error": null}),
+        );
+        let env_regex = Regex::new(ENV_PATTERN).unwrap();
+        let line = config.replace_cmd(&env_regex, "assert ${tx.committed} == true");
+        assert_eq!(line, "assert true == true");
+        assert!(check_assert(&args(&line)[1..]).is_ok());
+        let line = config.replace_cmd(&env_regex, "assert ${tx.dropped}");
+        assert!(check_assert(&args(&line)[1..]).is_err());
+        let line = config.replace_cmd(&env_regex, "assert ${tx.error} == null");
+        assert!(check_assert(&args(&line)[1..]).is_ok());
+    }
+
+    #[test]
+    fn test_plugin_query_args() {
+        let args = |line: &str| shell_words::split(line).unwrap();
+        let (query, rest) = take_query_args(&args("show --query=a.b --raw --name 'x y'")).unwrap();
+        assert_eq!(query, OutputQuery::new(Some("a.b"), true).unwrap());
+        assert_eq!(rest, args("show --name 'x y'"));
+        let (query, rest) = take_query_args(&args("show --query '$[0]' --columns=a,b")).unwrap();
+        assert_eq!(
+            query,
+            OutputQuery::new(Some("$[0]"), false)
+                .unwrap()
+                .with_columns(Some("a,b"))
+        );
+        assert_eq!(rest, vec!["show".to_string()]);
+        assert!(take_query_args(&args("show --query")).is_err());
+
+        let rest = ["a b", "it's", "--name=x"];
+        let line = rest
+            .iter()
+            .map(|arg| quote_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(args(&line), rest);
     }
 }
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::Path;
 use std::process;
 
 use ckb_build_info::Version;
@@ -12,17 +12,20 @@ use clap::{App, AppSettings, Arg};
 use interactive::InteractiveEnv;
 use plugin::PluginManager;
 use subcommands::{
-    AccountSubCommand, ApiServerSubCommand, CliSubCommand, DAOSubCommand, DeploySubCommand,
-    MockTxSubCommand, MoleculeSubCommand, PluginSubCommand, PubSubCommand, RpcSubCommand,
-    SudtSubCommand, TxSubCommand, UtilSubCommand, WalletSubCommand,
+    AccountSubCommand, AddressBookSubCommand, ApiServerSubCommand, CliSubCommand, DAOSubCommand,
+    DeploySubCommand, MockTxSubCommand, MoleculeSubCommand, PluginSubCommand, ProfileSubCommand,
+    PubSubCommand, RpcSubCommand, SudtSubCommand, TxSubCommand, UtilSubCommand, WalletSubCommand,
 };
 use utils::other::get_genesis_info;
 use utils::{
-    arg_parser::{ArgParser, UrlParser},
+    arg_parser::{ArgParser, FilePathParser, UrlParser},
     config::GlobalConfig,
-    other::{check_alerts, get_key_store, get_network_type},
-    printer::{ColorWhen, OutputFormat},
-    rpc::{HttpRpcClient, RawHttpRpcClient},
+    other::{
+        check_alerts, ckb_cli_dir, get_key_store, get_network_type, parse_password_fd,
+        set_password_source, PasswordSource,
+    },
+    printer::{ColorWhen, OutputFormat, OutputQuery},
+    rpc::RawHttpRpcClient,
 };
 
 mod interactive;
@@ -54,21 +57,15 @@ fn main() -> Result<(), io::Error> {
         .map(ToOwned::to_owned)
         .or_else(|| env_map.remove("API_URL"));
     let local_only = matches.is_present("local-only");
+    let profile_opt = matches.value_of("profile");
 
-    let ckb_cli_dir = if let Some(dir_string) = env_map.remove("CKB_CLI_HOME") {
-        let dir = PathBuf::from(dir_string.as_str());
-        if dir.exists() && !dir.is_dir() {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("{} is not a directory", dir_string),
-            ));
-        }
-        dir
-    } else {
-        let mut dir = dirs::home_dir().unwrap();
-        dir.push(".ckb-cli");
-        dir
-    };
+    let ckb_cli_dir = ckb_cli_dir();
+    if ckb_cli_dir.exists() && !ckb_cli_dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a directory", ckb_cli_dir.display()),
+        ));
+    }
     if !ckb_cli_dir.exists() {
         fs::create_dir_all(&ckb_cli_dir)?;
     }
@@ -81,10 +78,24 @@ fn main() -> Result<(), io::Error> {
     if config_file.as_path().exists() {
         let content = fs::read_to_string(&config_file)?;
         let configs: serde_json::Value = serde_json::from_str(content.as_str()).unwrap();
+        if !configs["profiles"].is_null() {
+            let profiles = serde_json::from_value(configs["profiles"].clone()).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid profiles in config file: {}", err),
+                )
+            })?;
+            config.set_profiles(profiles);
+        }
         if ckb_url_opt.is_none() {
             if let Some(value) = configs["url"].as_str() {
                 config.set_url(value.to_string());
             }
+            if let Some(name) = configs["profile"].as_str() {
+                if let Err(err) = config.use_profile(name) {
+                    eprintln!("WARNING: {}", err);
+                }
+            }
         }
         config.set_debug(configs["debug"].as_bool().unwrap_or(false));
         config.set_no_sync(configs["no-sync"].as_bool().unwrap_or(false));
@@ -96,13 +107,29 @@ fn main() -> Result<(), io::Error> {
         config.set_edit_style(configs["edit_style"].as_bool().unwrap_or(true));
     }
 
+    if let Some(name) = profile_opt {
+        config
+            .use_profile(name)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    }
+
     let ckb_url = config.get_url().to_string();
-    let mut rpc_client = HttpRpcClient::new(ckb_url.clone());
+    let mut rpc_client = config.rpc_client();
     let mut raw_rpc_client = RawHttpRpcClient::new(ckb_url.as_str());
 
     if !local_only {
         check_alerts(&mut rpc_client);
         config.set_network(get_network_type(&mut rpc_client).ok());
+        // The profile can be switched by `config profile use` even if the node is in another
+        // network, the interactive shell checks it in `InteractiveEnv::from_config`
+        if let Some(profile) = config.active_profile() {
+            if !matches!(matches.subcommand_name(), None | Some("config")) {
+                if let Err(err) = profile.check_network(config.network()) {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            }
+        }
     }
 
     let color = ColorWhen::new(!matches.is_present("no-color")).color();
@@ -111,6 +138,17 @@ fn main() -> Result<(), io::Error> {
     if let Some(format) = matches.value_of("output-format") {
         output_format = OutputFormat::from_str(format).unwrap();
     }
+    let query = OutputQuery::from_matches(&matches).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    match PasswordSource::from_matches(&matches) {
+        Ok(source) => set_password_source(source),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
     let mut key_store = get_key_store(ckb_cli_dir.clone()).map_err(|err| {
         io::Error::new(
             io::ErrorKind::Other,
@@ -121,7 +159,8 @@ fn main() -> Result<(), io::Error> {
     let result = match matches.subcommand() {
         ("rpc", Some(sub_matches)) => match sub_matches.subcommand() {
             ("subscribe", Some(sub_sub_matches)) => {
-                PubSubCommand::new(output_format, color).process(sub_sub_matches, debug)
+                PubSubCommand::new(output_format, color, query.clone())
+                    .process(sub_sub_matches, debug)
             }
             _ => {
                 RpcSubCommand::new(&mut rpc_client, &mut raw_rpc_client).process(sub_matches, debug)
@@ -147,6 +186,12 @@ fn main() -> Result<(), io::Error> {
             PluginSubCommand::new(&mut plugin_mgr).process(sub_matches, debug)
         }
         ("molecule", Some(sub_matches)) => MoleculeSubCommand::new().process(sub_matches, debug),
+        ("config", Some(sub_matches)) => match sub_matches.subcommand() {
+            ("profile", Some(profile_matches)) => {
+                ProfileSubCommand::new(&mut config, &config_file).process(profile_matches, debug)
+            }
+            _ => Err("Missing subcommand, see: ckb-cli config --help".to_string()),
+        },
         ("wallet", Some(sub_matches)) => {
             WalletSubCommand::new(&mut rpc_client, &mut plugin_mgr, None)
                 .process(sub_matches, debug)
@@ -159,8 +204,13 @@ fn main() -> Result<(), io::Error> {
         }
         ("sudt", Some(sub_matches)) => {
             get_genesis_info(&None, &mut rpc_client).and_then(|genesis_info| {
-                SudtSubCommand::new(&mut rpc_client, &mut plugin_mgr, genesis_info)
-                    .process(sub_matches, debug)
+                SudtSubCommand::new(
+                    &mut rpc_client,
+                    &mut plugin_mgr,
+                    genesis_info,
+                    ckb_cli_dir.clone(),
+                )
+                .process(sub_matches, debug)
             })
         }
         ("deploy", Some(sub_matches)) => {
@@ -169,10 +219,18 @@ fn main() -> Result<(), io::Error> {
                     .process(sub_matches, debug)
             })
         }
+        ("addressbook", Some(sub_matches)) => {
+            AddressBookSubCommand::new(ckb_cli_dir.clone(), config.network())
+                .process(sub_matches, debug)
+        }
         _ => {
             if let Err(err) =
-                InteractiveEnv::from_config(ckb_cli_dir, config, plugin_mgr, key_store)
-                    .and_then(|mut env| env.start())
+                InteractiveEnv::from_config(ckb_cli_dir, config, plugin_mgr, key_store).and_then(
+                    |mut env| match matches.value_of("script") {
+                        Some(path) => env.run_script(Path::new(path)),
+                        None => env.start(),
+                    },
+                )
             {
                 eprintln!("Process error: {}", err);
                 process::exit(1);
@@ -181,10 +239,8 @@ fn main() -> Result<(), io::Error> {
         }
     };
 
-    match result {
-        Ok(output) => {
-            output.print(output_format, color);
-        }
+    match result.and_then(|output| output.print(output_format, color, &query)) {
+        Ok(()) => {}
         Err(err) => {
             eprintln!("{}", err);
             process::exit(1);
@@ -245,6 +301,12 @@ pub fn build_cli<'a>(version_short: &'a str, version_long: &'a str) -> App<'a> {
         .subcommand(DAOSubCommand::subcommand())
         .subcommand(SudtSubCommand::subcommand("sudt"))
         .subcommand(DeploySubCommand::subcommand("deploy"))
+        .subcommand(AddressBookSubCommand::subcommand("addressbook"))
+        .subcommand(
+            App::new("config")
+                .about("Config ckb-cli")
+                .subcommand(ProfileSubCommand::subcommand("profile")),
+        )
         .arg(
 
             Arg::with_name("url")
@@ -257,15 +319,32 @@ The default value is http://127.0.0.1:8114
 You may also use some public available nodes, check the list of public nodes: https://github.com/nervosnetwork/ckb/wiki/Public-JSON-RPC-nodes"#,
                 ),
         )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .takes_value(true)
+                .conflicts_with("url")
+                .about("Use the named network profile (see: `ckb-cli config profile`)"),
+        )
+        .arg(
+            Arg::with_name("script")
+                .long("script")
+                .takes_value(true)
+                .validator(|input| FilePathParser::new(true).validate(input))
+                .about("Run the commands of the script file (like `source` in the interactive shell) instead of starting the interactive shell, exit with error at the first failed command"),
+        )
         .arg(
             Arg::with_name("output-format")
                 .long("output-format")
                 .takes_value(true)
-                .possible_values(&["yaml", "json"])
+                .possible_values(&["yaml", "json", "table", "csv", "tsv"])
                 .default_value("yaml")
                 .global(true)
                 .about("Select output format"),
         )
+        .arg(arg_query().global(true))
+        .arg(arg_raw().global(true))
+        .arg(arg_columns().global(true))
         .arg(
             Arg::with_name("no-color")
                 .long("no-color")
@@ -284,6 +363,52 @@ You may also use some public available nodes, check the list of public nodes: ht
                 .global(true)
                 .about("This is a local only subcommand, do not check alerts and get network type"),
         )
+        .arg(
+            Arg::with_name("password-file")
+                .long("password-file")
+                .takes_value(true)
+                .global(true)
+                .conflicts_with_all(&["password-env", "password-fd"])
+                .about("Read keystore passwords from the file instead of prompting, one password per line in the order of the prompts"),
+        )
+        .arg(
+            Arg::with_name("password-env")
+                .long("password-env")
+                .takes_value(true)
+                .value_name("VAR")
+                .global(true)
+                .conflicts_with("password-fd")
+                .about("Read the keystore password from the environment variable instead of prompting"),
+        )
+        .arg(
+            Arg::with_name("password-fd")
+                .long("password-fd")
+                .takes_value(true)
+                .value_name("N")
+                .global(true)
+                .validator(|input| parse_password_fd(input).map(|_| ()))
+                .about("Read keystore passwords from the file descriptor instead of prompting, one password per line in the order of the prompts"),
+        )
+}
+
+fn arg_query() -> Arg<'static> {
+    Arg::with_name("query")
+        .long("query")
+        .takes_value(true)
+        .about("Only print the selected part of the output, the query is a JSONPath like `$.live_cells[0].capacity` or `transactions[*].hash`")
+}
+
+fn arg_raw() -> Arg<'static> {
+    Arg::with_name("raw")
+        .long("raw")
+        .about("Print string, number and boolean output without quotes, an array of them is printed one item per line")
+}
+
+fn arg_columns() -> Arg<'static> {
+    Arg::with_name("columns")
+        .long("columns")
+        .takes_value(true)
+        .about("The columns of table, csv and tsv output formats, separated by comma (like `capacity,out_point`, a column also selects its nested columns)")
 }
 
 pub fn build_interactive() -> App<'static> {
@@ -321,7 +446,7 @@ You may also use some public available nodes, check the list of public nodes: ht
                     Arg::with_name("output-format")
                         .long("output-format")
                         .takes_value(true)
-                        .possible_values(&["yaml", "json"])
+                        .possible_values(&["yaml", "json", "table", "csv", "tsv"])
                         .default_value("yaml")
                         .about("Select output format"),
                 )
@@ -334,9 +459,24 @@ You may also use some public available nodes, check the list of public nodes: ht
                     Arg::with_name("edit_style")
                         .long("edit_style")
                         .about("Switch edit style"),
-                ),
+                )
+                .subcommand(ProfileSubCommand::subcommand("profile")),
         )
+        .arg(arg_query().global(true))
+        .arg(arg_raw().global(true))
+        .arg(arg_columns().global(true))
         .subcommand(App::new("info").about("Display global variables"))
+        .subcommand(
+            App::new("source")
+                .about("Run the commands of a script file, stop at the first error. Besides commands, a script line can be: `let <name> = <command>` (save the output into variable ${name}), `assert <value>`, `assert <left> <op> <right>` (op: == != < <= > >=) or a `#` comment")
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .index(1)
+                        .validator(|input| FilePathParser::new(true).validate(input))
+                        .about("The script file path"),
+                ),
+        )
         .subcommand(
             App::new("exit")
                 .visible_alias("quit")
@@ -353,4 +493,5 @@ You may also use some public available nodes, check the list of public nodes: ht
         .subcommand(DAOSubCommand::subcommand())
         .subcommand(SudtSubCommand::subcommand("sudt"))
         .subcommand(DeploySubCommand::subcommand("deploy"))
+        .subcommand(AddressBookSubCommand::subcommand("addressbook"))
 }
@@ -8,14 +8,71 @@ use crossbeam_channel::bounded;
 use ckb_sdk::util::serialize_signature;
 use ckb_signer::{DerivedKeySet, Key, KeyStore, MasterPrivKey};
 use ckb_types::core::service::Request;
+use ckb_types::{H160, H256};
 use plugin_protocol::{JsonrpcError, KeyStoreRequest, PluginRequest, PluginResponse};
 
 use super::manager::PluginHandler;
 use crate::utils::other::get_key_store;
 use crate::utils::rpc::JsonBytes;
+#[cfg(unix)]
+use crate::utils::unlock_agent::AgentClient;
 
 pub const ERROR_KEYSTORE_REQUIRE_PASSWORD: &str = "keystore require password";
 
+/// Sign with the key held by the unlock agent, the password is required if the agent is not
+/// running or the account is not unlocked in it
+#[cfg(unix)]
+fn sign_by_agent(
+    ckb_cli_dir: &Path,
+    hash160: H160,
+    path: String,
+    message: H256,
+    recoverable: bool,
+) -> Result<Vec<u8>, String> {
+    let client = AgentClient::new(ckb_cli_dir);
+    if client.is_running() {
+        if let Ok(signature) = client.sign(hash160, path, message, recoverable) {
+            return Ok(signature);
+        }
+    }
+    Err(String::from(ERROR_KEYSTORE_REQUIRE_PASSWORD))
+}
+
+#[cfg(not(unix))]
+fn sign_by_agent(
+    _ckb_cli_dir: &Path,
+    _hash160: H160,
+    _path: String,
+    _message: H256,
+    _recoverable: bool,
+) -> Result<Vec<u8>, String> {
+    Err(String::from(ERROR_KEYSTORE_REQUIRE_PASSWORD))
+}
+
+#[cfg(unix)]
+fn extended_pubkey_by_agent(
+    ckb_cli_dir: &Path,
+    hash160: H160,
+    path: String,
+) -> Result<Vec<u8>, String> {
+    let client = AgentClient::new(ckb_cli_dir);
+    if client.is_running() {
+        if let Ok(pubkey) = client.extended_pubkey(hash160, path) {
+            return Ok(pubkey);
+        }
+    }
+    Err(String::from(ERROR_KEYSTORE_REQUIRE_PASSWORD))
+}
+
+#[cfg(not(unix))]
+fn extended_pubkey_by_agent(
+    _ckb_cli_dir: &Path,
+    _hash160: H160,
+    _path: String,
+) -> Result<Vec<u8>, String> {
+    Err(String::from(ERROR_KEYSTORE_REQUIRE_PASSWORD))
+}
+
 pub(crate) struct DefaultKeyStore {
     handler: PluginHandler,
     _thread: JoinHandle<()>,
@@ -39,6 +96,7 @@ impl DefaultKeyStore {
 
         fn handle_request(
             keystore: &mut KeyStore,
+            ckb_cli_dir: &Path,
             request: KeyStoreRequest,
         ) -> Result<PluginResponse, String> {
             match request {
@@ -172,8 +230,13 @@ impl DefaultKeyStore {
                     password,
                     recoverable,
                 } => {
-                    let password =
-                        password.ok_or_else(|| String::from(ERROR_KEYSTORE_REQUIRE_PASSWORD))?;
+                    let password = match password {
+                        Some(password) => password,
+                        None => {
+                            return sign_by_agent(ckb_cli_dir, hash160, path, message, recoverable)
+                                .map(|data| PluginResponse::Bytes(JsonBytes::from_vec(data)));
+                        }
+                    };
                     let path = DerivationPath::from_str(&path).map_err(|err| err.to_string())?;
                     let signature = if recoverable {
                         keystore
@@ -199,8 +262,13 @@ impl DefaultKeyStore {
                     path,
                     password,
                 } => {
-                    let password =
-                        password.ok_or_else(|| String::from(ERROR_KEYSTORE_REQUIRE_PASSWORD))?;
+                    let password = match password {
+                        Some(password) => password,
+                        None => {
+                            return extended_pubkey_by_agent(ckb_cli_dir, hash160, path)
+                                .map(|data| PluginResponse::Bytes(JsonBytes::from_vec(data)));
+                        }
+                    };
                     let path = DerivationPath::from_str(&path).map_err(|err| err.to_string())?;
                     let data = keystore
                         .extended_pubkey_with_password(&hash160, &path, password.as_bytes())
@@ -219,6 +287,7 @@ impl DefaultKeyStore {
 
         let (keystore_sender, keystore_receiver) = bounded(1);
         let mut keystore = get_key_store(ckb_cli_dir.to_path_buf())?;
+        let ckb_cli_dir = ckb_cli_dir.to_path_buf();
 
         let keystore_thread = thread::spawn(move || loop {
             match keystore_receiver.recv() {
@@ -228,7 +297,7 @@ impl DefaultKeyStore {
                 }) => {
                     let (id, plugin_request) = arguments;
                     let response = if let PluginRequest::KeyStore(request) = plugin_request {
-                        handle_request(&mut keystore, request).unwrap_or_else(|err| {
+                        handle_request(&mut keystore, &ckb_cli_dir, request).unwrap_or_else(|err| {
                             PluginResponse::Error(JsonrpcError {
                                 code: 0,
                                 message: err,
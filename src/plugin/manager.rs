@@ -17,7 +17,7 @@ use ckb_signer::{DerivedKeySet, MasterPrivKey, CKB_ROOT_PATH};
 use ckb_types::{bytes::Bytes, core::service::Request, H160, H256};
 
 use super::builtin::{DefaultIndexer, DefaultKeyStore, ERROR_KEYSTORE_REQUIRE_PASSWORD};
-use crate::utils::other::read_password;
+use crate::utils::other::{is_unlocked_in_agent, read_password};
 use crate::utils::rpc::HttpRpcClient;
 use plugin_protocol::{
     CallbackName, CallbackRequest, CallbackResponse, IndexerRequest, JsonrpcError, JsonrpcRequest,
@@ -226,6 +226,15 @@ impl PluginManager {
             .map(|(_, _, require_password)| require_password)
             .unwrap_or(true)
     }
+    /// The password to sign with the account, `None` if the keystore does not require password
+    /// or the account is unlocked in the unlock agent
+    pub fn keystore_password(&self, account: &H160) -> Result<Option<String>, String> {
+        if !self.keystore_require_password() || is_unlocked_in_agent(account) {
+            Ok(None)
+        } else {
+            read_password(false, None).map(Some)
+        }
+    }
     pub fn keystore_handler(&self) -> KeyStoreHandler {
         KeyStoreHandler::new(
             self.default_keystore_handler.clone(),
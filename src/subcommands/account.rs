@@ -1,24 +1,34 @@
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::time::Duration;
 
 use bitcoin::util::bip32::DerivationPath;
+use chrono::prelude::*;
 
 use ckb_sdk::{Address, AddressPayload, NetworkType};
-use ckb_signer::{Key, KeyStore, MasterPrivKey};
+use ckb_signer::{Key, KeyStore, MasterPrivKey, Mnemonic, ScryptType};
 use ckb_types::{packed::Script, prelude::*, H160, H256};
-use clap::{App, Arg, ArgMatches};
+use clap::{App, AppSettings, Arg, ArgMatches};
 use faster_hex::hex_string;
 
 use super::{CliSubCommand, Output};
 use crate::plugin::PluginManager;
 use crate::utils::{
+    account_meta::{check_label, created_at_from_filename, AccountMeta, AccountMetaStore},
     arg::lock_arg,
     arg_parser::{
-        ArgParser, ExtendedPrivkeyPathParser, FilePathParser, FixedHashParser, FromStrParser,
-        HexParser, PrivkeyPathParser, PrivkeyWrapper,
+        AccountParser, ArgParser, DurationParser, ExtendedPrivkeyPathParser, FilePathParser,
+        FixedHashParser, FromStrParser, HexParser, PrivkeyPathParser, PrivkeyWrapper,
     },
-    other::{address_json, read_password},
+    keystore_backup::BackupBundle,
+    other::{address_json, prompt_password, read_password},
+};
+#[cfg(unix)]
+use crate::utils::{
+    other::{ckb_cli_dir, get_key_store},
+    unlock_agent::{socket_path, AgentClient, UnlockAgent},
 };
 
 pub struct AccountSubCommand<'a> {
@@ -50,6 +60,20 @@ impl<'a> AccountSubCommand<'a> {
             .takes_value(true)
             .validator(|input| FromStrParser::<DerivationPath>::new().validate(input))
             .about("The derivation key path");
+        let arg_idle_timeout = Arg::with_name("idle-timeout")
+            .long("idle-timeout")
+            .takes_value(true)
+            .default_value("30m")
+            .validator(|input| DurationParser.validate(input))
+            .about(
+                "Stop the started unlock agent when there is no request in this time, format: 30s, 15m, 1h",
+            );
+        let arg_keep = Arg::with_name("keep")
+            .long("keep")
+            .takes_value(true)
+            .default_value("15m")
+            .validator(|input| DurationParser.validate(input))
+            .about("Lock the unlocked account after this time, format: 30s, 15m, 1h");
         App::new(name)
             .about("Manage accounts")
             .subcommands(vec![
@@ -64,6 +88,12 @@ impl<'a> AccountSubCommand<'a> {
                             .long("only-testnet-address")
                             .about("Only show CKB testnet address")
                     )
+                    .arg(
+                        Arg::with_name("tag")
+                            .long("tag")
+                            .takes_value(true)
+                            .about("Only show accounts with this tag")
+                    )
                     .about("List all accounts")
                     .long_about("List all accounts. There are two kinds of account item indicated by `source` field:
 
@@ -72,13 +102,28 @@ impl<'a> AccountSubCommand<'a> {
     * lock_hash: The lock script hash of secp256k1_blake160_sighash_all lock (See [1]).
     * has_ckb_pubkey_derivation_root_path: The CKB public key derivation root path (m/44'/309'/0') is stored so that password is not required to do public key derivation.
     * address: The Mainnet/Testnet addresses of secp256k1_blake160_sighash_all lock (See [1]).
+    * label, note, tags, created_at: The account metadata (See `ckb-cli account update`).
 
   When `source` is \"[plugin]: xxx_keysotre_plugin\" means the account is stored in keystore plugin (Ledger plugin like [2]). If the account metadata is imported by `ckb-cli account import-from-plugin` the output fields are just like \"Local File System\". If the account is not imported, the output fields are:
     * account-id: The account id used to import the account metadata from plugin.
 
 [1]: https://github.com/nervosnetwork/ckb-system-scripts/blob/master/c/secp256k1_blake160_sighash_all.c
 [2]: https://github.com/obsidiansystems/ckb-plugin-ledger"),
-                App::new("new").about("Create a new account and print related information."),
+                App::new("new")
+                    .about("Create a new account and print related information.")
+                    .arg(
+                        Arg::with_name("mnemonic")
+                            .long("mnemonic")
+                            .about("Create the account from a new BIP-39 mnemonic, the words are only shown once (compatible with Neuron wallet)")
+                    )
+                    .arg(
+                        Arg::with_name("words")
+                            .long("words")
+                            .takes_value(true)
+                            .possible_values(&["12", "15", "18", "21", "24"])
+                            .requires("mnemonic")
+                            .about("The number of mnemonic words [default: 12]")
+                    ),
                 App::new("import")
                     .about("Import an unencrypted private key from <privkey-path> and create a new account.")
                     .arg(
@@ -93,6 +138,13 @@ impl<'a> AccountSubCommand<'a> {
                          .required_unless("privkey-path")
                          .validator(|input| ExtendedPrivkeyPathParser.validate(input))
                     ),
+                App::new("import-mnemonic")
+                    .about("Import an account from a BIP-39 mnemonic (English words, the derived addresses are the same as Neuron wallet). The mnemonic is read from the terminal.")
+                    .arg(
+                        Arg::with_name("passphrase")
+                            .long("passphrase")
+                            .about("Ask for the optional BIP-39 passphrase (the \"25th word\")")
+                    ),
                 App::new("import-from-plugin")
                     .about("Import an account from keystore plugin")
                     .arg(
@@ -121,8 +173,29 @@ impl<'a> AccountSubCommand<'a> {
                             .about("The keystore file path (json format)")
                     ),
                 App::new("update")
-                    .about("Update password of an account")
-                    .arg(lock_arg().required(true)),
+                    .about("Update password of an account, or update the label/note/tags of an account when any of them is given")
+                    .arg(lock_arg().required(true))
+                    .arg(
+                        Arg::with_name("label")
+                            .long("label")
+                            .takes_value(true)
+                            .validator(|input| if input.is_empty() { Ok(()) } else { check_label(input) })
+                            .about("A unique label of the account, can be used in place of lock-arg in <from-account> (empty value to remove)")
+                    )
+                    .arg(
+                        Arg::with_name("note")
+                            .long("note")
+                            .takes_value(true)
+                            .about("A free text note of the account (empty value to remove)")
+                    )
+                    .arg(
+                        Arg::with_name("tag")
+                            .long("tag")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .about("Tags of the account, replace the old tags (empty value to remove all tags)")
+                    ),
                 App::new("upgrade")
                     .about("Upgrade an account to latest json format")
                     .arg(lock_arg().required(true)),
@@ -189,10 +262,245 @@ impl<'a> AccountSubCommand<'a> {
                 App::new("remove")
                     .about("Print information about how to remove an account")
                     .arg(lock_arg().required(true)),
+                App::new("backup")
+                    .about("Backup all accounts of the local keystore into one file encrypted by a separate backup password (the accounts are still encrypted by their own passwords)")
+                    .arg(
+                        Arg::with_name("output")
+                            .long("output")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| FilePathParser::new(false).validate(input))
+                            .about("The output backup file path (for example: bundle.ckbbak)")
+                    ),
+                App::new("restore")
+                    .about("Restore accounts from a backup file created by `account backup`")
+                    .arg(
+                        Arg::with_name("input")
+                            .long("input")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| FilePathParser::new(true).validate(input))
+                            .about("The backup file path")
+                    )
+                    .arg(
+                        Arg::with_name("on-conflict")
+                            .long("on-conflict")
+                            .takes_value(true)
+                            .default_value("skip")
+                            .possible_values(&["skip", "overwrite", "abort"])
+                            .about("What to do when an account in the backup already exists in the keystore")
+                    )
+                    .arg(
+                        Arg::with_name("dry-run")
+                            .long("dry-run")
+                            .about("Only list the accounts in the backup and the actions to take")
+                    ),
+                App::new("unlock")
+                    .about("Unlock the account in the unlock agent (started if not running), later ckb-cli invocations sign with it without asking for the password (unix only)")
+                    .arg(
+                        Arg::with_name("account")
+                            .long("account")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| AccountParser::new(None).validate(input))
+                            .about("The account to unlock (lock arg, sighash address or label)")
+                    )
+                    .arg(arg_keep)
+                    .arg(arg_idle_timeout.clone()),
+                App::new("lock")
+                    .about("Lock the account unlocked by `account unlock`, lock all accounts and stop the unlock agent if no account is given")
+                    .arg(
+                        Arg::with_name("account")
+                            .long("account")
+                            .takes_value(true)
+                            .validator(|input| AccountParser::new(None).validate(input))
+                            .about("The account to lock (lock arg, sighash address or label)")
+                    ),
+                App::new("sessions")
+                    .about("Show the unlock agent and the accounts unlocked by `account unlock`, when they are locked"),
+                App::new("agent")
+                    .setting(AppSettings::Hidden)
+                    .about("The unlock agent, a background process holding unlocked accounts for signing in later ckb-cli invocations (unix only)")
+                    .subcommand(
+                        App::new("serve")
+                            .about("Run the unlock agent in the foreground (started by `account unlock`)")
+                            .arg(arg_idle_timeout),
+                    ),
             ])
     }
 }
 
+impl<'a> AccountSubCommand<'a> {
+    fn import_master_privkey(
+        &mut self,
+        master_privkey: MasterPrivKey,
+        password: String,
+    ) -> Result<Output, String> {
+        // The first receiving address of Neuron wallet, for checking the mnemonic is correct
+        let key_set = master_privkey
+            .ckb_root()
+            .derived_key_set_by_index(0, 1, 0, 0);
+        let first_receiving_payload =
+            AddressPayload::from_pubkey_hash(key_set.external[0].1.clone());
+        let lock_arg = self
+            .plugin_mgr
+            .keystore_handler()
+            .import_key(master_privkey, Some(password))?;
+        self.record_created_at(&lock_arg);
+        let address_payload = AddressPayload::from_pubkey_hash(lock_arg.clone());
+        let resp = serde_json::json!({
+            "lock_arg": format!("{:#x}", lock_arg),
+            "address": address_json(address_payload.clone(), true),
+            "address(deprecated)": address_json(address_payload, false),
+            "first_receiving_address": address_json(first_receiving_payload, true),
+        });
+        Ok(Output::new_output(resp))
+    }
+
+    /// Record the creation time of a new account, the account is already created so only warn
+    /// on failure.
+    fn record_created_at(&mut self, lock_arg: &H160) {
+        let keys_dir = self.key_store.keys_dir().to_path_buf();
+        let result = AccountMetaStore::load(&keys_dir).and_then(|mut store| {
+            let mut meta = store.get(lock_arg).cloned().unwrap_or_default();
+            if meta.created_at.is_none() {
+                meta.created_at = Some(Utc::now().to_rfc3339());
+                store.set(lock_arg, meta)?;
+                store.save(&keys_dir)?;
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            eprintln!("WARNING: record account creation time failed: {}", err);
+        }
+    }
+
+    #[cfg(unix)]
+    fn process_agent(&mut self, matches: &ArgMatches) -> Result<Output, String> {
+        let ckb_cli_dir = ckb_cli_dir();
+        match matches.subcommand() {
+            ("serve", Some(m)) => {
+                let idle_timeout: Duration = DurationParser.from_matches(m, "idle-timeout")?;
+                let key_store = get_key_store(ckb_cli_dir.clone())?;
+                UnlockAgent::new(key_store, idle_timeout).serve(&socket_path(&ckb_cli_dir))?;
+                Ok(Output::new_success())
+            }
+            _ => Err(Self::subcommand("account").generate_usage()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn process_agent(&mut self, _matches: &ArgMatches) -> Result<Output, String> {
+        Err("The unlock agent is only supported on unix".to_string())
+    }
+
+    /// The sessions are kept by the unlock agent, it is started by the first `account unlock`
+    #[cfg(unix)]
+    fn process_session(&mut self, matches: &ArgMatches) -> Result<Output, String> {
+        let ckb_cli_dir = ckb_cli_dir();
+        let client = AgentClient::new(&ckb_cli_dir);
+        match matches.subcommand() {
+            ("unlock", Some(m)) => {
+                let account: H160 = AccountParser::new(None).from_matches(m, "account")?;
+                let keep: Duration = DurationParser.from_matches(m, "keep")?;
+                let idle_timeout: Duration = DurationParser.from_matches(m, "idle-timeout")?;
+                if !self.key_store.has_account(&account, true) {
+                    return Err(format!("Account not found in keystore: {:#x}", account));
+                }
+                let client = if client.is_running() {
+                    let status = client.status()?;
+                    if status.idle_timeout_secs < keep.as_secs() {
+                        eprintln!(
+                            "WARNING: the running unlock agent stops after {}s without requests",
+                            status.idle_timeout_secs
+                        );
+                    }
+                    client
+                } else {
+                    AgentClient::start(&ckb_cli_dir, idle_timeout)?
+                };
+                let password = read_password(false, None)?;
+                client.unlock(account.clone(), password, keep)?;
+                let status = client.status()?;
+                let session = status
+                    .accounts
+                    .iter()
+                    .find(|item| item.account == account)
+                    .ok_or_else(|| format!("Unlock account {:#x} failed", account))?;
+                Ok(Output::new_output(serde_json::json!({
+                    "lock_arg": format!("{:#x}", account),
+                    "lock_after": format!("{}s", session.lock_after_secs),
+                })))
+            }
+            ("lock", Some(m)) => {
+                let account_opt: Option<H160> =
+                    AccountParser::new(None).from_matches_opt(m, "account")?;
+                // Already locked if the agent is not running
+                if client.is_running() {
+                    match account_opt {
+                        Some(account) => client.lock(account)?,
+                        None => client.stop()?,
+                    }
+                }
+                Ok(Output::new_success())
+            }
+            ("sessions", _) => {
+                if !client.is_running() {
+                    return Ok(Output::new_output(serde_json::json!({
+                        "agent_running": false,
+                        "sessions": [],
+                    })));
+                }
+                let status = client.status()?;
+                let meta_store = AccountMetaStore::load(self.key_store.keys_dir())?;
+                let sessions = status
+                    .accounts
+                    .iter()
+                    .map(|item| {
+                        let address_payload =
+                            AddressPayload::from_pubkey_hash(item.account.clone());
+                        serde_json::json!({
+                            "lock_arg": format!("{:#x}", item.account),
+                            "address": address_json(address_payload, true),
+                            "label": meta_store.get(&item.account).and_then(|meta| meta.label.clone()),
+                            "lock_after": format!("{}s", item.lock_after_secs),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(Output::new_output(serde_json::json!({
+                    "agent_running": true,
+                    "agent_pid": status.pid,
+                    "agent_idle_timeout": format!("{}s", status.idle_timeout_secs),
+                    "sessions": sessions,
+                })))
+            }
+            _ => Err(Self::subcommand("account").generate_usage()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn process_session(&mut self, _matches: &ArgMatches) -> Result<Output, String> {
+        Err("Unlock sessions are only supported on unix".to_string())
+    }
+
+    fn account_meta_json(&self, store: &AccountMetaStore, lock_arg: &H160) -> serde_json::Value {
+        let meta = store.get(lock_arg).cloned().unwrap_or_default();
+        let created_at = meta.created_at.or_else(|| {
+            self.key_store.get_filepath(lock_arg).ok().and_then(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(created_at_from_filename)
+            })
+        });
+        serde_json::json!({
+            "label": meta.label,
+            "note": meta.note,
+            "tags": meta.tags,
+            "created_at": created_at,
+        })
+    }
+}
+
 impl<'a> CliSubCommand for AccountSubCommand<'a> {
     fn process(&mut self, matches: &ArgMatches, _debug: bool) -> Result<Output, String> {
         match matches.subcommand() {
@@ -203,12 +511,25 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                 let only_mainnet_address = m.is_present("only-mainnet-address");
                 let only_testnet_address = m.is_present("only-testnet-address");
                 let partial_fields = only_mainnet_address || only_testnet_address;
+                let tag_opt = m.value_of("tag");
                 self.key_store
                     .refresh_dir()
                     .map_err(|err| err.to_string())?;
+                let meta_store = AccountMetaStore::load(self.key_store.keys_dir())?;
                 let resp = accounts
                     .into_iter()
                     .enumerate()
+                    .filter(|(_, (data, _))| {
+                        tag_opt
+                            .map(|tag| {
+                                data.len() == 20
+                                    && meta_store
+                                        .get(&H160::from_slice(data.as_ref()).expect("H160"))
+                                        .map(|meta| meta.tags.iter().any(|t| t == tag))
+                                        .unwrap_or(false)
+                            })
+                            .unwrap_or(true)
+                    })
                     .map(|(idx, (data, source))| {
                         if data.len() == 20 {
                             let lock_arg = H160::from_slice(data.as_ref()).expect("H160");
@@ -231,7 +552,7 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                                 }
                             } else {
                                 let has_ckb_root = self.key_store.get_ckb_root(&lock_arg, false).is_some();
-                                serde_json::json!({
+                                let mut value = serde_json::json!({
                                     "#": idx,
                                     "source": source,
                                     "lock_arg": format!("{:#x}", lock_arg),
@@ -239,7 +560,11 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                                     "has_ckb_pubkey_derivation_root_path": has_ckb_root,
                                     "address": address_json(address_payload.clone(), true),
                                     "address(deprecated)": address_json(address_payload, false),
-                                })
+                                });
+                                if let serde_json::Value::Object(meta) = self.account_meta_json(&meta_store, &lock_arg) {
+                                    value.as_object_mut().expect("json object").extend(meta);
+                                }
+                                value
                             }
                         } else {
                             serde_json::json!({
@@ -252,6 +577,18 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                     .collect::<Vec<_>>();
                 Ok(Output::new_output(resp))
             }
+            ("new", Some(m)) if m.is_present("mnemonic") => {
+                let word_count: usize = FromStrParser::<usize>::default()
+                    .from_matches_opt(m, "words")?
+                    .unwrap_or(12);
+                let mnemonic = Mnemonic::generate(word_count).map_err(|err| err.to_string())?;
+                show_mnemonic(&mnemonic)?;
+                eprintln!("Your new account is locked with a password. Please give a password. Do not forget this password.");
+                let password = read_password(true, None)?;
+                let master_privkey =
+                    MasterPrivKey::from_mnemonic(&mnemonic, "").map_err(|err| err.to_string())?;
+                self.import_master_privkey(master_privkey, password)
+            }
             ("new", _) => {
                 eprintln!("Your new account is locked with a password. Please give a password. Do not forget this password.");
                 let password = read_password(true, None)?;
@@ -259,6 +596,7 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                     .plugin_mgr
                     .keystore_handler()
                     .create_account(password)?;
+                self.record_created_at(&lock_arg);
                 let address_payload = AddressPayload::from_pubkey_hash(lock_arg.clone());
                 let lock_hash: H256 = Script::from(&address_payload).calc_script_hash().unpack();
                 let resp = serde_json::json!({
@@ -288,6 +626,7 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                     .plugin_mgr
                     .keystore_handler()
                     .import_key(master_privkey, password)?;
+                self.record_created_at(&lock_arg);
                 let address_payload = AddressPayload::from_pubkey_hash(lock_arg.clone());
                 let resp = serde_json::json!({
                     "lock_arg": format!("{:#x}", lock_arg),
@@ -296,6 +635,19 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                 });
                 Ok(Output::new_output(resp))
             }
+            ("import-mnemonic", Some(m)) => {
+                let phrase = prompt_password(false, Some("Mnemonic"))?;
+                let mnemonic = Mnemonic::from_phrase(&phrase).map_err(|err| err.to_string())?;
+                let passphrase = if m.is_present("passphrase") {
+                    prompt_password(true, Some("BIP-39 passphrase"))?
+                } else {
+                    String::new()
+                };
+                let master_privkey = MasterPrivKey::from_mnemonic(&mnemonic, &passphrase)
+                    .map_err(|err| err.to_string())?;
+                let password = read_password(true, None)?;
+                self.import_master_privkey(master_privkey, password)
+            }
             ("import-from-plugin", Some(m)) => {
                 let account_id: Vec<u8> = HexParser.from_matches(m, "account-id")?;
                 let password = if self.plugin_mgr.keystore_require_password() {
@@ -307,6 +659,7 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                     .plugin_mgr
                     .keystore_handler()
                     .import_account(account_id.into(), password)?;
+                self.record_created_at(&lock_arg);
                 let address_payload = AddressPayload::from_pubkey_hash(lock_arg.clone());
                 let resp = serde_json::json!({
                     "lock_arg": format!("{:#x}", lock_arg),
@@ -331,6 +684,7 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                     .plugin_mgr
                     .keystore_handler()
                     .import_key(master_privkey, new_password)?;
+                self.record_created_at(&lock_arg);
                 let address_payload = AddressPayload::from_pubkey_hash(lock_arg.clone());
                 let resp = serde_json::json!({
                     "lock_arg": format!("{:x}", lock_arg),
@@ -339,6 +693,48 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                 });
                 Ok(Output::new_output(resp))
             }
+            ("update", Some(m))
+                if ["label", "note", "tag"]
+                    .iter()
+                    .any(|name| m.is_present(name)) =>
+            {
+                let lock_arg: H160 =
+                    FixedHashParser::<H160>::default().from_matches(m, "lock-arg")?;
+                self.key_store
+                    .refresh_dir()
+                    .map_err(|err| err.to_string())?;
+                if !self.key_store.has_account(&lock_arg, false) {
+                    return Err(format!("Account not found: {:#x}", lock_arg));
+                }
+                let keys_dir = self.key_store.keys_dir().to_path_buf();
+                let mut store = AccountMetaStore::load(&keys_dir)?;
+                let mut meta = store.get(&lock_arg).cloned().unwrap_or_default();
+                let non_empty = |value: &str| {
+                    if value.is_empty() {
+                        None
+                    } else {
+                        Some(value.to_string())
+                    }
+                };
+                if let Some(label) = m.value_of("label") {
+                    meta.label = non_empty(label);
+                }
+                if let Some(note) = m.value_of("note") {
+                    meta.note = non_empty(note);
+                }
+                if let Some(tags) = m.values_of("tag") {
+                    meta.tags = tags.filter_map(non_empty).collect();
+                    meta.tags.sort();
+                    meta.tags.dedup();
+                }
+                store.set(&lock_arg, meta)?;
+                store.save(&keys_dir)?;
+                let mut resp = serde_json::json!({ "lock_arg": format!("{:#x}", lock_arg) });
+                if let serde_json::Value::Object(meta) = self.account_meta_json(&store, &lock_arg) {
+                    resp.as_object_mut().expect("json object").extend(meta);
+                }
+                Ok(Output::new_output(resp))
+            }
             ("update", Some(m)) => {
                 let lock_arg: H160 =
                     FixedHashParser::<H160>::default().from_matches(m, "lock-arg")?;
@@ -458,11 +854,7 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                     .from_matches_opt(m, "path")?
                     .unwrap_or(root_key_path);
 
-                let password = if self.plugin_mgr.keystore_require_password() {
-                    Some(read_password(false, None)?)
-                } else {
-                    None
-                };
+                let password = self.plugin_mgr.keystore_password(&lock_arg)?;
                 let extended_pubkey = self
                     .plugin_mgr
                     .keystore_handler()
@@ -488,7 +880,157 @@ impl<'a> CliSubCommand for AccountSubCommand<'a> {
                 });
                 Ok(Output::new_output(resp))
             }
+            ("backup", Some(m)) => {
+                let output: PathBuf = FilePathParser::new(false).from_matches(m, "output")?;
+                if output.exists() {
+                    return Err(format!("File exists: {}", output.display()));
+                }
+                let meta_store = AccountMetaStore::load(self.key_store.keys_dir())?;
+                let bundle = BackupBundle::from_key_store(self.key_store, &meta_store)?;
+                if bundle.accounts.is_empty() {
+                    return Err("No account in the local keystore".to_string());
+                }
+                let password = read_password(true, Some("Backup password"))?;
+                let content = bundle.encrypt(password.as_bytes(), ScryptType::default())?;
+                fs::write(&output, content).map_err(|err| err.to_string())?;
+                let resp = serde_json::json!({
+                    "output": output.to_string_lossy(),
+                    "accounts": bundle
+                        .accounts
+                        .iter()
+                        .map(|account| format!("{:#x}", account.lock_arg))
+                        .collect::<Vec<_>>(),
+                });
+                Ok(Output::new_output(resp))
+            }
+            ("restore", Some(m)) => {
+                let input: PathBuf = FilePathParser::new(true).from_matches(m, "input")?;
+                let on_conflict = m.value_of("on-conflict").expect("on-conflict argument");
+                let dry_run = m.is_present("dry-run");
+                let content = fs::read_to_string(&input).map_err(|err| err.to_string())?;
+                let password = read_password(false, Some("Backup password"))?;
+                let bundle = BackupBundle::decrypt(&content, password.as_bytes())?;
+
+                self.key_store
+                    .refresh_dir()
+                    .map_err(|err| err.to_string())?;
+                // Decide all actions first, so nothing is written when aborted
+                let mut actions = Vec::with_capacity(bundle.accounts.len());
+                for account in &bundle.accounts {
+                    let action = if !self.key_store.has_account(&account.lock_arg, false) {
+                        "restore"
+                    } else {
+                        match on_conflict {
+                            "skip" => "skip",
+                            "overwrite" => "overwrite",
+                            _ => {
+                                return Err(format!(
+                                    "Account {:#x} already exists, use `--on-conflict skip|overwrite` to continue",
+                                    account.lock_arg
+                                ))
+                            }
+                        }
+                    };
+                    actions.push(action);
+                }
+                if !dry_run {
+                    for (account, action) in bundle.accounts.iter().zip(actions.iter()) {
+                        let path = match *action {
+                            "restore" => self.key_store.keys_dir().join(&account.filename),
+                            "overwrite" => self
+                                .key_store
+                                .get_filepath(&account.lock_arg)
+                                .map_err(|err| err.to_string())?,
+                            _ => continue,
+                        };
+                        let content = serde_json::to_string(&account.keystore)
+                            .map_err(|err| err.to_string())?;
+                        fs::write(&path, content).map_err(|err| err.to_string())?;
+                    }
+                    self.key_store
+                        .refresh_dir()
+                        .map_err(|err| err.to_string())?;
+
+                    let keys_dir = self.key_store.keys_dir().to_path_buf();
+                    let mut store = AccountMetaStore::load(&keys_dir)?;
+                    for (account, action) in bundle.accounts.iter().zip(actions.iter()) {
+                        if let (Some(meta), "restore" | "overwrite") =
+                            (account.meta.as_ref(), *action)
+                        {
+                            if let Err(err) = store.set(&account.lock_arg, meta.clone()) {
+                                eprintln!(
+                                    "WARNING: label of account {:#x} is not restored: {}",
+                                    account.lock_arg, err
+                                );
+                                let meta = AccountMeta {
+                                    label: None,
+                                    ..meta.clone()
+                                };
+                                store.set(&account.lock_arg, meta)?;
+                            }
+                        }
+                    }
+                    store.save(&keys_dir)?;
+                }
+                let resp = bundle
+                    .accounts
+                    .iter()
+                    .zip(actions)
+                    .map(|(account, action)| {
+                        let address_payload =
+                            AddressPayload::from_pubkey_hash(account.lock_arg.clone());
+                        serde_json::json!({
+                            "lock_arg": format!("{:#x}", account.lock_arg),
+                            "address": address_json(address_payload, true),
+                            "has_ckb_pubkey_derivation_root_path": account.has_ckb_root(),
+                            "action": action,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(Output::new_output(resp))
+            }
+            ("unlock", Some(_)) | ("lock", Some(_)) | ("sessions", Some(_)) => {
+                self.process_session(matches)
+            }
+            ("agent", Some(m)) => self.process_agent(m),
             _ => Err(Self::subcommand("account").generate_usage()),
         }
     }
 }
+
+#[cfg(unix)]
+const MNEMONIC_QUIZ_COUNT: usize = 3;
+
+/// Show the mnemonic once, then ask for some random words to make sure it's written down
+fn show_mnemonic(mnemonic: &Mnemonic) -> Result<(), String> {
+    eprintln!(
+        "Write down the mnemonic words in order and keep them safe, they are only shown once:\n"
+    );
+    for (idx, word) in mnemonic.words().iter().enumerate() {
+        eprintln!("{:>4}. {}", idx + 1, word);
+    }
+    prompt_password(
+        false,
+        Some("\nPress Enter after you have written down the words"),
+    )?;
+    // Clear the screen so the words are not left in the terminal
+    eprint!("\x1b[2J\x1b[H");
+
+    let mut positions = rand::seq::index::sample(
+        &mut rand::thread_rng(),
+        mnemonic.words().len(),
+        MNEMONIC_QUIZ_COUNT,
+    )
+    .into_vec();
+    positions.sort_unstable();
+    for position in positions {
+        let answer = prompt_password(false, Some(&format!("Word #{}", position + 1)))?;
+        if answer.trim().to_lowercase() != mnemonic.words()[position] {
+            return Err(format!(
+                "Word #{} is wrong, the account is not created",
+                position + 1
+            ));
+        }
+    }
+    Ok(())
+}
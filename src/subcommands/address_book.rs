@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use ckb_sdk::{Address, NetworkType};
+use clap::{App, Arg, ArgMatches};
+
+use super::{CliSubCommand, Output};
+use crate::utils::{
+    address_book::{check_alias, split_csv_line, AddressBook, AddressEntry},
+    arg_parser::{AddressParser, ArgParser, FilePathParser},
+    config::parse_network_name,
+};
+
+pub struct AddressBookSubCommand {
+    ckb_cli_dir: PathBuf,
+    network: Option<NetworkType>,
+}
+
+impl AddressBookSubCommand {
+    pub fn new(ckb_cli_dir: PathBuf, network: Option<NetworkType>) -> AddressBookSubCommand {
+        AddressBookSubCommand {
+            ckb_cli_dir,
+            network,
+        }
+    }
+
+    pub fn subcommand(name: &'static str) -> App<'static> {
+        let arg_alias = Arg::with_name("alias")
+            .long("alias")
+            .required(true)
+            .takes_value(true)
+            .validator(check_alias)
+            .about("The alias, use it as `@<alias>` in place of an address");
+        let arg_network = Arg::with_name("network")
+            .long("network")
+            .takes_value(true)
+            .possible_values(&["mainnet", "testnet", "staging", "dev"]);
+        let arg_force = Arg::with_name("force")
+            .long("force")
+            .about("Replace the alias if it already exists");
+        App::new(name)
+            .about("Manage the address book, the aliases can be used as `@<alias>` anywhere an address is expected (like `--to-address @alice`)")
+            .subcommands(vec![
+                App::new("add")
+                    .about("Add an address, use `--force` to replace an existing alias")
+                    .arg(arg_alias.clone())
+                    .arg(
+                        Arg::with_name("address")
+                            .long("address")
+                            .required(true)
+                            .takes_value(true)
+                            .validator(|input| AddressParser::default().validate(input))
+                            .about("The address (saved in the full format)"),
+                    )
+                    .arg(arg_network.clone().about(
+                        "The network of the address (default is the current network if the address prefix matches, otherwise mainnet or testnet by the prefix)",
+                    ))
+                    .arg(
+                        Arg::with_name("note")
+                            .long("note")
+                            .takes_value(true)
+                            .about("A note of the address"),
+                    )
+                    .arg(arg_force.clone()),
+                App::new("list")
+                    .about("List the addresses")
+                    .arg(arg_network.about("Only list the addresses of the network")),
+                App::new("remove")
+                    .about("Remove an address")
+                    .arg(arg_alias),
+                App::new("import-csv")
+                    .about("Import addresses from a csv file, the columns are: alias,address[,network[,note]] (the header line is optional)")
+                    .arg(
+                        Arg::with_name("path")
+                            .long("path")
+                            .required(true)
+                            .takes_value(true)
+                            .validator(|input| FilePathParser::new(true).validate(input))
+                            .about("The csv file path"),
+                    )
+                    .arg(arg_force),
+            ])
+    }
+
+    /// The current network is preferred, since the address prefix of staging and dev is the same
+    /// as testnet
+    fn entry_network(
+        &self,
+        address: &Address,
+        network: Option<&str>,
+    ) -> Result<NetworkType, String> {
+        match network {
+            Some(name) => parse_network_name(name),
+            None => Ok(self
+                .network
+                .filter(|network| network.to_prefix() == address.network().to_prefix())
+                .unwrap_or_else(|| address.network())),
+        }
+    }
+
+    fn entry_json(alias: &str, entry: &AddressEntry) -> serde_json::Value {
+        serde_json::json!({
+            "alias": alias,
+            "address": entry.address,
+            "network": entry.network,
+            "note": entry.note,
+        })
+    }
+}
+
+impl CliSubCommand for AddressBookSubCommand {
+    fn process(&mut self, matches: &ArgMatches, _debug: bool) -> Result<Output, String> {
+        match matches.subcommand() {
+            ("add", Some(m)) => {
+                let alias = m.value_of("alias").unwrap().to_string();
+                let address: Address = AddressParser::default().from_matches(m, "address")?;
+                let network = self.entry_network(&address, m.value_of("network"))?;
+                let note = m.value_of("note").map(ToString::to_string);
+                let entry = AddressEntry::new(&address, network, note)?;
+                let mut book = AddressBook::load(&self.ckb_cli_dir)?;
+                book.add(alias.clone(), entry.clone(), m.is_present("force"))?;
+                book.save(&self.ckb_cli_dir)?;
+                Ok(Output::new_output(Self::entry_json(&alias, &entry)))
+            }
+            ("list", Some(m)) => {
+                let network = m.value_of("network");
+                let resp = AddressBook::load(&self.ckb_cli_dir)?
+                    .entries
+                    .iter()
+                    .filter(|(_, entry)| network.map_or(true, |name| entry.network == name))
+                    .map(|(alias, entry)| Self::entry_json(alias, entry))
+                    .collect::<Vec<_>>();
+                Ok(Output::new_output(resp))
+            }
+            ("remove", Some(m)) => {
+                let alias = m.value_of("alias").unwrap();
+                let mut book = AddressBook::load(&self.ckb_cli_dir)?;
+                let entry = book.remove(alias)?;
+                book.save(&self.ckb_cli_dir)?;
+                Ok(Output::new_output(Self::entry_json(alias, &entry)))
+            }
+            ("import-csv", Some(m)) => {
+                let path: PathBuf = FilePathParser::new(true).from_matches(m, "path")?;
+                let force = m.is_present("force");
+                let content = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+                let mut book = AddressBook::load(&self.ckb_cli_dir)?;
+                let mut aliases = HashSet::new();
+                let mut resp = Vec::new();
+                // All lines are checked before saving, nothing is imported if any line is invalid
+                for (idx, line) in content.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let fields = split_csv_line(line)?;
+                    if idx == 0 && fields[0] == "alias" {
+                        continue;
+                    }
+                    let entry_result = || {
+                        if fields.len() < 2 || fields.len() > 4 {
+                            return Err(format!("expected 2 to 4 columns, found {}", fields.len()));
+                        }
+                        let address = AddressParser::default().parse(&fields[1])?;
+                        let network = fields.get(2).map(String::as_str).filter(|s| !s.is_empty());
+                        let network = self.entry_network(&address, network)?;
+                        let note = fields.get(3).filter(|s| !s.is_empty()).cloned();
+                        AddressEntry::new(&address, network, note)
+                    };
+                    let alias = fields[0].clone();
+                    let entry = entry_result()
+                        .and_then(|entry| {
+                            if !aliases.insert(alias.clone()) {
+                                return Err(format!("duplicated alias @{}", alias));
+                            }
+                            book.add(alias.clone(), entry.clone(), force)?;
+                            Ok(entry)
+                        })
+                        .map_err(|err| format!("Invalid line {}: {}", idx + 1, err))?;
+                    resp.push(Self::entry_json(&alias, &entry));
+                }
+                book.save(&self.ckb_cli_dir)?;
+                Ok(Output::new_output(resp))
+            }
+            _ => Err(Self::subcommand("addressbook").generate_usage()),
+        }
+    }
+}
@@ -94,9 +94,7 @@ impl<'a> CliSubCommand for ApiServerSubCommand<'a> {
 
         let mut io_handler = IoHandler::new();
         let handler = ApiRpcImpl {
-            rpc_client: Arc::new(Mutex::new(HttpRpcClient::new(
-                self.rpc_client.url().to_string(),
-            ))),
+            rpc_client: Arc::new(Mutex::new(self.rpc_client.clone())),
             plugin_mgr: Arc::new(Mutex::new(self.plugin_mgr.take().unwrap())),
             genesis_info: Arc::new(Mutex::new(self.genesis_info.clone())),
             privkey_path,
@@ -392,6 +390,7 @@ impl HttpTransferArgs {
         TransferArgs {
             privkey_path: Some(privkey_path),
             from_account: None,
+            external_signer: None,
             from_locked_address: self.from_locked_address,
             password: None,
             derive_receiving_address_length: None,
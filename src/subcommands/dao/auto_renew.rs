@@ -0,0 +1,388 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ckb_jsonrpc_types as json_types;
+use ckb_sdk::{traits::CellCollector, HumanCapacity};
+use ckb_types::{
+    core::{EpochNumberWithFraction, HeaderView, TransactionView},
+    packed::{OutPoint, Script},
+    prelude::*,
+    H160, H256,
+};
+use plugin_protocol::LiveCellInfo;
+use serde_derive::{Deserialize, Serialize};
+
+use super::command::TransactArgs;
+use super::util::{epoch_json, epoch_reached, load_prepared_cell_info, minimal_withdraw_epoch};
+use super::DAOSubCommand;
+use crate::utils::other::{check_lack_of_capacity, is_unlocked_in_agent, read_password};
+
+/// Options of `dao auto-renew`
+pub struct AutoRenewOptions {
+    /// Prepare deposits when the end of current lock period is within this many epochs
+    pub window: u64,
+    pub state_file: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenewActionKind {
+    Prepare,
+    Withdraw,
+    Deposit,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenewActionStatus {
+    /// Sent to the node but not committed yet
+    Pending,
+    Committed,
+    /// Rejected or disappeared from the node, the cells will be picked up again
+    Dropped,
+    /// Sending to the node failed, the cells will be picked up again
+    Failed,
+}
+
+/// A transaction sent by `dao auto-renew`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RenewAction {
+    pub kind: RenewActionKind,
+    pub tx_hash: H256,
+    /// Input out points of the transaction (for deposit action this is empty)
+    pub out_points: Vec<json_types::OutPoint>,
+    /// Prepare: deposited capacity, withdraw: withdrawn capacity, deposit: deposited capacity
+    pub capacity: u64,
+    /// The tip epoch number when the action is taken
+    pub epoch: u64,
+    pub status: RenewActionStatus,
+    /// The withdraw transaction renewed by this deposit action
+    #[serde(default)]
+    pub source_tx_hash: Option<H256>,
+}
+
+/// The decisions made by `dao auto-renew`, stored to the state file after every action so that
+/// a restarted process will not send the same transaction twice.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AutoRenewState {
+    pub actions: Vec<RenewAction>,
+}
+
+impl AutoRenewState {
+    pub fn load(path: &Path) -> Result<AutoRenewState, String> {
+        if !path.exists() {
+            return Ok(AutoRenewState::default());
+        }
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        serde_json::from_str(&content)
+            .map_err(|err| format!("Invalid state file {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        // Write to a temporary file first, so that the state file is never half written
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, content).map_err(|err| err.to_string())?;
+        fs::rename(&tmp_path, path).map_err(|err| err.to_string())
+    }
+
+    /// Out points consumed by pending transactions
+    fn locked_out_points(&self) -> HashSet<OutPoint> {
+        self.actions
+            .iter()
+            .filter(|action| action.status == RenewActionStatus::Pending)
+            .flat_map(|action| action.out_points.iter().cloned().map(OutPoint::from))
+            .collect()
+    }
+
+    /// Committed withdraw actions not renewed by a pending or committed deposit action
+    fn withdraws_to_renew(&self) -> Vec<(H256, u64)> {
+        let renewed: HashSet<&H256> = self
+            .actions
+            .iter()
+            .filter(|action| {
+                action.kind == RenewActionKind::Deposit
+                    && matches!(
+                        action.status,
+                        RenewActionStatus::Pending | RenewActionStatus::Committed
+                    )
+            })
+            .filter_map(|action| action.source_tx_hash.as_ref())
+            .collect();
+        self.actions
+            .iter()
+            .filter(|action| {
+                action.kind == RenewActionKind::Withdraw
+                    && action.status == RenewActionStatus::Committed
+                    && !renewed.contains(&action.tx_hash)
+            })
+            .map(|action| (action.tx_hash.clone(), action.capacity))
+            .collect()
+    }
+}
+
+/// The keystore password kept in memory for a limited time (timed unlock)
+pub struct TimedPassword {
+    password: Option<(String, Instant)>,
+    keep: Duration,
+}
+
+impl TimedPassword {
+    pub fn new(keep: Duration) -> TimedPassword {
+        TimedPassword {
+            password: None,
+            keep,
+        }
+    }
+
+    pub fn get(&mut self) -> Result<String, String> {
+        let now = Instant::now();
+        match self.password.as_ref() {
+            Some((password, expire_at)) if *expire_at > now => Ok(password.clone()),
+            _ => {
+                let password = read_password(false, None)?;
+                self.password = Some((password.clone(), now + self.keep));
+                Ok(password)
+            }
+        }
+    }
+}
+
+impl<'a> DAOSubCommand<'a> {
+    /// Run one auto-renew cycle:
+    ///
+    ///   1. update the status of pending transactions in the state file
+    ///   2. re-deposit the capacity of committed withdraw transactions
+    ///   3. withdraw prepared cells which are withdrawable
+    ///   4. prepare deposited cells whose lock period ends within `window` epochs
+    pub fn auto_renew_cycle(
+        &mut self,
+        args: &mut TransactArgs,
+        options: &AutoRenewOptions,
+        timed_password: &mut TimedPassword,
+    ) -> Result<serde_json::Value, String> {
+        // Cells locked by a failed transaction of the last cycle are available again
+        self.cell_collector.reset();
+        let mut state = AutoRenewState::load(&options.state_file)?;
+        self.refresh_renew_state(&mut state)?;
+        state.save(&options.state_file)?;
+
+        let lock_script: Script = args.address.payload().into();
+        let tip_header: HeaderView = self.rpc_client.get_tip_header()?.into();
+        let tip_epoch = tip_header.epoch();
+        let locked_out_points = state.locked_out_points();
+        let mut sent = Vec::new();
+
+        for (withdraw_tx_hash, capacity) in state.withdraws_to_renew() {
+            self.unlock_args(args, timed_password)?;
+            let tx = self.deposit(args, capacity)?;
+            let action = RenewAction {
+                kind: RenewActionKind::Deposit,
+                tx_hash: tx.hash().unpack(),
+                out_points: Vec::new(),
+                capacity,
+                epoch: tip_epoch.number(),
+                status: RenewActionStatus::Pending,
+                source_tx_hash: Some(withdraw_tx_hash),
+            };
+            sent.push(self.send_renew_tx(&mut state, &options.state_file, &tx, action)?);
+        }
+
+        let mut withdrawable = Vec::new();
+        for cell in self.query_prepare_cells(lock_script.clone())? {
+            let out_point = live_cell_out_point(&cell);
+            if locked_out_points.contains(&out_point) {
+                continue;
+            }
+            let info = load_prepared_cell_info(self.rpc_client, &cell)?;
+            let withdraw_epoch =
+                minimal_withdraw_epoch(info.deposit_header.epoch(), info.prepare_header.epoch());
+            if epoch_reached(tip_epoch, withdraw_epoch) {
+                withdrawable.push(out_point);
+            }
+        }
+        if !withdrawable.is_empty() {
+            self.unlock_args(args, timed_password)?;
+            let tx = self.withdraw(args, withdrawable.clone())?;
+            let capacity = tx
+                .outputs()
+                .into_iter()
+                .filter(|output| output.lock() == lock_script)
+                .map(|output| -> u64 { output.capacity().unpack() })
+                .sum();
+            let action = RenewAction {
+                kind: RenewActionKind::Withdraw,
+                tx_hash: tx.hash().unpack(),
+                out_points: withdrawable.into_iter().map(Into::into).collect(),
+                capacity,
+                epoch: tip_epoch.number(),
+                status: RenewActionStatus::Pending,
+                source_tx_hash: None,
+            };
+            sent.push(self.send_renew_tx(&mut state, &options.state_file, &tx, action)?);
+        }
+
+        let mut to_prepare = Vec::new();
+        let mut prepare_capacity = 0;
+        for cell in self.query_deposit_cells(lock_script)? {
+            let out_point = live_cell_out_point(&cell);
+            if locked_out_points.contains(&out_point) {
+                continue;
+            }
+            let deposit_header = self.get_block_header(cell.number)?;
+            if in_renew_window(deposit_header.epoch(), tip_epoch, options.window) {
+                to_prepare.push(out_point);
+                prepare_capacity += cell.capacity;
+            }
+        }
+        if !to_prepare.is_empty() {
+            self.unlock_args(args, timed_password)?;
+            let tx = self.prepare(args, to_prepare.clone())?;
+            let action = RenewAction {
+                kind: RenewActionKind::Prepare,
+                tx_hash: tx.hash().unpack(),
+                out_points: to_prepare.into_iter().map(Into::into).collect(),
+                capacity: prepare_capacity,
+                epoch: tip_epoch.number(),
+                status: RenewActionStatus::Pending,
+                source_tx_hash: None,
+            };
+            sent.push(self.send_renew_tx(&mut state, &options.state_file, &tx, action)?);
+        }
+
+        let pending = state
+            .actions
+            .iter()
+            .filter(|action| action.status == RenewActionStatus::Pending)
+            .count();
+        Ok(serde_json::json!({
+            "tip_epoch": epoch_json(tip_epoch),
+            "sent": sent.iter().map(|action| serde_json::json!({
+                "kind": action.kind,
+                "tx_hash": action.tx_hash,
+                "cells": action.out_points.len(),
+                "capacity": format!("{:#}", HumanCapacity::from(action.capacity)),
+            })).collect::<Vec<_>>(),
+            "pending": pending,
+        }))
+    }
+
+    fn refresh_renew_state(&mut self, state: &mut AutoRenewState) -> Result<(), String> {
+        for action in state
+            .actions
+            .iter_mut()
+            .filter(|action| action.status == RenewActionStatus::Pending)
+        {
+            let status_opt = self
+                .rpc_client
+                .get_transaction(action.tx_hash.clone())?
+                .map(|tx_with_status| tx_with_status.tx_status.status);
+            action.status = match status_opt {
+                Some(json_types::Status::Committed) => RenewActionStatus::Committed,
+                Some(json_types::Status::Pending) | Some(json_types::Status::Proposed) => {
+                    RenewActionStatus::Pending
+                }
+                _ => RenewActionStatus::Dropped,
+            };
+        }
+        Ok(())
+    }
+
+    fn unlock_args(
+        &mut self,
+        args: &mut TransactArgs,
+        timed_password: &mut TimedPassword,
+    ) -> Result<(), String> {
+        if args.privkey.is_none()
+            && args.external_signer.is_none()
+            && self.plugin_mgr.keystore_require_password()
+        {
+            // The unlock agent may expire during the long running renew, check it every time
+            let account =
+                H160::from_slice(args.address.payload().args().as_ref()).expect("lock args");
+            if !is_unlocked_in_agent(&account) {
+                args.password = Some(timed_password.get()?);
+            }
+        }
+        Ok(())
+    }
+
+    /// The action is saved as pending before sending, so that a restarted process will not
+    /// send the same transaction twice if it's killed right after sending.
+    fn send_renew_tx(
+        &mut self,
+        state: &mut AutoRenewState,
+        state_file: &Path,
+        tx: &TransactionView,
+        action: RenewAction,
+    ) -> Result<RenewAction, String> {
+        check_lack_of_capacity(tx)?;
+        state.actions.push(action);
+        state.save(state_file)?;
+        if let Err(err) = self.rpc_client.send_transaction(tx.data(), None) {
+            if let Some(action) = state.actions.last_mut() {
+                action.status = RenewActionStatus::Failed;
+            }
+            state.save(state_file)?;
+            return Err(err);
+        }
+        Ok(state.actions.last().cloned().expect("action pushed"))
+    }
+}
+
+/// Whether the current lock period of a deposit ends within `window` epochs from `tip_epoch`
+pub(crate) fn in_renew_window(
+    deposit_epoch: EpochNumberWithFraction,
+    tip_epoch: EpochNumberWithFraction,
+    window: u64,
+) -> bool {
+    let period_end = minimal_withdraw_epoch(deposit_epoch, tip_epoch);
+    let window_start = EpochNumberWithFraction::new(
+        period_end.number().saturating_sub(window),
+        period_end.index(),
+        period_end.length(),
+    );
+    epoch_reached(tip_epoch, window_start)
+}
+
+fn live_cell_out_point(cell: &LiveCellInfo) -> OutPoint {
+    OutPoint::new(cell.tx_hash.pack(), cell.output_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_renew_window() {
+        let deposit_epoch = EpochNumberWithFraction::new(10, 5, 1000);
+        assert!(!in_renew_window(
+            deposit_epoch,
+            EpochNumberWithFraction::new(100, 0, 1000),
+            12
+        ));
+        assert!(!in_renew_window(
+            deposit_epoch,
+            EpochNumberWithFraction::new(178, 4, 1000),
+            12
+        ));
+        assert!(in_renew_window(
+            deposit_epoch,
+            EpochNumberWithFraction::new(178, 5, 1000),
+            12
+        ));
+        assert!(in_renew_window(
+            deposit_epoch,
+            EpochNumberWithFraction::new(190, 5, 1000),
+            12
+        ));
+        // Just missed the first period, wait for the second one
+        assert!(!in_renew_window(
+            deposit_epoch,
+            EpochNumberWithFraction::new(190, 6, 1000),
+            12
+        ));
+    }
+}
@@ -1,46 +1,70 @@
+use crate::subcommands::dao::auto_renew::{AutoRenewOptions, TimedPassword};
 use crate::subcommands::dao::util::{calculate_dao_maximum_withdraw, send_transaction};
-use crate::subcommands::{CliSubCommand, DAOSubCommand, Output};
+use crate::subcommands::{tx::ReprTxHelper, CliSubCommand, DAOSubCommand, Output};
 use crate::utils::{
     arg,
     arg_parser::{
-        AddressParser, ArgParser, CapacityParser, FixedHashParser, FromStrParser, OutPointParser,
-        PrivkeyPathParser, PrivkeyWrapper,
+        AccountParser, AddressParser, ArgParser, CapacityParser, DurationParser, FilePathParser,
+        FromStrParser, OutPointParser, PrivkeyPathParser, PrivkeyWrapper,
     },
-    other::{get_address, get_network_type},
+    other::{get_address, get_fee_rate, get_network_type},
+    printer::{OutputFormat, Printable},
+    rpc::HttpRpcClient,
+    tx_helper::TxHelper,
 };
 use ckb_crypto::secp::SECP256K1;
-use ckb_sdk::{Address, AddressPayload, HumanCapacity, NetworkType};
-use ckb_types::{packed::Script, H160};
+use ckb_sdk::{unlock::MultisigConfig, Address, AddressPayload, HumanCapacity, NetworkType};
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{Script, WitnessArgs},
+    prelude::*,
+    H160,
+};
 use clap::{App, Arg, ArgMatches};
 use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
 impl<'a> CliSubCommand for DAOSubCommand<'a> {
     fn process(&mut self, matches: &ArgMatches, debug: bool) -> Result<Output, String> {
         let network_type = get_network_type(self.rpc_client)?;
         match matches.subcommand() {
             ("deposit", Some(m)) => {
-                let args = TransactArgs::from_matches(m, network_type)?;
+                let args = TransactArgs::from_matches(m, network_type, self.rpc_client)?;
                 let capacity: u64 = CapacityParser.from_matches(m, "capacity")?;
                 let transaction = self.deposit(&args, capacity)?;
-                send_transaction(self.rpc_client, transaction, debug)
+                self.finish_tx(&args, transaction, network_type, debug)
             }
             ("prepare", Some(m)) => {
-                let args = TransactArgs::from_matches(m, network_type)?;
+                let args = TransactArgs::from_matches(m, network_type, self.rpc_client)?;
                 let out_points = OutPointParser.from_matches_vec(m, "out-point")?;
                 if out_points.len() != out_points.iter().collect::<HashSet<_>>().len() {
                     return Err("Duplicated out-points".to_string());
                 }
                 let transaction = self.prepare(&args, out_points)?;
-                send_transaction(self.rpc_client, transaction, debug)
+                self.finish_tx(&args, transaction, network_type, debug)
             }
             ("withdraw", Some(m)) => {
-                let args = TransactArgs::from_matches(m, network_type)?;
-                let out_points = OutPointParser.from_matches_vec(m, "out-point")?;
+                let args = TransactArgs::from_matches(m, network_type, self.rpc_client)?;
+                let amount_opt: Option<u64> = CapacityParser.from_matches_opt(m, "amount")?;
+                let out_points = if let Some(amount) = amount_opt {
+                    let plan = self.plan_withdraw(args.address.payload().into(), amount)?;
+                    eprintln!(
+                        "[Withdraw Plan]:\n{}",
+                        plan.summary.render(OutputFormat::Yaml, false)
+                    );
+                    plan.out_points
+                } else {
+                    OutPointParser.from_matches_vec(m, "out-point")?
+                };
                 if out_points.len() != out_points.iter().collect::<HashSet<_>>().len() {
                     return Err("Duplicated out-points".to_string());
                 }
                 let transaction = self.withdraw(&args, out_points)?;
-                send_transaction(self.rpc_client, transaction, debug)
+                self.finish_tx(&args, transaction, network_type, debug)
             }
             ("query-deposited-cells", Some(m)) => {
                 let address_payload = get_address(Some(network_type), m)?;
@@ -73,12 +97,103 @@ impl<'a> CliSubCommand for DAOSubCommand<'a> {
                 });
                 Ok(Output::new_output(resp))
             }
+            ("report", Some(m)) => {
+                let address_payload = get_address(Some(network_type), m)?;
+                let mut lock_scripts = vec![Script::from(&address_payload)];
+                if m.is_present("derived") {
+                    let receiving_address_length: u32 = FromStrParser::<u32>::default()
+                        .from_matches(m, "derive-receiving-address-length")?;
+                    let change_address_length: u32 = FromStrParser::<u32>::default()
+                        .from_matches(m, "derive-change-address-length")?;
+                    let lock_arg = H160::from_slice(address_payload.args().as_ref())
+                        .map_err(|err| err.to_string())?;
+                    let key_set = self
+                        .plugin_mgr
+                        .keystore_handler()
+                        .derived_key_set_by_index(
+                            lock_arg,
+                            0,
+                            receiving_address_length,
+                            0,
+                            change_address_length,
+                            None,
+                        )?;
+                    for (_, hash160) in key_set.external.iter().chain(key_set.change.iter()) {
+                        let payload = AddressPayload::from_pubkey_hash(hash160.clone());
+                        lock_scripts.push(Script::from(&payload));
+                    }
+                }
+                let resp = self.report(lock_scripts, network_type)?;
+                Ok(Output::new_output(resp))
+            }
+            ("auto-renew", Some(m)) => {
+                let address_payload = get_address(Some(network_type), m)?;
+                let privkey: Option<PrivkeyWrapper> =
+                    PrivkeyPathParser.from_matches_opt(m, "privkey-path")?;
+                if let Some(privkey) = privkey.as_ref() {
+                    let pubkey = secp256k1::PublicKey::from_secret_key(&SECP256K1, privkey);
+                    if AddressPayload::from_pubkey(&pubkey) != address_payload {
+                        return Err("<privkey-path> does not match <address>".to_string());
+                    }
+                }
+                let external_signer = m.value_of("external-signer").map(|s| s.to_string());
+                let mut args = TransactArgs {
+                    privkey,
+                    external_signer,
+                    address: Address::new(network_type, address_payload, false),
+                    fee_rate: get_fee_rate(m, self.rpc_client)?,
+                    force_small_change_as_fee: FromStrParser::<HumanCapacity>::default()
+                        .from_matches_opt(m, "max-tx-fee")?,
+                    password: None,
+                    multisig_config: None,
+                    tx_file: None,
+                };
+                let options = AutoRenewOptions {
+                    window: FromStrParser::<u64>::default().from_matches(m, "window")?,
+                    state_file: FilePathParser::new(false).from_matches(m, "state-file")?,
+                };
+                let unlock_keep: Duration = DurationParser.from_matches(m, "unlock-keep")?;
+                let mut timed_password = TimedPassword::new(unlock_keep);
+                if !m.is_present("daemon") {
+                    let resp = self.auto_renew_cycle(&mut args, &options, &mut timed_password)?;
+                    return Ok(Output::new_output(resp));
+                }
+                let interval: Duration = DurationParser.from_matches(m, "interval")?;
+                loop {
+                    match self.auto_renew_cycle(&mut args, &options, &mut timed_password) {
+                        Ok(resp) => println!("{}", resp),
+                        Err(err) => eprintln!("auto-renew cycle failed: {}", err),
+                    }
+                    thread::sleep(interval);
+                }
+            }
             _ => Err(Self::subcommand().generate_usage()),
         }
     }
 }
 
 impl<'a> DAOSubCommand<'a> {
+    // Send the signed transaction, or save the multisig transaction to <tx-file>
+    fn finish_tx(
+        &mut self,
+        args: &TransactArgs,
+        transaction: TransactionView,
+        network_type: NetworkType,
+        debug: bool,
+    ) -> Result<Output, String> {
+        match (args.multisig_config.as_ref(), args.tx_file.as_ref()) {
+            (Some(cfg), Some(tx_file)) => {
+                let mut helper = TxHelper::new(transaction);
+                helper.add_multisig_config(cfg.clone());
+                let repr = ReprTxHelper::new(helper, network_type);
+                let content = serde_json::to_string_pretty(&repr).map_err(|err| err.to_string())?;
+                fs::write(tx_file, content).map_err(|err| err.to_string())?;
+                Ok(Output::new_success())
+            }
+            _ => send_transaction(self.rpc_client, transaction, debug),
+        }
+    }
+
     pub fn subcommand() -> App<'static> {
         App::new("dao")
             .about("Deposit / prepare / withdraw / query NervosDAO balance (with local index) / key utils")
@@ -94,72 +209,220 @@ impl<'a> DAOSubCommand<'a> {
                 App::new("withdraw")
                     .about("Withdraw specified cells from NervosDAO")
                     .args(&TransactArgs::args())
-                    .arg(arg::out_point().required(true).multiple(true)),
+                    .arg(
+                        arg::out_point()
+                            .required_unless("amount")
+                            .conflicts_with("amount")
+                            .multiple(true),
+                    )
+                    .arg(
+                        Arg::with_name("amount")
+                            .long("amount")
+                            .takes_value(true)
+                            .validator(|input| CapacityParser.validate(input))
+                            .about("Withdraw at least this amount (unit: CKB, transaction fee included), the withdrawable prepared cells are selected automatically")
+                    ),
                 App::new("query-deposited-cells")
                     .about("Query NervosDAO deposited capacity by address")
                     .arg(arg::address()),
                 App::new("query-prepared-cells")
                     .about("Query NervosDAO prepared capacity by address")
+                    .arg(arg::address()),
+                App::new("report")
+                    .about("Report NervosDAO positions by address: accrued compensation, next withdraw window and earliest withdraw epoch of prepared cells")
                     .arg(arg::address())
+                    .arg(arg::lock_arg())
+                    .arg(arg::derived())
+                    .arg(arg::derive_receiving_address_length())
+                    .arg(arg::derive_change_address_length()),
+                App::new("auto-renew")
+                    .about("Automatically renew NervosDAO deposits: prepare deposits near the end of their lock period, withdraw matured prepared cells and deposit them again")
+                    .arg(arg::address().required(true))
+                    .arg(arg::privkey_path())
+                    .arg(arg::external_signer().conflicts_with(arg::privkey_path().get_name()))
+                    .arg(arg::fee_rate())
+                    .arg(arg::max_tx_fee())
+                    .arg(
+                        Arg::with_name("window")
+                            .long("window")
+                            .takes_value(true)
+                            .default_value("12")
+                            .validator(|input| FromStrParser::<u64>::default().validate(input))
+                            .about("Prepare deposits whose lock period ends within this many epochs")
+                    )
+                    .arg(
+                        Arg::with_name("state-file")
+                            .long("state-file")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| FilePathParser::new(false).validate(input))
+                            .about("The file to record sent transactions, so that a restarted process will not send them again")
+                    )
+                    .arg(
+                        Arg::with_name("daemon")
+                            .long("daemon")
+                            .about("Keep running and check the deposits every <interval>")
+                    )
+                    .arg(
+                        Arg::with_name("interval")
+                            .long("interval")
+                            .takes_value(true)
+                            .default_value("10m")
+                            .validator(|input| DurationParser.validate(input))
+                            .about("Check interval in daemon mode")
+                    )
+                    .arg(
+                        Arg::with_name("unlock-keep")
+                            .long("unlock-keep")
+                            .takes_value(true)
+                            .default_value("24h")
+                            .validator(|input| DurationParser.validate(input))
+                            .about("How long the keystore password is kept in memory before asking again")
+                    ),
             ])
     }
 }
 
 pub struct TransactArgs {
     pub(crate) privkey: Option<PrivkeyWrapper>,
+    /// The external signer command, signs for the <from-account> instead of the keystore
+    pub(crate) external_signer: Option<String>,
     pub(crate) address: Address,
     pub(crate) fee_rate: u64,
     pub(crate) force_small_change_as_fee: Option<u64>,
+    /// Keystore password already read by the caller
+    pub(crate) password: Option<String>,
+    /// The multisig config of <address>, the transaction is saved to <tx-file> without signing
+    pub(crate) multisig_config: Option<MultisigConfig>,
+    pub(crate) tx_file: Option<PathBuf>,
 }
 
 impl TransactArgs {
-    fn from_matches(m: &ArgMatches, network_type: NetworkType) -> Result<Self, String> {
+    fn from_matches(
+        m: &ArgMatches,
+        network_type: NetworkType,
+        rpc_client: &HttpRpcClient,
+    ) -> Result<Self, String> {
         let privkey: Option<PrivkeyWrapper> =
             PrivkeyPathParser.from_matches_opt(m, "privkey-path")?;
-        let address = if let Some(privkey) = privkey.as_ref() {
+        let multisig_address_opt: Option<Address> = AddressParser::new_multisig()
+            .set_network(network_type)
+            .from_matches_opt(m, "multisig-address")?;
+        let mut multisig_config = None;
+        let mut tx_file = None;
+        let address = if let Some(multisig_address) = multisig_address_opt {
+            let sighash_addresses: Vec<Address> = AddressParser::new_sighash()
+                .set_network(network_type)
+                .from_matches_vec(m, "sighash-address")?;
+            let require_first_n: u8 =
+                FromStrParser::<u8>::default().from_matches(m, "require-first-n")?;
+            let threshold: u8 = FromStrParser::<u8>::default().from_matches(m, "threshold")?;
+            let sighash_addresses = sighash_addresses
+                .into_iter()
+                .map(|address| H160::from_slice(address.payload().args().as_ref()).unwrap())
+                .collect::<Vec<_>>();
+            let cfg = MultisigConfig::new_with(sighash_addresses, require_first_n, threshold)
+                .map_err(|err| err.to_string())?;
+            if multisig_address.payload().args().as_ref() != cfg.hash160().as_bytes() {
+                return Err(
+                    "<multisig-address> is not built from the given multisig config (multisig address with since is not supported)"
+                        .to_string(),
+                );
+            }
+            multisig_config = Some(cfg);
+            tx_file = Some(FilePathParser::new(false).from_matches(m, "tx-file")?);
+            multisig_address
+        } else if let Some(privkey) = privkey.as_ref() {
             let pubkey = secp256k1::PublicKey::from_secret_key(&SECP256K1, privkey);
             let payload = AddressPayload::from_pubkey(&pubkey);
             Address::new(network_type, payload, false)
         } else {
-            let account: H160 = FixedHashParser::<H160>::default()
+            let account: H160 = AccountParser::new(Some(network_type))
                 .from_matches_opt(m, "from-account")
-                .or_else(|err| {
-                    let result: Result<Option<Address>, String> = AddressParser::new_sighash()
-                        .set_network(network_type)
-                        .from_matches_opt(m, "from-account");
-                    result
-                        .map(|address_opt| {
-                            address_opt
-                                .map(|address| H160::from_slice(&address.payload().args()).unwrap())
-                        })
-                        .map_err(|_| format!("Invalid value for '--from-account': {}", err))
-                })?
+                .map_err(|err| format!("Invalid value for '--from-account': {}", err))?
                 .ok_or_else(|| {
                     // It's a bug of clap, otherwise if <privkey-path> is not given <from-account> must required.
                     // The bug only happen when put <fee-rate> before <out-point>.
-                    String::from("<privkey-path> or <from-account> is required!")
+                    String::from(
+                        "<privkey-path>, <from-account> or <multisig-address> is required!",
+                    )
                 })?;
             let payload = AddressPayload::from_pubkey_hash(account);
             Address::new(network_type, payload, false)
         };
-        let fee_rate: u64 = FromStrParser::<u64>::default().from_matches(m, "fee-rate")?;
+        let fee_rate: u64 = get_fee_rate(m, rpc_client)?;
 
         let force_small_change_as_fee =
             FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
+        let external_signer = m.value_of("external-signer").map(|s| s.to_string());
         Ok(Self {
             privkey,
+            external_signer,
             address,
             fee_rate,
             force_small_change_as_fee,
+            password: None,
+            multisig_config,
+            tx_file,
         })
     }
 
+    pub(crate) fn placeholder_witness(&self) -> WitnessArgs {
+        if let Some(cfg) = self.multisig_config.as_ref() {
+            cfg.placeholder_witness()
+        } else {
+            WitnessArgs::new_builder()
+                .lock(Some(Bytes::from(vec![0u8; 65])).pack())
+                .build()
+        }
+    }
+
     fn args<'a>() -> Vec<Arg<'a>> {
         vec![
-            arg::privkey_path().required_unless(arg::from_account().get_name()),
-            arg::from_account().required_unless(arg::privkey_path().get_name()),
+            arg::privkey_path()
+                .required_unless_one(&[arg::from_account().get_name(), "multisig-address"]),
+            arg::from_account()
+                .required_unless_one(&[arg::privkey_path().get_name(), "multisig-address"]),
+            arg::external_signer()
+                .requires(arg::from_account().get_name())
+                .conflicts_with(arg::privkey_path().get_name()),
             arg::fee_rate(),
             arg::max_tx_fee(),
+            Arg::with_name("multisig-address")
+                .long("multisig-address")
+                .takes_value(true)
+                .conflicts_with_all(&[
+                    arg::privkey_path().get_name(),
+                    arg::from_account().get_name(),
+                ])
+                .requires_all(&["sighash-address", "tx-file"])
+                .validator(|input| AddressParser::new_multisig().validate(input))
+                .about("Build the transaction from this multisig address and save it to <tx-file>, then sign it by `tx sign-inputs` and send it by `tx send`"),
+            Arg::with_name("sighash-address")
+                .long("sighash-address")
+                .takes_value(true)
+                .multiple(true)
+                .requires("multisig-address")
+                .validator(|input| AddressParser::new_sighash().validate(input))
+                .about("Sighash addresses of the multisig config"),
+            Arg::with_name("require-first-n")
+                .long("require-first-n")
+                .takes_value(true)
+                .default_value("0")
+                .validator(|input| FromStrParser::<u8>::default().validate(input))
+                .about("Require first n signatures of corresponding pubkey"),
+            Arg::with_name("threshold")
+                .long("threshold")
+                .takes_value(true)
+                .default_value("1")
+                .validator(|input| FromStrParser::<u8>::default().validate(input))
+                .about("Multisig threshold"),
+            Arg::with_name("tx-file")
+                .long("tx-file")
+                .takes_value(true)
+                .requires("multisig-address")
+                .validator(|input| FilePathParser::new(false).validate(input))
+                .about("Multisig transaction data file (format: json)"),
         ]
     }
 }
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use byteorder::{ByteOrder, LittleEndian};
 
 use ckb_sdk::{
-    constants::{DAO_TYPE_HASH, SIGHASH_TYPE_HASH},
+    constants::{DAO_TYPE_HASH, MULTISIG_TYPE_HASH, SIGHASH_TYPE_HASH},
     traits::{
         default_impls::{
             DefaultCellCollector, DefaultCellDepResolver, DefaultHeaderDepResolver,
@@ -19,12 +19,15 @@ use ckb_sdk::{
         CapacityBalancer, CapacityProvider, TxBuilder,
     },
     types::ScriptId,
-    unlock::{ScriptUnlocker, SecpSighashScriptSigner, SecpSighashUnlocker},
+    unlock::{
+        ScriptUnlocker, SecpMultisigScriptSigner, SecpMultisigUnlocker, SecpSighashScriptSigner,
+        SecpSighashUnlocker,
+    },
 };
 use ckb_types::{
     bytes::Bytes,
     core::{FeeRate, ScriptHashType, TransactionView},
-    packed::{CellInput, OutPoint, Script, WitnessArgs},
+    packed::{CellInput, OutPoint, Script},
     prelude::*,
     H160,
 };
@@ -35,14 +38,17 @@ use crate::{
     plugin::PluginManager,
     utils::{
         genesis_info::GenesisInfo,
-        other::{map_tx_builder_error_2_str, read_password, to_live_cell_info},
+        other::{map_tx_builder_error_2_str, to_live_cell_info},
         rpc::HttpRpcClient,
-        signer::KeyStoreHandlerSigner,
+        signer::{DummySigner, ExternalSigner, KeyStoreHandlerSigner},
     },
 };
 
+mod auto_renew;
 mod command;
-mod util;
+mod report;
+pub(crate) mod util;
+mod withdraw_plan;
 
 // Should CLI handle "immature header problem"?
 pub struct DAOSubCommand<'a> {
@@ -61,7 +67,7 @@ impl<'a> DAOSubCommand<'a> {
         genesis_info: GenesisInfo,
     ) -> Self {
         let tx_dep_provider = DefaultTransactionDependencyProvider::new(rpc_client.url(), 10);
-        let cell_collector = DefaultCellCollector::new(rpc_client.url());
+        let cell_collector = DefaultCellCollector::new(rpc_client.indexer_url());
         let cell_dep_resolver = genesis_info.cell_dep_resolver;
         let header_dep_resolver = DefaultHeaderDepResolver::new(rpc_client.url());
         Self {
@@ -85,15 +91,53 @@ impl<'a> DAOSubCommand<'a> {
             change_lock_script: None,
             capacity_provider: CapacityProvider::new_simple(vec![(
                 lock_script.clone(),
-                WitnessArgs::new_builder()
-                    .lock(Some(Bytes::from(vec![0u8; 65])).pack())
-                    .build(),
+                args.placeholder_witness(),
             )]),
             force_small_change_as_fee: args.force_small_change_as_fee,
         };
 
+        // The multisig transaction is only balanced here, it will be signed by `tx sign-inputs`
+        if let Some(cfg) = args.multisig_config.as_ref() {
+            let signer = DummySigner {
+                args: cfg
+                    .sighash_addresses()
+                    .iter()
+                    .map(|hash160| Bytes::from(hash160.as_bytes().to_vec()))
+                    .collect(),
+            };
+            let multisig_unlocker = SecpMultisigUnlocker::new(SecpMultisigScriptSigner::new(
+                Box::new(signer),
+                cfg.clone(),
+            ));
+            let mut unlockers: HashMap<_, Box<dyn ScriptUnlocker>> = HashMap::new();
+            unlockers.insert(
+                ScriptId::new_type(MULTISIG_TYPE_HASH.clone()),
+                Box::new(multisig_unlocker),
+            );
+            return builder
+                .build_balanced(
+                    &mut self.cell_collector,
+                    &self.cell_dep_resolver,
+                    &self.header_dep_resolver,
+                    &self.tx_dep_provider,
+                    &balancer,
+                    &unlockers,
+                )
+                .map_err(|err| {
+                    map_tx_builder_error_2_str(balancer.force_small_change_as_fee.is_none(), err)
+                });
+        }
+
         let signer: Box<dyn Signer> = if let Some(privkey) = args.privkey.as_ref() {
             Box::new(privkey.clone())
+        } else if let Some(command) = args.external_signer.as_ref() {
+            let account =
+                H160::from_slice(lock_script.args().raw_data().as_ref()).expect("lock args");
+            Box::new(ExternalSigner::for_account(
+                command.clone(),
+                self.rpc_client.url(),
+                account,
+            ))
         } else {
             let account =
                 H160::from_slice(lock_script.args().raw_data().as_ref()).expect("lock args");
@@ -106,8 +150,14 @@ impl<'a> DAOSubCommand<'a> {
                     0,
                 )),
             );
-            if self.plugin_mgr.keystore_require_password() {
-                signer.set_password(account.clone(), read_password(false, None)?);
+            let password = match args.password.as_ref() {
+                Some(password) if self.plugin_mgr.keystore_require_password() => {
+                    Some(password.clone())
+                }
+                _ => self.plugin_mgr.keystore_password(&account)?,
+            };
+            if let Some(password) = password {
+                signer.set_password(account.clone(), password);
             }
             signer.set_change_path(account, change_path.to_string());
             Box::new(signer)
@@ -171,11 +221,7 @@ impl<'a> DAOSubCommand<'a> {
             .into_iter()
             .map(|out_point| DaoWithdrawItem::new(out_point, None))
             .collect::<Vec<_>>();
-        items[0].init_witness = Some(
-            WitnessArgs::new_builder()
-                .lock(Some(Bytes::from(vec![0u8; 65])).pack())
-                .build(),
-        );
+        items[0].init_witness = Some(args.placeholder_witness());
         let receiver = DaoWithdrawReceiver::LockScript {
             script: lock_script,
             fee_rate: Some(FeeRate::from_u64(args.fee_rate)),
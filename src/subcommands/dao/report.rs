@@ -0,0 +1,123 @@
+use ckb_sdk::{
+    util::calculate_dao_maximum_withdraw4, Address, AddressPayload, HumanCapacity, NetworkType,
+};
+use ckb_types::{
+    core::{Capacity, HeaderView},
+    packed::{OutPoint, Script},
+    prelude::*,
+};
+use plugin_protocol::LiveCellInfo;
+
+use super::util::{epoch_json, epoch_reached, load_prepared_cell_info, minimal_withdraw_epoch};
+use super::DAOSubCommand;
+use crate::utils::other::get_live_cell;
+
+impl<'a> DAOSubCommand<'a> {
+    /// Report NervosDAO positions of all given lock scripts
+    pub fn report(
+        &mut self,
+        lock_scripts: Vec<Script>,
+        network: NetworkType,
+    ) -> Result<serde_json::Value, String> {
+        let tip_header: HeaderView = self.rpc_client.get_tip_header()?.into();
+        let tip_epoch = tip_header.epoch();
+
+        let mut deposits = Vec::new();
+        let mut prepared = Vec::new();
+        let mut total_deposited = 0;
+        let mut total_deposit_compensation = 0;
+        let mut total_prepared = 0;
+        let mut total_prepared_compensation = 0;
+        let mut total_withdrawable = 0;
+        for lock_script in lock_scripts {
+            let address = Address::new(network, AddressPayload::from(lock_script.clone()), true);
+            for cell in self.query_deposit_cells(lock_script.clone())? {
+                let deposit_header = self.get_block_header(cell.number)?;
+                let maximum_withdraw =
+                    self.deposit_maximum_withdraw(&cell, &deposit_header, &tip_header)?;
+                let compensation = maximum_withdraw.saturating_sub(cell.capacity);
+                let next_window = minimal_withdraw_epoch(deposit_header.epoch(), tip_epoch);
+                total_deposited += cell.capacity;
+                total_deposit_compensation += compensation;
+                deposits.push(serde_json::json!({
+                    "address": address.to_string(),
+                    "out_point": out_point_string(&cell),
+                    "capacity": format!("{:#}", HumanCapacity::from(cell.capacity)),
+                    "deposit_block_number": cell.number,
+                    "deposit_epoch": epoch_json(deposit_header.epoch()),
+                    "compensation": format!("{:#}", HumanCapacity::from(compensation)),
+                    "next_withdraw_window_epoch": epoch_json(next_window),
+                    "epochs_to_next_window": next_window.number().saturating_sub(tip_epoch.number()),
+                }));
+            }
+            for cell in self.query_prepare_cells(lock_script)? {
+                let info = load_prepared_cell_info(self.rpc_client, &cell)?;
+                let compensation = info.maximum_withdraw.saturating_sub(cell.capacity);
+                let withdraw_epoch = minimal_withdraw_epoch(
+                    info.deposit_header.epoch(),
+                    info.prepare_header.epoch(),
+                );
+                let withdrawable = epoch_reached(tip_epoch, withdraw_epoch);
+                total_prepared += cell.capacity;
+                total_prepared_compensation += compensation;
+                if withdrawable {
+                    total_withdrawable += info.maximum_withdraw;
+                }
+                prepared.push(serde_json::json!({
+                    "address": address.to_string(),
+                    "out_point": out_point_string(&cell),
+                    "capacity": format!("{:#}", HumanCapacity::from(cell.capacity)),
+                    "deposit_block_number": info.deposit_header.number(),
+                    "deposit_epoch": epoch_json(info.deposit_header.epoch()),
+                    "prepare_block_number": info.prepare_header.number(),
+                    "prepare_epoch": epoch_json(info.prepare_header.epoch()),
+                    "compensation": format!("{:#}", HumanCapacity::from(compensation)),
+                    "maximum_withdraw": format!("{:#}", HumanCapacity::from(info.maximum_withdraw)),
+                    "earliest_withdraw_epoch": epoch_json(withdraw_epoch),
+                    "withdrawable": withdrawable,
+                }));
+            }
+        }
+        Ok(serde_json::json!({
+            "tip_epoch": epoch_json(tip_epoch),
+            "deposits": deposits,
+            "prepared": prepared,
+            "total_deposited": format!("{:#}", HumanCapacity::from(total_deposited)),
+            "total_deposit_compensation": format!("{:#}", HumanCapacity::from(total_deposit_compensation)),
+            "total_prepared": format!("{:#}", HumanCapacity::from(total_prepared)),
+            "total_prepared_compensation": format!("{:#}", HumanCapacity::from(total_prepared_compensation)),
+            "total_withdrawable": format!("{:#}", HumanCapacity::from(total_withdrawable)),
+        }))
+    }
+
+    pub(super) fn get_block_header(&mut self, number: u64) -> Result<HeaderView, String> {
+        self.rpc_client
+            .get_header_by_number(number)?
+            .map(Into::into)
+            .ok_or_else(|| format!("Can not get header of block {}", number))
+    }
+
+    // Calculate the maximum withdraw as if the deposit cell is prepared at current tip
+    fn deposit_maximum_withdraw(
+        &mut self,
+        deposit_cell: &LiveCellInfo,
+        deposit_header: &HeaderView,
+        tip_header: &HeaderView,
+    ) -> Result<u64, String> {
+        let out_point = OutPoint::new(deposit_cell.tx_hash.pack(), deposit_cell.output_index);
+        let (output, output_data) = get_live_cell(self.rpc_client, out_point, true)?;
+        let occupied_capacity = output
+            .occupied_capacity(Capacity::bytes(output_data.len()).unwrap())
+            .unwrap();
+        Ok(calculate_dao_maximum_withdraw4(
+            deposit_header,
+            tip_header,
+            &output,
+            occupied_capacity.as_u64(),
+        ))
+    }
+}
+
+fn out_point_string(cell: &LiveCellInfo) -> String {
+    format!("{:#x}-{}", cell.tx_hash, cell.output_index)
+}
@@ -1,21 +1,78 @@
 use crate::subcommands::Output;
 use crate::utils::{
-    other::check_lack_of_capacity,
+    other::{check_lack_of_capacity, get_live_cell},
     printer::{OutputFormat, Printable},
     rpc::HttpRpcClient,
 };
-use ckb_sdk::util::calculate_dao_maximum_withdraw4;
+use ckb_sdk::{constants::DAO_TYPE_HASH, util::calculate_dao_maximum_withdraw4};
 use ckb_types::core::{Capacity, TransactionView};
-use ckb_types::{core::HeaderView, packed, prelude::*};
+use ckb_types::{
+    core::{EpochNumberWithFraction, HeaderView, ScriptHashType},
+    packed,
+    prelude::*,
+};
 use plugin_protocol::LiveCellInfo;
 
+/// The lock period of NervosDAO deposit in epochs
+pub(crate) const DAO_LOCK_PERIOD_EPOCHS: u64 = 180;
+
+/// Headers and deposit output of a prepared cell
+pub(crate) struct PreparedCellInfo {
+    pub(crate) deposit_header: HeaderView,
+    pub(crate) prepare_header: HeaderView,
+    pub(crate) maximum_withdraw: u64,
+}
+
 pub(crate) fn calculate_dao_maximum_withdraw(
     rpc_client: &mut HttpRpcClient,
     prepare_cell: &LiveCellInfo,
 ) -> Result<u64, String> {
+    load_prepared_cell_info(rpc_client, prepare_cell).map(|info| info.maximum_withdraw)
+}
+
+pub(crate) fn load_prepared_cell_info(
+    rpc_client: &mut HttpRpcClient,
+    prepare_cell: &LiveCellInfo,
+) -> Result<PreparedCellInfo, String> {
+    load_prepared_cell_info_by_out_point(rpc_client, &prepare_cell.out_point())
+}
+
+/// The total NervosDAO compensation of the prepared cells in transaction inputs, it's required to
+/// calculate the transaction fee of a withdraw transaction.
+pub(crate) fn calculate_dao_compensation(
+    rpc_client: &mut HttpRpcClient,
+    tx: &TransactionView,
+) -> Result<u64, String> {
+    let mut compensation = 0;
+    for input in tx.inputs().into_iter() {
+        let out_point = input.previous_output();
+        let (output, output_data) = get_live_cell(rpc_client, out_point.clone(), true)?;
+        let is_dao = output
+            .type_()
+            .to_opt()
+            .map(|script| {
+                script.code_hash() == DAO_TYPE_HASH.pack()
+                    && script.hash_type() == ScriptHashType::Type.into()
+            })
+            .unwrap_or(false);
+        let is_prepared = output_data.len() == 8 && output_data.iter().any(|byte| *byte != 0);
+        if is_dao && is_prepared {
+            let capacity: u64 = output.capacity().unpack();
+            let info = load_prepared_cell_info_by_out_point(rpc_client, &out_point)?;
+            compensation += info.maximum_withdraw.saturating_sub(capacity);
+        }
+    }
+    Ok(compensation)
+}
+
+fn load_prepared_cell_info_by_out_point(
+    rpc_client: &mut HttpRpcClient,
+    prepare_out_point: &packed::OutPoint,
+) -> Result<PreparedCellInfo, String> {
+    let prepare_output_index: u32 = prepare_out_point.index().unpack();
     // Get the deposit_header and prepare_header corresponding to the `prepare_cell`
     let prepare_tx_status = rpc_client
-        .get_transaction(prepare_cell.tx_hash.clone())?
+        .get_transaction(prepare_out_point.tx_hash().unpack())?
         .ok_or_else(|| "invalid prepare out_point, the tx is not found".to_string())?;
     let prepare_block_hash = prepare_tx_status
         .tx_status
@@ -31,7 +88,7 @@ pub(crate) fn calculate_dao_maximum_withdraw(
     };
     let deposit_out_point = prepare_tx
         .inputs()
-        .get(prepare_cell.index.output_index as usize)
+        .get(prepare_output_index as usize)
         .ok_or_else(|| "invalid prepare tx".to_string())?
         .previous_output();
     let deposit_tx_status = {
@@ -72,12 +129,59 @@ pub(crate) fn calculate_dao_maximum_withdraw(
     let occupied_capacity = output
         .occupied_capacity(Capacity::bytes(output_data.len()).unwrap())
         .unwrap();
-    Ok(calculate_dao_maximum_withdraw4(
+    let maximum_withdraw = calculate_dao_maximum_withdraw4(
         &deposit_header,
         &prepare_header,
         &output,
         occupied_capacity.as_u64(),
-    ))
+    );
+    Ok(PreparedCellInfo {
+        deposit_header,
+        prepare_header,
+        maximum_withdraw,
+    })
+}
+
+/// The minimal since epoch of withdrawing a deposit prepared at `prepare_epoch`, the same
+/// rule as the NervosDAO type script (the deposited epochs is rounded up to the lock period).
+pub(crate) fn minimal_withdraw_epoch(
+    deposit_epoch: EpochNumberWithFraction,
+    prepare_epoch: EpochNumberWithFraction,
+) -> EpochNumberWithFraction {
+    let mut deposited_epochs = prepare_epoch.number() - deposit_epoch.number();
+    if prepare_epoch.index() * deposit_epoch.length()
+        > deposit_epoch.index() * prepare_epoch.length()
+    {
+        deposited_epochs += 1;
+    }
+    let lock_epochs = std::cmp::max(
+        (deposited_epochs + DAO_LOCK_PERIOD_EPOCHS - 1) / DAO_LOCK_PERIOD_EPOCHS
+            * DAO_LOCK_PERIOD_EPOCHS,
+        DAO_LOCK_PERIOD_EPOCHS,
+    );
+    EpochNumberWithFraction::new(
+        deposit_epoch.number() + lock_epochs,
+        deposit_epoch.index(),
+        deposit_epoch.length(),
+    )
+}
+
+/// Whether the `current` epoch is equal to or after the `target` epoch
+pub(crate) fn epoch_reached(
+    current: EpochNumberWithFraction,
+    target: EpochNumberWithFraction,
+) -> bool {
+    current.number() > target.number()
+        || (current.number() == target.number()
+            && current.index() * target.length() >= target.index() * current.length())
+}
+
+pub(crate) fn epoch_json(epoch: EpochNumberWithFraction) -> serde_json::Value {
+    serde_json::json!({
+        "number": epoch.number(),
+        "index": epoch.index(),
+        "length": epoch.length(),
+    })
 }
 
 pub(crate) fn send_transaction(
@@ -97,3 +201,47 @@ pub(crate) fn send_transaction(
     let resp = rpc_client.send_transaction(transaction.data(), None)?;
     Ok(Output::new_output(resp))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimal_withdraw_epoch() {
+        let deposit_epoch = EpochNumberWithFraction::new(10, 5, 1000);
+        // Prepared in the first lock period
+        let withdraw_epoch =
+            minimal_withdraw_epoch(deposit_epoch, EpochNumberWithFraction::new(100, 0, 1000));
+        assert_eq!(withdraw_epoch, EpochNumberWithFraction::new(190, 5, 1000));
+        // Prepared exactly at the end of first lock period
+        let withdraw_epoch =
+            minimal_withdraw_epoch(deposit_epoch, EpochNumberWithFraction::new(190, 5, 1000));
+        assert_eq!(withdraw_epoch, EpochNumberWithFraction::new(190, 5, 1000));
+        // Prepared after the first lock period
+        let withdraw_epoch =
+            minimal_withdraw_epoch(deposit_epoch, EpochNumberWithFraction::new(190, 6, 1000));
+        assert_eq!(withdraw_epoch, EpochNumberWithFraction::new(370, 5, 1000));
+    }
+
+    #[test]
+    fn test_epoch_reached() {
+        let target = EpochNumberWithFraction::new(190, 5, 1000);
+        assert!(epoch_reached(target, target));
+        assert!(epoch_reached(
+            EpochNumberWithFraction::new(191, 0, 1000),
+            target
+        ));
+        assert!(epoch_reached(
+            EpochNumberWithFraction::new(190, 1, 100),
+            target
+        ));
+        assert!(!epoch_reached(
+            EpochNumberWithFraction::new(190, 4, 1000),
+            target
+        ));
+        assert!(!epoch_reached(
+            EpochNumberWithFraction::new(189, 999, 1000),
+            target
+        ));
+    }
+}
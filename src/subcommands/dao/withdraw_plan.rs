@@ -0,0 +1,137 @@
+use ckb_sdk::HumanCapacity;
+use ckb_types::{
+    core::HeaderView,
+    packed::{OutPoint, Script},
+    prelude::*,
+    H256,
+};
+
+use super::util::{epoch_json, epoch_reached, load_prepared_cell_info, minimal_withdraw_epoch};
+use super::DAOSubCommand;
+
+/// Prepared cells selected to withdraw the requested amount
+pub struct WithdrawPlan {
+    pub out_points: Vec<OutPoint>,
+    /// The plan printed before signing
+    pub summary: serde_json::Value,
+}
+
+impl<'a> DAOSubCommand<'a> {
+    /// Select withdrawable prepared cells to cover `amount`, the transaction fee will be paid
+    /// from the withdrawn capacity.
+    ///
+    /// Only cells whose since constraint (the end of current lock period) is already reached are
+    /// selected, so the `DaoWithdrawBuilder` will accept them.
+    pub fn plan_withdraw(
+        &mut self,
+        lock_script: Script,
+        amount: u64,
+    ) -> Result<WithdrawPlan, String> {
+        if amount == 0 {
+            return Err("<amount> must be greater than 0".to_string());
+        }
+        let tip_header: HeaderView = self.rpc_client.get_tip_header()?.into();
+        let tip_epoch = tip_header.epoch();
+        let mut candidates = Vec::new();
+        let mut not_matured = 0;
+        for cell in self.query_prepare_cells(lock_script)? {
+            let info = load_prepared_cell_info(self.rpc_client, &cell)?;
+            let withdraw_epoch =
+                minimal_withdraw_epoch(info.deposit_header.epoch(), info.prepare_header.epoch());
+            if epoch_reached(tip_epoch, withdraw_epoch) {
+                let out_point = OutPoint::new(cell.tx_hash.pack(), cell.output_index);
+                candidates.push((out_point, cell.capacity, info.maximum_withdraw));
+            } else {
+                not_matured += 1;
+            }
+        }
+        let values: Vec<u64> = candidates.iter().map(|(_, _, value)| *value).collect();
+        let selected = select_cells(&values, amount).ok_or_else(|| {
+            format!(
+                "Withdrawable capacity is not enough, required: {}, withdrawable: {} ({} prepared cells are not matured yet)",
+                HumanCapacity::from(amount),
+                HumanCapacity::from(values.iter().sum::<u64>()),
+                not_matured,
+            )
+        })?;
+
+        let total_maximum_withdraw: u64 = selected.iter().map(|idx| values[*idx]).sum();
+        let cells: Vec<_> = selected
+            .iter()
+            .map(|idx| {
+                let (out_point, capacity, maximum_withdraw) = &candidates[*idx];
+                let tx_hash: H256 = out_point.tx_hash().unpack();
+                let index: u32 = out_point.index().unpack();
+                serde_json::json!({
+                    "out_point": format!("{:#x}-{}", tx_hash, index),
+                    "capacity": format!("{:#}", HumanCapacity::from(*capacity)),
+                    "maximum_withdraw": format!("{:#}", HumanCapacity::from(*maximum_withdraw)),
+                })
+            })
+            .collect();
+        let summary = serde_json::json!({
+            "tip_epoch": epoch_json(tip_epoch),
+            "amount": format!("{:#}", HumanCapacity::from(amount)),
+            "cells": cells,
+            "total_maximum_withdraw": format!("{:#}", HumanCapacity::from(total_maximum_withdraw)),
+            "not_matured_cells": not_matured,
+        });
+        let out_points = selected
+            .into_iter()
+            .map(|idx| candidates[idx].0.clone())
+            .collect();
+        Ok(WithdrawPlan {
+            out_points,
+            summary,
+        })
+    }
+}
+
+/// Select the minimal number of values whose sum is at least `amount`, returns the indexes.
+///
+/// The largest values are picked first, then the last picked one is replaced by the smallest
+/// value which still covers the rest of the amount, to avoid withdrawing too much.
+pub(crate) fn select_cells(values: &[u64], amount: u64) -> Option<Vec<usize>> {
+    let mut sorted: Vec<usize> = (0..values.len()).collect();
+    sorted.sort_by(|a, b| values[*b].cmp(&values[*a]));
+    let mut selected = Vec::new();
+    let mut total = 0;
+    for idx in sorted.iter() {
+        if total >= amount {
+            break;
+        }
+        selected.push(*idx);
+        total += values[*idx];
+    }
+    if total < amount {
+        return None;
+    }
+    if let Some(last) = selected.pop() {
+        let rest = amount - (total - values[last]);
+        let replacement = sorted[selected.len()..]
+            .iter()
+            .rev()
+            .find(|idx| values[**idx] >= rest)
+            .cloned()
+            .unwrap_or(last);
+        selected.push(replacement);
+    }
+    Some(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_cells() {
+        let values = vec![300, 1000, 500, 200];
+        assert_eq!(select_cells(&values, 0), Some(vec![]));
+        assert_eq!(select_cells(&values, 250), Some(vec![0]));
+        assert_eq!(select_cells(&values, 1000), Some(vec![1]));
+        assert_eq!(select_cells(&values, 1200), Some(vec![1, 3]));
+        assert_eq!(select_cells(&values, 1400), Some(vec![1, 2]));
+        assert_eq!(select_cells(&values, 2000), Some(vec![1, 2, 0, 3]));
+        assert_eq!(select_cells(&values, 2001), None);
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use ckb_jsonrpc_types as json_types;
+use ckb_types::H256;
+use serde_derive::Serialize;
+
+use super::deployment::{Deployment, DeploymentRecipe};
+use crate::utils::cell_dep::{CellDepItem, CellDepName, CellDeps, ScriptId};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// The `CellDeps` file format read by `sudt --cell-deps`
+    CellDeps,
+    /// Lumos config `SCRIPTS` section
+    Lumos,
+    /// TOML snippet for backend config
+    Toml,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+    fn from_str(input: &str) -> Result<ExportFormat, String> {
+        match input {
+            "cell-deps" => Ok(ExportFormat::CellDeps),
+            "lumos" => Ok(ExportFormat::Lumos),
+            "toml" => Ok(ExportFormat::Toml),
+            _ => Err(format!("Invalid export format: {}", input)),
+        }
+    }
+}
+
+/// A deployed cell described as a script id plus the cell dep needed to use it.
+#[derive(Clone, Debug)]
+pub struct ExportItem {
+    pub name: String,
+    pub script_id: ScriptId,
+    pub cell_dep: json_types::CellDep,
+}
+
+/// Build export items from the latest deployment recipe.
+///
+/// Type id cells are referenced by the type script hash with `hash_type = type`, other cells by
+/// their data hash with the given `data_hash_type`. When the deployment config is given, cells
+/// included in a dep group are referenced through that dep group.
+pub fn build_export_items(
+    recipe: &DeploymentRecipe,
+    deployment_opt: Option<&Deployment>,
+    data_hash_type: json_types::ScriptHashType,
+) -> Result<Vec<ExportItem>> {
+    let dep_group_recipes: HashMap<&String, _> = recipe
+        .dep_group_recipes
+        .iter()
+        .map(|dep_group_recipe| (&dep_group_recipe.name, dep_group_recipe))
+        .collect();
+    let mut cell_dep_groups: HashMap<&String, _> = HashMap::default();
+    if let Some(deployment) = deployment_opt {
+        for dep_group in &deployment.dep_groups {
+            let dep_group_recipe = dep_group_recipes.get(&dep_group.name).ok_or_else(|| {
+                anyhow!(
+                    "Can not find dep_group: {} in deployment recipe",
+                    dep_group.name
+                )
+            })?;
+            for cell_name in &dep_group.cells {
+                cell_dep_groups
+                    .entry(cell_name)
+                    .or_insert(*dep_group_recipe);
+            }
+        }
+    }
+
+    let items = recipe
+        .cell_recipes
+        .iter()
+        .map(|cell_recipe| {
+            let script_id = if let Some(type_id) = cell_recipe.type_id.as_ref() {
+                ScriptId {
+                    code_hash: type_id.clone(),
+                    hash_type: json_types::ScriptHashType::Type,
+                }
+            } else {
+                ScriptId {
+                    code_hash: cell_recipe.data_hash.clone(),
+                    hash_type: data_hash_type.clone(),
+                }
+            };
+            let cell_dep = if let Some(dep_group_recipe) = cell_dep_groups.get(&cell_recipe.name) {
+                new_cell_dep(
+                    &dep_group_recipe.tx_hash,
+                    dep_group_recipe.index,
+                    json_types::DepType::DepGroup,
+                )
+            } else {
+                new_cell_dep(
+                    &cell_recipe.tx_hash,
+                    cell_recipe.index,
+                    json_types::DepType::Code,
+                )
+            };
+            ExportItem {
+                name: cell_recipe.name.clone(),
+                script_id,
+                cell_dep,
+            }
+        })
+        .collect();
+    Ok(items)
+}
+
+fn new_cell_dep(tx_hash: &H256, index: u32, dep_type: json_types::DepType) -> json_types::CellDep {
+    json_types::CellDep {
+        out_point: json_types::OutPoint {
+            tx_hash: tx_hash.clone(),
+            index: index.into(),
+        },
+        dep_type,
+    }
+}
+
+/// Convert to a `CellDeps` file, cells whose name is not a known cell dep name are returned
+/// as the second value.
+pub fn to_cell_deps(items: &[ExportItem]) -> (CellDeps, Vec<String>) {
+    let mut cell_deps = CellDeps {
+        items: HashMap::default(),
+        extensions: Vec::new(),
+    };
+    let mut skipped = Vec::new();
+    for item in items {
+        match CellDepName::from_str(&item.name) {
+            Ok(name) => {
+                cell_deps.items.insert(
+                    name,
+                    CellDepItem {
+                        script_id: item.script_id.clone(),
+                        cell_dep: item.cell_dep.clone(),
+                    },
+                );
+            }
+            Err(_) => skipped.push(item.name.clone()),
+        }
+    }
+    (cell_deps, skipped)
+}
+
+pub fn to_lumos(items: &[ExportItem]) -> serde_json::Value {
+    let scripts: serde_json::Map<String, serde_json::Value> = items
+        .iter()
+        .map(|item| {
+            let hash_type = match item.script_id.hash_type {
+                json_types::ScriptHashType::Data => "data",
+                json_types::ScriptHashType::Type => "type",
+                json_types::ScriptHashType::Data1 => "data1",
+            };
+            let dep_type = match item.cell_dep.dep_type {
+                json_types::DepType::Code => "code",
+                json_types::DepType::DepGroup => "depGroup",
+            };
+            let value = serde_json::json!({
+                "CODE_HASH": format!("{:#x}", item.script_id.code_hash),
+                "HASH_TYPE": hash_type,
+                "TX_HASH": format!("{:#x}", item.cell_dep.out_point.tx_hash),
+                "INDEX": format!("{:#x}", item.cell_dep.out_point.index.value()),
+                "DEP_TYPE": dep_type,
+            });
+            (item.name.to_uppercase(), value)
+        })
+        .collect();
+    serde_json::json!({ "SCRIPTS": scripts })
+}
+
+#[derive(Serialize)]
+struct TomlScript {
+    code_hash: H256,
+    hash_type: json_types::ScriptHashType,
+    tx_hash: H256,
+    index: u32,
+    dep_type: json_types::DepType,
+}
+
+#[derive(Serialize)]
+struct TomlScripts {
+    scripts: BTreeMap<String, TomlScript>,
+}
+
+pub fn to_toml(items: &[ExportItem]) -> Result<String> {
+    let scripts = items
+        .iter()
+        .map(|item| {
+            let script = TomlScript {
+                code_hash: item.script_id.code_hash.clone(),
+                hash_type: item.script_id.hash_type.clone(),
+                tx_hash: item.cell_dep.out_point.tx_hash.clone(),
+                index: item.cell_dep.out_point.index.value(),
+                dep_type: item.cell_dep.dep_type.clone(),
+            };
+            (item.name.clone(), script)
+        })
+        .collect();
+    Ok(toml::to_string(&TomlScripts { scripts })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subcommands::deploy::deployment::{CellRecipe, DepGroup, DepGroupRecipe};
+    use ckb_types::h256;
+
+    fn test_recipe() -> DeploymentRecipe {
+        DeploymentRecipe {
+            cell_recipes: vec![
+                CellRecipe {
+                    name: "sudt".to_string(),
+                    tx_hash: h256!("0x1"),
+                    index: 0,
+                    occupied_capacity: 0,
+                    data_hash: h256!("0xd1"),
+                    type_id: None,
+                },
+                CellRecipe {
+                    name: "my_cell".to_string(),
+                    tx_hash: h256!("0x1"),
+                    index: 1,
+                    occupied_capacity: 0,
+                    data_hash: h256!("0xd2"),
+                    type_id: Some(h256!("0xe2")),
+                },
+            ],
+            dep_group_recipes: vec![DepGroupRecipe {
+                name: "my_dep_group".to_string(),
+                tx_hash: h256!("0x2"),
+                index: 0,
+                data_hash: h256!("0xd3"),
+                occupied_capacity: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_build_export_items() {
+        let recipe = test_recipe();
+        let items = build_export_items(&recipe, None, json_types::ScriptHashType::Data1).unwrap();
+        assert_eq!(items[0].script_id.code_hash, h256!("0xd1"));
+        assert_eq!(
+            items[0].script_id.hash_type,
+            json_types::ScriptHashType::Data1
+        );
+        assert_eq!(items[1].script_id.code_hash, h256!("0xe2"));
+        assert_eq!(
+            items[1].script_id.hash_type,
+            json_types::ScriptHashType::Type
+        );
+        assert_eq!(items[1].cell_dep.dep_type, json_types::DepType::Code);
+
+        let deployment = Deployment {
+            dep_groups: vec![DepGroup {
+                name: "my_dep_group".to_string(),
+                cells: vec!["my_cell".to_string()],
+            }],
+            ..Default::default()
+        };
+        let items = build_export_items(
+            &recipe,
+            Some(&deployment),
+            json_types::ScriptHashType::Data1,
+        )
+        .unwrap();
+        assert_eq!(items[0].cell_dep.dep_type, json_types::DepType::Code);
+        assert_eq!(items[1].cell_dep.dep_type, json_types::DepType::DepGroup);
+        assert_eq!(items[1].cell_dep.out_point.tx_hash, h256!("0x2"));
+
+        let (cell_deps, skipped) = to_cell_deps(&items);
+        assert!(cell_deps.get_item(CellDepName::Sudt).is_some());
+        assert_eq!(skipped, vec!["my_cell".to_string()]);
+    }
+}
@@ -26,6 +26,18 @@ pub struct IntermediumInfo {
     pub dep_group_tx: Option<json_types::Transaction>,
     pub dep_group_tx_signatures: HashMap<JsonBytes, Vec<JsonBytes>>,
     pub dep_group_changes: Vec<ReprStateChange>,
+    // Only exists when generated with `--pin-inputs`
+    #[serde(default)]
+    pub pinned_inputs: Option<PinnedInputs>,
+}
+
+/// The exact inputs used to build the transactions, for rebuilding and verifying them
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct PinnedInputs {
+    pub from_address: String,
+    pub fee_rate: u64,
+    pub cell_tx_inputs: Vec<json_types::OutPoint>,
+    pub dep_group_tx_inputs: Vec<json_types::OutPoint>,
 }
 
 impl IntermediumInfo {
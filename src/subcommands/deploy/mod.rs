@@ -22,27 +22,31 @@ use crate::plugin::PluginManager;
 use crate::utils::{
     arg,
     arg_parser::{
-        AddressParser, ArgParser, DirPathParser, FilePathParser, FixedHashParser, FromStrParser,
+        AccountParser, AddressParser, ArgParser, DirPathParser, FilePathParser, FromStrParser,
         PrivkeyPathParser, PrivkeyWrapper,
     },
     genesis_info::GenesisInfo,
-    other::{get_live_cell_with_cache, get_network_type, read_password},
+    other::{get_fee_rate, get_live_cell_with_cache, get_network_type},
     rpc::HttpRpcClient,
-    signer::KeyStoreHandlerSigner,
+    signer::{ExternalSigner, KeyStoreHandlerSigner},
     tx_helper::SignerFn,
 };
 
 mod deployment;
+mod export;
 mod intermedium_info;
 mod state_change;
 mod tx_builder;
+mod upgrade_check;
 
 use deployment::{
     Cell, CellLocation, CellRecipe, DepGroup, DepGroupRecipe, Deployment, DeploymentRecipe,
 };
-use intermedium_info::IntermediumInfo;
+use export::{build_export_items, to_cell_deps, to_lumos, to_toml, ExportFormat};
+use intermedium_info::{IntermediumInfo, PinnedInputs};
 use state_change::{CellChange, ChangeInfo, DepGroupChange, ReprStateChange, StateChange};
-use tx_builder::build_tx;
+use tx_builder::{build_new_pinned_tx, build_pinned_tx, build_tx};
+use upgrade_check::{check_cell_changes, check_type_id};
 
 const DEPLOYMENT_TOML: &str = include_str!("../../deployment.toml");
 
@@ -112,10 +116,21 @@ impl<'a> DeploySubCommand<'a> {
                         Arg::with_name("sign-now")
                             .long("sign-now")
                             .about("Sign the cell/dep_group transaction add signatures to info-file now"),
+                    )
+                    .arg(arg::external_signer().requires("sign-now"))
+                    .arg(
+                        Arg::with_name("pin-inputs")
+                            .long("pin-inputs")
+                            .about("Record the exact inputs in info-file and build the transactions only from them, so that they can be verified by `deploy verify-info`"),
                     ),
                 App::new("sign-txs")
                     .arg(arg::privkey_path().required_unless(arg::from_account().get_name()))
                     .arg(arg::from_account().required_unless(arg::privkey_path().get_name()))
+                    .arg(
+                        arg::external_signer()
+                            .requires(arg::from_account().get_name())
+                            .conflicts_with(arg::privkey_path().get_name()),
+                    )
                     .arg(arg_info_file.clone())
                     .arg(
                         Arg::with_name("add-signatures")
@@ -126,10 +141,47 @@ impl<'a> DeploySubCommand<'a> {
                 App::new("explain-txs")
                     .arg(arg_info_file.clone())
                     .about("Explain cell transaction and dep_group transaction"),
+                App::new("verify-info")
+                    .arg(arg_deployment.clone())
+                    .arg(arg_migration_dir.clone())
+                    .arg(arg_info_file.clone())
+                    .about("Rebuild cell/dep_group transactions from deployment config and pinned inputs, then check they are identical to the info-file"),
                 App::new("apply-txs")
                     .arg(arg_info_file.clone())
-                    .arg(arg_migration_dir)
+                    .arg(arg_migration_dir.clone())
                     .about("Send cell/dep_group transactions and write results to migration directory"),
+                App::new("export")
+                    .arg(arg_migration_dir.clone().about("Migration directory to load the latest deployment recipe from"))
+                    .arg(
+                        Arg::with_name("format")
+                            .long("format")
+                            .takes_value(true)
+                            .possible_values(&["cell-deps", "lumos", "toml"])
+                            .default_value("cell-deps")
+                            .about("Export format: `cell-deps` (file for `sudt --cell-deps`), `lumos` (lumos `SCRIPTS` config), `toml` (config snippet)"),
+                    )
+                    .arg(
+                        arg_deployment
+                            .clone()
+                            .required(false)
+                            .about("deployment config file path (.toml), cells in a dep_group will be exported with the dep_group as cell dep"),
+                    )
+                    .arg(
+                        Arg::with_name("data-hash-type")
+                            .long("data-hash-type")
+                            .takes_value(true)
+                            .possible_values(&["data", "data1"])
+                            .default_value("data1")
+                            .about("The hash_type used for cells without type id"),
+                    )
+                    .arg(
+                        Arg::with_name("output-file")
+                            .long("output-file")
+                            .takes_value(true)
+                            .validator(|input| FilePathParser::new(false).validate(input))
+                            .about("Output file path, print to stdout if not given"),
+                    )
+                    .about("Export the latest deployment recipe as cell deps config"),
                 App::new("init-config")
                     .arg(arg_deployment.validator(|input| FilePathParser::new(false).validate(input)))
                     .about("Initialize default deployment config (format: toml)")
@@ -145,12 +197,13 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                 let from_address: Address = AddressParser::new_sighash()
                     .set_network(network)
                     .from_matches(m, "from-address")?;
-                let fee_rate: u64 = FromStrParser::<u64>::default().from_matches(m, "fee-rate")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
                 let deployment_config: PathBuf =
                     FilePathParser::new(true).from_matches(m, "deployment-config")?;
                 let migration_dir: PathBuf =
                     DirPathParser::new(true).from_matches(m, "migration-dir")?;
                 let info_file: PathBuf = FilePathParser::new(false).from_matches(m, "info-file")?;
+                let pin_inputs = m.is_present("pin-inputs");
 
                 if info_file.exists() {
                     return Err(format!("Output info-file already exists: {:?}", info_file));
@@ -173,6 +226,12 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                     last_recipe.as_ref().map(|recipe| &recipe.cell_recipes[..]),
                 )
                 .map_err(|err| err.to_string())?;
+                for warning in
+                    check_cell_changes(self.rpc_client.indexer_url(), &lock_script, &cell_changes)
+                        .map_err(|err| err.to_string())?
+                {
+                    eprintln!("WARNING: {}", warning);
+                }
 
                 let mut multisig_config = None;
                 if !deployment.multisig_config.sighash_addresses.is_empty() {
@@ -183,15 +242,27 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                 // * Build new cell transaction
                 let cell_tx_opt = {
                     log::info!("Building cell transaction ...");
-                    build_tx(
-                        (&from_address, fee_rate),
-                        multisig_config.as_ref(),
-                        &lock_script,
-                        &cell_changes,
-                        None,
-                        &self.genesis_info,
-                        self.rpc_client.url(),
-                    )
+                    if pin_inputs {
+                        build_new_pinned_tx(
+                            (&from_address, fee_rate),
+                            multisig_config.as_ref(),
+                            &lock_script,
+                            &cell_changes,
+                            None,
+                            &self.genesis_info,
+                            self.rpc_client,
+                        )
+                    } else {
+                        build_tx(
+                            (&from_address, fee_rate),
+                            multisig_config.as_ref(),
+                            &lock_script,
+                            &cell_changes,
+                            None,
+                            &self.genesis_info,
+                            self.rpc_client,
+                        )
+                    }
                     .map_err(|err| err.to_string())?
                 };
 
@@ -216,15 +287,27 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                 // * Build new dep_group transaction
                 let dep_group_tx_opt = {
                     log::info!("Building dep_group transaction ...");
-                    build_tx(
-                        (&from_address, fee_rate),
-                        multisig_config.as_ref(),
-                        &lock_script,
-                        &dep_group_changes,
-                        cell_tx_opt.clone(),
-                        &self.genesis_info,
-                        self.rpc_client.url(),
-                    )
+                    if pin_inputs {
+                        build_new_pinned_tx(
+                            (&from_address, fee_rate),
+                            multisig_config.as_ref(),
+                            &lock_script,
+                            &dep_group_changes,
+                            cell_tx_opt.as_ref(),
+                            &self.genesis_info,
+                            self.rpc_client,
+                        )
+                    } else {
+                        build_tx(
+                            (&from_address, fee_rate),
+                            multisig_config.as_ref(),
+                            &lock_script,
+                            &dep_group_changes,
+                            cell_tx_opt.clone(),
+                            &self.genesis_info,
+                            self.rpc_client,
+                        )
+                    }
                     .map_err(|err| err.to_string())?
                 };
                 if cell_tx_opt.is_none() && dep_group_tx_opt.is_none() {
@@ -264,6 +347,26 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                     cell_recipes: new_cell_recipes,
                     dep_group_recipes: new_dep_group_recipes,
                 };
+                let pinned_inputs = if pin_inputs {
+                    let to_json_inputs = |tx_opt: Option<&packed::Transaction>| {
+                        tx_opt
+                            .map(|tx| {
+                                tx_input_out_points(tx)
+                                    .into_iter()
+                                    .map(json_types::OutPoint::from)
+                                    .collect()
+                            })
+                            .unwrap_or_default()
+                    };
+                    Some(PinnedInputs {
+                        from_address: from_address.to_string(),
+                        fee_rate,
+                        cell_tx_inputs: to_json_inputs(cell_tx_opt.as_ref()),
+                        dep_group_tx_inputs: to_json_inputs(dep_group_tx_opt.as_ref()),
+                    })
+                } else {
+                    None
+                };
                 let mut info = IntermediumInfo {
                     deployment,
                     last_recipe,
@@ -275,28 +378,36 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                     dep_group_tx: dep_group_tx_opt.map(Into::into),
                     dep_group_tx_signatures: HashMap::default(),
                     dep_group_changes: repr_dep_group_changes,
+                    pinned_inputs,
                 };
                 explain_txs(&info).map_err(|err| err.to_string())?;
 
                 // Sign if required
                 if m.is_present("sign-now") {
                     let account = H160::from_slice(from_address.payload().args().as_ref()).unwrap();
-                    let signer = {
-                        let handler = self.plugin_mgr.keystore_handler();
-                        let change_path = handler.root_key_path(account.clone())?;
-                        let mut signer = KeyStoreHandlerSigner::new(
-                            handler,
-                            Box::new(DefaultTransactionDependencyProvider::new(
+                    let signer: Box<dyn Signer> =
+                        if let Some(command) = m.value_of("external-signer") {
+                            Box::new(ExternalSigner::for_account(
+                                command.to_string(),
                                 self.rpc_client.url(),
-                                0,
-                            )),
-                        );
-                        if self.plugin_mgr.keystore_require_password() {
-                            signer.set_password(account.clone(), read_password(false, None)?);
-                        }
-                        signer.set_change_path(account.clone(), change_path.to_string());
-                        Box::new(signer)
-                    };
+                                account.clone(),
+                            ))
+                        } else {
+                            let handler = self.plugin_mgr.keystore_handler();
+                            let change_path = handler.root_key_path(account.clone())?;
+                            let mut signer = KeyStoreHandlerSigner::new(
+                                handler,
+                                Box::new(DefaultTransactionDependencyProvider::new(
+                                    self.rpc_client.url(),
+                                    0,
+                                )),
+                            );
+                            if let Some(password) = self.plugin_mgr.keystore_password(&account)? {
+                                signer.set_password(account.clone(), password);
+                            }
+                            signer.set_change_path(account.clone(), change_path.to_string());
+                            Box::new(signer)
+                        };
                     let signer_fn = Box::new(
                         move |lock_args: &HashSet<H160>,
                               message: &H256,
@@ -337,31 +448,25 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                 let account_opt: Option<H160> = m
                     .value_of("from-account")
                     .map(|input| {
-                        FixedHashParser::<H160>::default()
-                            .parse(input)
-                            .or_else(|err| {
-                                let mut parser = AddressParser::new_sighash();
-                                match get_network_type(self.rpc_client) {
-                                    Ok(network) => {
-                                        parser.set_network(network);
-                                    }
-                                    Err(err) => {
-                                        eprintln!("WARNING: get network type failed: {}", err);
-                                    }
-                                }
-                                let result: Result<Address, String> = parser.parse(input);
-                                result
-                                    .map(|address| {
-                                        H160::from_slice(&address.payload().args()).unwrap()
-                                    })
-                                    .map_err(|_| err)
+                        let network = get_network_type(self.rpc_client)
+                            .map_err(|err| {
+                                eprintln!("WARNING: get network type failed: {}", err);
                             })
+                            .ok();
+                        AccountParser::new(network).parse(input)
                     })
                     .transpose()?;
 
+                let external_signer = m.value_of("external-signer");
                 let all_signatures = modify_info_file(&info_file, |info: &mut IntermediumInfo| {
                     let signer: Box<dyn Signer> = if let Some(privkey) = privkey_opt {
                         Box::new(privkey)
+                    } else if let Some(command) = external_signer {
+                        Box::new(ExternalSigner::for_account(
+                            command.to_string(),
+                            self.rpc_client.url(),
+                            account_opt.clone().unwrap(),
+                        ))
                     } else {
                         let account = account_opt.clone().unwrap();
                         let handler = self.plugin_mgr.keystore_handler();
@@ -375,11 +480,12 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                                 0,
                             )),
                         );
-                        if self.plugin_mgr.keystore_require_password() {
-                            signer.set_password(
-                                account.clone(),
-                                read_password(false, None).map_err(|err| anyhow!(err))?,
-                            );
+                        if let Some(password) = self
+                            .plugin_mgr
+                            .keystore_password(&account)
+                            .map_err(|err| anyhow!(err))?
+                        {
+                            signer.set_password(account.clone(), password);
                         }
                         signer.set_change_path(account, change_path.to_string());
                         Box::new(signer)
@@ -441,6 +547,83 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
 
                 Ok(Output::new_success())
             }
+            ("verify-info", Some(m)) => {
+                let deployment_config: PathBuf =
+                    FilePathParser::new(true).from_matches(m, "deployment-config")?;
+                let migration_dir: PathBuf =
+                    DirPathParser::new(true).from_matches(m, "migration-dir")?;
+                let info_file: PathBuf = FilePathParser::new(true).from_matches(m, "info-file")?;
+
+                let file = fs::File::open(info_file).map_err(|err| err.to_string())?;
+                let info: IntermediumInfo =
+                    serde_json::from_reader(&file).map_err(|err| err.to_string())?;
+                let pinned = info.pinned_inputs.clone().ok_or_else(|| {
+                    "No pinned inputs in info-file, please generate it by `deploy gen-txs --pin-inputs`".to_string()
+                })?;
+                let deployment =
+                    load_deployment(&deployment_config).map_err(|err| err.to_string())?;
+                if deployment != info.deployment {
+                    return Err("The deployment config is different from info-file".to_string());
+                }
+                let last_recipe =
+                    load_last_snapshot(&migration_dir).map_err(|err| err.to_string())?;
+                let network = get_network_type(self.rpc_client)?;
+                let from_address: Address = AddressParser::new_sighash()
+                    .set_network(network)
+                    .parse(&pinned.from_address)?;
+
+                let (cell_tx_opt, dep_group_tx_opt, new_recipe) = rebuild_pinned_txs(
+                    self.rpc_client,
+                    &self.genesis_info,
+                    &deployment,
+                    last_recipe.as_ref(),
+                    &from_address,
+                    &pinned,
+                )
+                .map_err(|err| err.to_string())?;
+
+                let tx_matched =
+                    |expected: Option<&json_types::Transaction>,
+                     actual: Option<&packed::Transaction>| {
+                        match (expected, actual) {
+                            (Some(expected), Some(actual)) => {
+                                packed::Transaction::from(expected.clone()).as_slice()
+                                    == actual.as_slice()
+                            }
+                            (None, None) => true,
+                            _ => false,
+                        }
+                    };
+                let json_matched =
+                    |a: serde_json::Result<serde_json::Value>,
+                     b: serde_json::Result<serde_json::Value>| {
+                        matches!((a, b), (Ok(a), Ok(b)) if a == b)
+                    };
+                let resp = serde_json::json!({
+                    "cell_tx": tx_matched(info.cell_tx.as_ref(), cell_tx_opt.as_ref()),
+                    "dep_group_tx": tx_matched(info.dep_group_tx.as_ref(), dep_group_tx_opt.as_ref()),
+                    "last_recipe": json_matched(
+                        serde_json::to_value(&last_recipe),
+                        serde_json::to_value(&info.last_recipe),
+                    ),
+                    "new_recipe": json_matched(
+                        serde_json::to_value(&new_recipe),
+                        serde_json::to_value(&info.new_recipe),
+                    ),
+                });
+                let all_matched = resp
+                    .as_object()
+                    .map(|map| map.values().all(|value| value.as_bool() == Some(true)))
+                    .unwrap_or(false);
+                if all_matched {
+                    Ok(Output::new_output(resp))
+                } else {
+                    Err(format!(
+                        "The info-file is not identical to the rebuilt transactions: {}",
+                        resp
+                    ))
+                }
+            }
             ("apply-txs", Some(m)) => {
                 let info_file: PathBuf = FilePathParser::new(false).from_matches(m, "info-file")?;
                 let migration_dir: PathBuf =
@@ -537,6 +720,54 @@ impl<'a> CliSubCommand for DeploySubCommand<'a> {
                 });
                 Ok(Output::new_output(resp))
             }
+            ("export", Some(m)) => {
+                let migration_dir: PathBuf =
+                    DirPathParser::new(true).from_matches(m, "migration-dir")?;
+                let format: ExportFormat =
+                    FromStrParser::<ExportFormat>::new().from_matches(m, "format")?;
+                let deployment_config: Option<PathBuf> =
+                    FilePathParser::new(true).from_matches_opt(m, "deployment-config")?;
+                let output_file: Option<PathBuf> =
+                    FilePathParser::new(false).from_matches_opt(m, "output-file")?;
+                let data_hash_type = match m.value_of("data-hash-type") {
+                    Some("data") => json_types::ScriptHashType::Data,
+                    _ => json_types::ScriptHashType::Data1,
+                };
+
+                let recipe = load_last_snapshot(&migration_dir)
+                    .map_err(|err| err.to_string())?
+                    .ok_or_else(|| format!("No deployment recipe found in {:?}", migration_dir))?;
+                let deployment = deployment_config
+                    .map(|path| load_deployment(&path))
+                    .transpose()
+                    .map_err(|err| err.to_string())?;
+                let items = build_export_items(&recipe, deployment.as_ref(), data_hash_type)
+                    .map_err(|err| err.to_string())?;
+
+                let content = match format {
+                    ExportFormat::CellDeps => {
+                        let (cell_deps, skipped) = to_cell_deps(&items);
+                        for name in skipped {
+                            eprintln!(
+                                "WARNING: cell {} is not a known cell dep name, skipped",
+                                name
+                            );
+                        }
+                        serde_json::to_string_pretty(&cell_deps).map_err(|err| err.to_string())?
+                    }
+                    ExportFormat::Lumos => serde_json::to_string_pretty(&to_lumos(&items))
+                        .map_err(|err| err.to_string())?,
+                    ExportFormat::Toml => to_toml(&items).map_err(|err| err.to_string())?,
+                };
+                if let Some(path) = output_file {
+                    let mut file = fs::File::create(&path).map_err(|err| err.to_string())?;
+                    file.write_all(content.as_bytes())
+                        .map_err(|err| err.to_string())?;
+                } else {
+                    println!("{}", content);
+                }
+                Ok(Output::new_success())
+            }
             ("init-config", Some(m)) => {
                 let deployment_config: PathBuf =
                     FilePathParser::new(false).from_matches(m, "deployment-config")?;
@@ -689,6 +920,91 @@ fn sign_info(
     Ok(all_signatures)
 }
 
+// Rebuild cell/dep_group transactions from deployment config and pinned inputs (see `gen-txs`)
+fn rebuild_pinned_txs(
+    rpc_client: &mut HttpRpcClient,
+    genesis_info: &GenesisInfo,
+    deployment: &Deployment,
+    last_recipe: Option<&DeploymentRecipe>,
+    from_address: &Address,
+    pinned: &PinnedInputs,
+) -> Result<(
+    Option<packed::Transaction>,
+    Option<packed::Transaction>,
+    DeploymentRecipe,
+)> {
+    let lock_script = packed::Script::from(deployment.lock.clone());
+    let multisig_config = if deployment.multisig_config.sighash_addresses.is_empty() {
+        None
+    } else {
+        Some(MultisigConfig::try_from(deployment.multisig_config.clone()).map_err(Error::msg)?)
+    };
+    let cell_changes = load_cells(
+        rpc_client,
+        &lock_script,
+        &deployment.cells,
+        last_recipe.map(|recipe| &recipe.cell_recipes[..]),
+    )?;
+    let cell_tx_inputs: Vec<packed::OutPoint> = pinned
+        .cell_tx_inputs
+        .iter()
+        .cloned()
+        .map(packed::OutPoint::from)
+        .collect();
+    let cell_tx_opt = build_pinned_tx(
+        (from_address, pinned.fee_rate),
+        multisig_config.as_ref(),
+        &lock_script,
+        &cell_changes,
+        &cell_tx_inputs,
+        None,
+        genesis_info,
+        rpc_client,
+    )?;
+    let new_cell_recipes =
+        build_new_cell_recipes(&lock_script, cell_tx_opt.as_ref(), &cell_changes)?;
+
+    let dep_group_changes = load_dep_groups(
+        rpc_client,
+        &lock_script,
+        &deployment.dep_groups,
+        last_recipe.map(|recipe| &recipe.dep_group_recipes[..]),
+        &cell_changes,
+        &new_cell_recipes,
+    )?;
+    let dep_group_tx_inputs: Vec<packed::OutPoint> = pinned
+        .dep_group_tx_inputs
+        .iter()
+        .cloned()
+        .map(packed::OutPoint::from)
+        .collect();
+    let dep_group_tx_opt = build_pinned_tx(
+        (from_address, pinned.fee_rate),
+        multisig_config.as_ref(),
+        &lock_script,
+        &dep_group_changes,
+        &dep_group_tx_inputs,
+        cell_tx_opt.as_ref(),
+        genesis_info,
+        rpc_client,
+    )?;
+    let new_dep_group_recipes =
+        build_new_dep_group_recipes(&lock_script, dep_group_tx_opt.as_ref(), &dep_group_changes);
+    let new_recipe = DeploymentRecipe {
+        cell_recipes: new_cell_recipes,
+        dep_group_recipes: new_dep_group_recipes,
+    };
+    Ok((cell_tx_opt, dep_group_tx_opt, new_recipe))
+}
+
+fn tx_input_out_points(tx: &packed::Transaction) -> Vec<packed::OutPoint> {
+    tx.raw()
+        .inputs()
+        .into_iter()
+        .map(|input| input.previous_output())
+        .collect()
+}
+
 fn load_cells(
     rpc_client: &mut HttpRpcClient,
     lock_script: &packed::Script,
@@ -729,6 +1045,7 @@ fn load_cells(
         let change = if let Some((old_recipe, removed)) = cell_recipes_map.get_mut(&cell.name) {
             let old_recipe = old_recipe.clone();
             *removed = false;
+            check_type_id(&config, &old_recipe)?;
 
             let (old_data_hash, _, old_output) =
                 load_cell_info(rpc_client, &old_recipe.tx_hash, old_recipe.index)?;
@@ -736,9 +1053,8 @@ fn load_cells(
             let old_type_id_args = old_output.type_.map(|script| script.args.into_bytes());
             let data_unchanged = data_hash == old_data_hash;
             let lock_script_unchanged = lock_script.as_slice() == old_lock_script.as_slice();
-            let type_id_unchanged = old_recipe.type_id.is_some() == config.enable_type_id;
             // NOTE: we trust `old_recipe.data_hash` here
-            if data_unchanged && lock_script_unchanged && type_id_unchanged {
+            if data_unchanged && lock_script_unchanged {
                 StateChange::Unchanged {
                     data,
                     data_hash,
@@ -754,6 +1070,7 @@ fn load_cells(
                     old_recipe,
                     old_type_id_args,
                     output_index,
+                    old_capacity: old_output.capacity.value(),
                 }
             }
         } else {
@@ -895,6 +1212,7 @@ fn load_dep_groups(
                     old_recipe,
                     output_index,
                     old_type_id_args: None,
+                    old_capacity: old_output.capacity.value(),
                 }
             }
         } else {
@@ -994,6 +1312,20 @@ fn explain_txs(info: &IntermediumInfo) -> Result<()> {
             width = max_width
         );
     }
+    fn print_data_hash_diff(change: &ReprStateChange) {
+        if change.kind != "Changed" {
+            return;
+        }
+        if let (Some(old_hash), Some(new_hash)) =
+            (change.old_data_hash.as_ref(), change.new_data_hash.as_ref())
+        {
+            if old_hash == new_hash {
+                println!("    data_hash: {:#x} (unchanged)", new_hash);
+            } else {
+                println!("    data_hash: {:#x} -> {:#x}", old_hash, new_hash);
+            }
+        }
+    }
     fn print_tx_fee(
         tx: &json_types::Transaction,
         used_input_txs: &HashMap<H256, json_types::Transaction>,
@@ -1042,6 +1374,7 @@ fn explain_txs(info: &IntermediumInfo) -> Result<()> {
         .unwrap_or_default();
     for change in &info.cell_changes {
         print_item("cell", max_width, change);
+        print_data_hash_diff(change);
     }
     print_total_change(&info.cell_changes);
     if let Some(tx) = info.cell_tx.as_ref() {
@@ -1057,6 +1390,7 @@ fn explain_txs(info: &IntermediumInfo) -> Result<()> {
         .unwrap_or_default();
     for change in &info.dep_group_changes {
         print_item("dep_group", max_width, change);
+        print_data_hash_diff(change);
     }
     print_total_change(&info.dep_group_changes);
     if let Some(tx) = info.dep_group_tx.as_ref() {
@@ -22,6 +22,8 @@ pub enum StateChange<C, R> {
         old_recipe: R,
         old_type_id_args: Option<Bytes>,
         output_index: u64,
+        // Live capacity of the old cell (consumed as an input)
+        old_capacity: u64,
     },
     NewAdded {
         data: Bytes,
@@ -56,6 +58,33 @@ impl<C, R> StateChange<C, R> {
             StateChange::Reference { .. } => false,
         }
     }
+
+    // The old and new data hash shown by `explain-txs`
+    fn data_hashes<F>(&self, recipe_data_hash: F) -> (Option<H256>, Option<H256>)
+    where
+        F: Fn(&R) -> &H256,
+    {
+        match self {
+            StateChange::Changed {
+                data_hash,
+                old_recipe,
+                ..
+            }
+            | StateChange::Unchanged {
+                data_hash,
+                old_recipe,
+                ..
+            } => (
+                Some(recipe_data_hash(old_recipe).clone()),
+                Some(data_hash.clone()),
+            ),
+            StateChange::NewAdded { data_hash, .. } => (None, Some(data_hash.clone())),
+            StateChange::Reference { .. } => (None, None),
+            StateChange::Removed { old_recipe } => {
+                (Some(recipe_data_hash(old_recipe).clone()), None)
+            }
+        }
+    }
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
@@ -64,6 +93,10 @@ pub struct ReprStateChange {
     pub kind: String,
     pub old_capacity: u64,
     pub new_capacity: u64,
+    #[serde(default)]
+    pub old_data_hash: Option<H256>,
+    #[serde(default)]
+    pub new_data_hash: Option<H256>,
 }
 
 pub type CellChange = StateChange<Cell, CellRecipe>;
@@ -103,11 +136,14 @@ impl ChangeInfo for CellChange {
             StateChange::Reference { .. } => ("Reference", 0),
             StateChange::Removed { old_recipe } => ("Removed", old_recipe.occupied_capacity),
         };
+        let (old_data_hash, new_data_hash) = self.data_hashes(|recipe| &recipe.data_hash);
         ReprStateChange {
             name: self.name().clone(),
             kind: kind.to_string(),
             old_capacity,
             new_capacity,
+            old_data_hash,
+            new_data_hash,
         }
     }
 
@@ -298,11 +334,14 @@ impl ChangeInfo for DepGroupChange {
             StateChange::Reference { .. } => ("Reference", 0),
             StateChange::Removed { old_recipe } => ("Removed", old_recipe.occupied_capacity),
         };
+        let (old_data_hash, new_data_hash) = self.data_hashes(|recipe| &recipe.data_hash);
         ReprStateChange {
             name: self.name().clone(),
             kind: kind.to_string(),
             old_capacity,
             new_capacity,
+            old_data_hash,
+            new_data_hash,
         }
     }
 
@@ -1,29 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::{anyhow, Result};
 use ckb_sdk::{
     constants::{MULTISIG_TYPE_HASH, SIGHASH_TYPE_HASH},
     traits::{
         CellCollector, CellQueryOptions, DefaultCellCollector, DefaultHeaderDepResolver,
-        DefaultTransactionDependencyProvider, OffchainTransactionDependencyProvider, Signer,
-        SignerError, TransactionDependencyError, TransactionDependencyProvider, ValueRangeOption,
+        DefaultTransactionDependencyProvider, OffchainTransactionDependencyProvider,
+        TransactionDependencyError, TransactionDependencyProvider, ValueRangeOption,
     },
     tx_builder::{balance_tx_capacity, fill_placeholder_witnesses, CapacityBalancer},
     unlock::{
         MultisigConfig, ScriptUnlocker, SecpMultisigScriptSigner, SecpMultisigUnlocker,
         SecpSighashUnlocker,
     },
-    Address, ScriptId,
+    Address, HumanCapacity, ScriptId,
 };
 use ckb_types::{
     bytes::Bytes,
-    core::{HeaderView, TransactionBuilder, TransactionView},
+    core::{Capacity, FeeRate, HeaderView, TransactionBuilder, TransactionView},
     packed::{self, Byte32, CellOutput, OutPoint},
     prelude::*,
 };
 
 use super::state_change::ChangeInfo;
-use crate::utils::genesis_info::GenesisInfo;
+use crate::utils::{
+    genesis_info::GenesisInfo, other::get_live_cell_with_cache, rpc::HttpRpcClient,
+    signer::DummySigner,
+};
 
 // build balanced transaction
 pub fn build_tx<T: ChangeInfo>(
@@ -33,7 +36,7 @@ pub fn build_tx<T: ChangeInfo>(
     infos: &[T],
     pending_tx: Option<packed::Transaction>,
     genesis_info: &GenesisInfo,
-    ckb_rpc: &str,
+    rpc_client: &HttpRpcClient,
 ) -> Result<Option<packed::Transaction>> {
     let to_capacity: u64 = infos
         .iter()
@@ -44,7 +47,8 @@ pub fn build_tx<T: ChangeInfo>(
         return Ok(None);
     }
 
-    let mut cell_collector = DefaultCellCollector::new(ckb_rpc);
+    let ckb_rpc = rpc_client.url();
+    let mut cell_collector = DefaultCellCollector::new(rpc_client.indexer_url());
     if let Some(pending_tx) = pending_tx.as_ref() {
         cell_collector.apply_tx(pending_tx.clone())?;
     }
@@ -143,17 +147,230 @@ pub fn build_tx<T: ChangeInfo>(
     Ok(Some(balanced_tx.data()))
 }
 
-#[derive(Clone)]
-struct DummySigner {
-    args: Vec<Bytes>,
+// Build the transaction to pin: the changed cells and enough plain cells from `from_address` as
+// inputs, the result is the same as rebuilding it from its inputs by `build_pinned_tx`.
+#[allow(clippy::too_many_arguments)]
+pub fn build_new_pinned_tx<T: ChangeInfo>(
+    (from_address, fee_rate): (&Address, u64),
+    multisig_config: Option<&MultisigConfig>,
+    lock_script: &packed::Script,
+    infos: &[T],
+    pending_tx: Option<&packed::Transaction>,
+    genesis_info: &GenesisInfo,
+    rpc_client: &mut HttpRpcClient,
+) -> Result<Option<packed::Transaction>> {
+    let to_capacity: u64 = infos
+        .iter()
+        .filter(|info| info.has_new_output())
+        .map(|info| info.occupied_capacity(lock_script))
+        .sum();
+    if to_capacity == 0 {
+        return Ok(None);
+    }
+
+    let mut live_cell_cache = pending_live_cells(pending_tx);
+    let mut inputs = Vec::new();
+    for (input, _) in infos.iter().filter_map(|info| info.build_input()) {
+        let out_point = input.previous_output();
+        let (output, _) =
+            get_live_cell_with_cache(&mut live_cell_cache, rpc_client, out_point.clone(), false)
+                .map_err(|err| anyhow!(err))?;
+        inputs.push((out_point, output));
+    }
+
+    let mut cell_collector = DefaultCellCollector::new(rpc_client.indexer_url());
+    if let Some(pending_tx) = pending_tx {
+        cell_collector.apply_tx(pending_tx.clone())?;
+    }
+    let from_script = packed::Script::from(from_address.payload());
+    let cell_deps = pinned_cell_deps(multisig_config, genesis_info);
+    loop {
+        let lacking_capacity = if inputs.is_empty() {
+            to_capacity
+        } else {
+            match assemble_pinned_tx(
+                (from_address, fee_rate),
+                multisig_config,
+                lock_script,
+                infos,
+                &inputs,
+                cell_deps.clone(),
+            ) {
+                Ok(tx) => return Ok(Some(tx)),
+                Err(lacking_capacity) => lacking_capacity,
+            }
+        };
+        let mut query = CellQueryOptions::new_lock(from_script.clone());
+        query.secondary_script_len_range = Some(ValueRangeOption::new_exact(0));
+        query.data_len_range = Some(ValueRangeOption::new_exact(0));
+        query.min_total_capacity = lacking_capacity;
+        let (more_cells, _) = cell_collector.collect_live_cells(&query, true)?;
+        if more_cells.is_empty() {
+            return Err(anyhow!(
+                "Capacity(mature) not enough from {}, require {} more",
+                from_address,
+                HumanCapacity(lacking_capacity),
+            ));
+        }
+        inputs.extend(
+            more_cells
+                .into_iter()
+                .map(|cell| (cell.out_point, cell.output)),
+        );
+    }
+}
+
+// Rebuild transaction from exactly the pinned inputs (in order), the result only depends on the
+// deployment config, the pinned inputs and the fee rate, so it can be rebuilt for verification.
+#[allow(clippy::too_many_arguments)]
+pub fn build_pinned_tx<T: ChangeInfo>(
+    (from_address, fee_rate): (&Address, u64),
+    multisig_config: Option<&MultisigConfig>,
+    lock_script: &packed::Script,
+    infos: &[T],
+    pinned_inputs: &[packed::OutPoint],
+    pending_tx: Option<&packed::Transaction>,
+    genesis_info: &GenesisInfo,
+    rpc_client: &mut HttpRpcClient,
+) -> Result<Option<packed::Transaction>> {
+    if !infos.iter().any(|info| info.has_new_output()) {
+        return Ok(None);
+    }
+    if pinned_inputs.is_empty() {
+        return Err(anyhow!("No pinned inputs"));
+    }
+    for (input, _) in infos.iter().filter_map(|info| info.build_input()) {
+        if !pinned_inputs.contains(&input.previous_output()) {
+            return Err(anyhow!(
+                "Pinned inputs not include changed cell: {}",
+                input.previous_output()
+            ));
+        }
+    }
+
+    let mut live_cell_cache = pending_live_cells(pending_tx);
+    let mut inputs = Vec::with_capacity(pinned_inputs.len());
+    for out_point in pinned_inputs {
+        let (output, _) =
+            get_live_cell_with_cache(&mut live_cell_cache, rpc_client, out_point.clone(), false)
+                .map_err(|err| anyhow!(err))?;
+        inputs.push((out_point.clone(), output));
+    }
+    let tx = assemble_pinned_tx(
+        (from_address, fee_rate),
+        multisig_config,
+        lock_script,
+        infos,
+        &inputs,
+        pinned_cell_deps(multisig_config, genesis_info),
+    )
+    .map_err(|lacking_capacity| {
+        anyhow!(
+            "Pinned inputs capacity not enough, require {} more",
+            HumanCapacity(lacking_capacity)
+        )
+    })?;
+    Ok(Some(tx))
+}
+
+// Assemble the pinned transaction from resolved inputs, the rest capacity goes to a change output
+// of `from_address`. Return the lacking capacity if the inputs are not enough.
+fn assemble_pinned_tx<T: ChangeInfo>(
+    (from_address, fee_rate): (&Address, u64),
+    multisig_config: Option<&MultisigConfig>,
+    lock_script: &packed::Script,
+    infos: &[T],
+    inputs: &[(packed::OutPoint, packed::CellOutput)],
+    cell_deps: Vec<packed::CellDep>,
+) -> std::result::Result<packed::Transaction, u64> {
+    let mut cell_inputs = Vec::with_capacity(inputs.len());
+    let mut witnesses = Vec::with_capacity(inputs.len());
+    let mut input_total: u64 = 0;
+    let mut lock_groups = HashSet::new();
+    for (out_point, output) in inputs {
+        let capacity: u64 = output.capacity().unpack();
+        input_total += capacity;
+        let lock = output.lock();
+        let witness = if lock_groups.insert(lock.calc_script_hash()) {
+            match multisig_config {
+                Some(cfg) if lock.code_hash().as_slice() == MULTISIG_TYPE_HASH.as_bytes() => {
+                    cfg.placeholder_witness().as_bytes()
+                }
+                _ => packed::WitnessArgs::new_builder()
+                    .lock(Some(Bytes::from(vec![0u8; 65])).pack())
+                    .build()
+                    .as_bytes(),
+            }
+        } else {
+            Bytes::new()
+        };
+        cell_inputs.push(packed::CellInput::new(out_point.clone(), 0));
+        witnesses.push(witness);
+    }
+
+    let first_cell_input = &cell_inputs[0];
+    let (mut outputs, mut outputs_data): (Vec<_>, Vec<_>) = infos
+        .iter()
+        .filter_map(|info| info.build_cell_output(lock_script, first_cell_input))
+        .unzip();
+    let output_total: u64 = outputs
+        .iter()
+        .map(|output| Unpack::<u64>::unpack(&output.capacity()))
+        .sum();
+    let from_script = packed::Script::from(from_address.payload());
+    let change_output = packed::CellOutput::new_builder().lock(from_script).build();
+    let change_occupied: u64 = change_output
+        .occupied_capacity(Capacity::zero())
+        .expect("change occupied capacity")
+        .as_u64();
+    outputs.push(change_output.clone());
+    outputs_data.push(Bytes::new());
+
+    let tx = TransactionBuilder::default()
+        .cell_deps(cell_deps)
+        .inputs(cell_inputs)
+        .outputs(outputs.clone())
+        .outputs_data(outputs_data.into_iter().map(|data| data.pack()))
+        .witnesses(witnesses.into_iter().map(|witness| witness.pack()))
+        .build();
+    let tx_size = tx.data().as_reader().serialized_size_in_block() as u64;
+    let fee = FeeRate::from_u64(fee_rate).fee(tx_size).as_u64();
+    let required = output_total + fee + change_occupied;
+    if input_total < required {
+        return Err(required - input_total);
+    }
+    let last_idx = outputs.len() - 1;
+    outputs[last_idx] = change_output
+        .as_builder()
+        .capacity((input_total - output_total - fee).pack())
+        .build();
+    Ok(tx.as_advanced_builder().set_outputs(outputs).build().data())
 }
-impl Signer for DummySigner {
-    fn match_id(&self, id: &[u8]) -> bool {
-        self.args.iter().any(|arg| arg.as_ref() == id)
+
+fn pinned_cell_deps(
+    multisig_config: Option<&MultisigConfig>,
+    genesis_info: &GenesisInfo,
+) -> Vec<packed::CellDep> {
+    let mut cell_deps = vec![genesis_info.sighash_dep()];
+    if multisig_config.is_some() {
+        cell_deps.push(genesis_info.multisig_dep());
     }
-    fn sign(&self, _: &[u8], _: &[u8], _: bool, _: &TransactionView) -> Result<Bytes, SignerError> {
-        unreachable!()
+    cell_deps
+}
+
+// Outputs of the pending transaction are not on chain yet
+fn pending_live_cells(
+    pending_tx: Option<&packed::Transaction>,
+) -> HashMap<(OutPoint, bool), (CellOutput, Bytes)> {
+    let mut live_cell_cache = HashMap::default();
+    if let Some(pending_tx) = pending_tx {
+        let tx_view = pending_tx.clone().into_view();
+        for (output_idx, (output, output_data)) in tx_view.outputs_with_data_iter().enumerate() {
+            let out_point = packed::OutPoint::new(tx_view.hash(), output_idx as u32);
+            live_cell_cache.insert((out_point, false), (output, output_data));
+        }
     }
+    live_cell_cache
 }
 
 struct TxDepProviderWrapper {
@@ -186,3 +403,131 @@ impl TransactionDependencyProvider for TxDepProviderWrapper {
             .or_else(|_| self.inner.get_header(block_hash))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subcommands::deploy::{
+        deployment::{Cell, CellLocation, CellRecipe},
+        state_change::CellChange,
+    };
+    use ckb_hash::blake2b_256;
+    use ckb_sdk::{constants::ONE_CKB, AddressPayload, NetworkType};
+    use ckb_types::{h160, h256, H256};
+
+    fn test_input(tx_hash: H256, lock: &packed::Script, capacity: u64) -> (OutPoint, CellOutput) {
+        let output = packed::CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock.clone())
+            .build();
+        (packed::OutPoint::new(tx_hash.pack(), 0), output)
+    }
+
+    #[test]
+    fn test_assemble_pinned_tx() {
+        let from_address = Address::new(
+            NetworkType::Testnet,
+            AddressPayload::from_pubkey_hash(h160!("0x1")),
+            true,
+        );
+        let from_script = packed::Script::from(from_address.payload());
+        let lock_script = from_script.clone();
+        let cell_deps = vec![packed::CellDep::new_builder()
+            .out_point(packed::OutPoint::new(h256!("0xde").pack(), 0))
+            .build()];
+        let data = Bytes::from(vec![1u8; 100]);
+        let infos = vec![
+            CellChange::NewAdded {
+                data_hash: H256::from(blake2b_256(&data)),
+                data: data.clone(),
+                config: Cell {
+                    name: "my_cell".to_string(),
+                    location: CellLocation::File {
+                        file: "my_cell".to_string(),
+                    },
+                    enable_type_id: true,
+                },
+                output_index: 0,
+            },
+            CellChange::Changed {
+                data_hash: H256::from(blake2b_256(&data)),
+                data,
+                config: Cell {
+                    name: "old_cell".to_string(),
+                    location: CellLocation::File {
+                        file: "old_cell".to_string(),
+                    },
+                    enable_type_id: false,
+                },
+                old_recipe: CellRecipe {
+                    name: "old_cell".to_string(),
+                    tx_hash: h256!("0x1"),
+                    index: 0,
+                    occupied_capacity: 10_000_000_000,
+                    data_hash: h256!("0xd1"),
+                    type_id: None,
+                },
+                old_type_id_args: None,
+                output_index: 1,
+                old_capacity: 10_000_000_000,
+            },
+        ];
+        let inputs = vec![
+            test_input(h256!("0x1"), &lock_script, 10_000_000_000),
+            test_input(h256!("0x2"), &from_script, 500_000_000_000),
+        ];
+        let build = |inputs: &[(OutPoint, CellOutput)]| {
+            assemble_pinned_tx(
+                (&from_address, 1000),
+                None,
+                &lock_script,
+                &infos,
+                inputs,
+                cell_deps.clone(),
+            )
+        };
+
+        // Same inputs always build the same transaction
+        let tx = build(&inputs).unwrap();
+        assert_eq!(build(&inputs).unwrap().calc_tx_hash(), tx.calc_tx_hash());
+        let raw = tx.raw();
+        assert_eq!(
+            raw.inputs()
+                .into_iter()
+                .map(|input| input.previous_output())
+                .collect::<Vec<_>>(),
+            inputs
+                .iter()
+                .map(|(out_point, _)| out_point.clone())
+                .collect::<Vec<_>>()
+        );
+        // Only the first witness of the lock group is a placeholder
+        assert_eq!(tx.witnesses().len(), 2);
+        assert!(tx.witnesses().get(1).unwrap().is_empty());
+
+        let input_total = 510_000_000_000u64;
+        let output_total: u64 = raw
+            .outputs()
+            .into_iter()
+            .map(|output| Unpack::<u64>::unpack(&output.capacity()))
+            .sum();
+        let tx_size = tx.as_reader().serialized_size_in_block() as u64;
+        assert_eq!(
+            input_total - output_total,
+            FeeRate::from_u64(1000).fee(tx_size).as_u64()
+        );
+        let change = raw.outputs().get(2).unwrap();
+        assert_eq!(change.lock(), from_script);
+
+        // Report the lacking capacity when the inputs are not enough, one more input also
+        // increases the fee a little.
+        let lacking = build(&inputs[..1]).unwrap_err();
+        let more = vec![
+            inputs[0].clone(),
+            test_input(h256!("0x2"), &from_script, lacking + ONE_CKB),
+        ];
+        let tx = build(&more).unwrap();
+        let change: u64 = tx.raw().outputs().get(2).unwrap().capacity().unpack();
+        assert!(change >= 61 * ONE_CKB);
+    }
+}
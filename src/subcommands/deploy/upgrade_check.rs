@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use ckb_sdk::{
+    traits::{
+        CellCollector, CellQueryOptions, DefaultCellCollector, MaturityOption, PrimaryScriptType,
+    },
+    HumanCapacity,
+};
+use ckb_types::{core::ScriptHashType, packed, prelude::*, H256};
+
+use super::deployment::{Cell, CellRecipe};
+use super::state_change::{CellChange, ChangeInfo, StateChange};
+
+type DataReferences = HashMap<H256, Vec<(&'static str, &'static str)>>;
+
+/// Changing `enable_type_id` of an existing cell changes how it's referenced by every dependent
+/// script, it must be deployed as a new cell.
+pub fn check_type_id(cell: &Cell, old_recipe: &CellRecipe) -> Result<()> {
+    if old_recipe.type_id.is_some() != cell.enable_type_id {
+        return Err(anyhow!(
+            "Can not change enable_type_id of existing cell: {} (from {} to {}), please deploy it as a new cell",
+            cell.name,
+            old_recipe.type_id.is_some(),
+            cell.enable_type_id
+        ));
+    }
+    Ok(())
+}
+
+/// Check the cell changes before building the cell transaction, returns warning messages.
+///
+///   * Changed cells whose old data hash is still referenced by live scripts with
+///     `hash_type = data/data1` (those scripts will still require the old binary)
+///   * Changed cells require more capacity than the old cell provides
+pub fn check_cell_changes(
+    ckb_rpc: &str,
+    lock_script: &packed::Script,
+    cell_changes: &[CellChange],
+) -> Result<Vec<String>> {
+    let mut cell_collector = DefaultCellCollector::new(ckb_rpc);
+    cell_change_warnings(lock_script, cell_changes, |data_hashes| {
+        find_data_references(&mut cell_collector, data_hashes)
+    })
+}
+
+fn cell_change_warnings<F>(
+    lock_script: &packed::Script,
+    cell_changes: &[CellChange],
+    find_references: F,
+) -> Result<Vec<String>>
+where
+    F: FnOnce(&[&H256]) -> Result<DataReferences>,
+{
+    // The references of all replaced data hashes are searched at once, each hash only once
+    let mut old_data_hashes = Vec::new();
+    for change in cell_changes {
+        if let StateChange::Changed {
+            data_hash,
+            old_recipe,
+            ..
+        } = change
+        {
+            if data_hash != &old_recipe.data_hash
+                && !old_data_hashes.contains(&&old_recipe.data_hash)
+            {
+                old_data_hashes.push(&old_recipe.data_hash);
+            }
+        }
+    }
+    let references = if old_data_hashes.is_empty() {
+        HashMap::default()
+    } else {
+        find_references(&old_data_hashes)?
+    };
+
+    let mut warnings = Vec::new();
+    for change in cell_changes {
+        if let StateChange::Changed {
+            data_hash,
+            old_recipe,
+            old_capacity,
+            ..
+        } = change
+        {
+            if data_hash != &old_recipe.data_hash {
+                for (hash_type, script_type) in references
+                    .get(&old_recipe.data_hash)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                {
+                    warnings.push(format!(
+                        "cell {} old data hash {:#x} is referenced by live {} scripts with hash_type = {}",
+                        change.name(),
+                        old_recipe.data_hash,
+                        script_type,
+                        hash_type,
+                    ));
+                }
+            }
+            let new_capacity = change.occupied_capacity(lock_script);
+            if new_capacity > *old_capacity {
+                warnings.push(format!(
+                    "cell {} requires more capacity than the old cell provides, old: {}, new: {}, extra {} will be collected from from-address",
+                    change.name(),
+                    HumanCapacity(*old_capacity),
+                    HumanCapacity(new_capacity),
+                    HumanCapacity(new_capacity - old_capacity),
+                ));
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+// Search the indexer (prefix search with empty args) for any live cell using the data hashes as
+// code_hash, returns the (hash_type, script_type) pairs found for each data hash.
+fn find_data_references(
+    cell_collector: &mut DefaultCellCollector,
+    data_hashes: &[&H256],
+) -> Result<DataReferences> {
+    let mut references = HashMap::default();
+    for data_hash in data_hashes {
+        let mut found = Vec::new();
+        for (hash_type, hash_type_name) in [
+            (ScriptHashType::Data, "data"),
+            (ScriptHashType::Data1, "data1"),
+        ] {
+            for (script_type, script_type_name) in [
+                (PrimaryScriptType::Lock, "lock"),
+                (PrimaryScriptType::Type, "type"),
+            ] {
+                let script = packed::Script::new_builder()
+                    .code_hash(data_hash.pack())
+                    .hash_type(hash_type.into())
+                    .build();
+                let mut query = CellQueryOptions::new(script, script_type);
+                query.maturity = MaturityOption::Both;
+                query.limit = Some(1);
+                let (cells, _) = cell_collector.collect_live_cells(&query, false)?;
+                if !cells.is_empty() {
+                    found.push((hash_type_name, script_type_name));
+                }
+            }
+        }
+        references.insert((*data_hash).clone(), found);
+    }
+    Ok(references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subcommands::deploy::deployment::CellLocation;
+    use ckb_sdk::constants::ONE_CKB;
+    use ckb_types::{bytes::Bytes, h256};
+
+    fn test_cell(name: &str, enable_type_id: bool) -> Cell {
+        Cell {
+            name: name.to_string(),
+            location: CellLocation::File {
+                file: name.to_string(),
+            },
+            enable_type_id,
+        }
+    }
+
+    fn test_recipe(name: &str, data_hash: H256, type_id: Option<H256>) -> CellRecipe {
+        CellRecipe {
+            name: name.to_string(),
+            tx_hash: h256!("0x1"),
+            index: 0,
+            occupied_capacity: 0,
+            data_hash,
+            type_id,
+        }
+    }
+
+    fn test_change(
+        name: &str,
+        data_len: usize,
+        old_data_hash: H256,
+        old_capacity: u64,
+    ) -> CellChange {
+        StateChange::Changed {
+            data: Bytes::from(vec![0u8; data_len]),
+            data_hash: h256!("0xaa"),
+            config: test_cell(name, false),
+            old_recipe: test_recipe(name, old_data_hash, None),
+            old_type_id_args: None,
+            output_index: 0,
+            old_capacity,
+        }
+    }
+
+    #[test]
+    fn test_check_type_id() {
+        let type_id = Some(h256!("0xe1"));
+        assert!(check_type_id(
+            &test_cell("a", true),
+            &test_recipe("a", h256!("0xd1"), type_id.clone())
+        )
+        .is_ok());
+        assert!(check_type_id(
+            &test_cell("a", false),
+            &test_recipe("a", h256!("0xd1"), None)
+        )
+        .is_ok());
+        let err = check_type_id(
+            &test_cell("a", false),
+            &test_recipe("a", h256!("0xd1"), type_id),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("(from true to false)"));
+        assert!(check_type_id(
+            &test_cell("a", true),
+            &test_recipe("a", h256!("0xd1"), None)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_cell_change_warnings() {
+        let lock_script = packed::Script::default();
+        // Script::default() occupies 32 + 1 bytes, plus 8 bytes capacity
+        let capacity = |data_len: u64| (33 + 8 + data_len) * ONE_CKB;
+        let cell_changes = vec![
+            test_change("a", 100, h256!("0xd1"), capacity(100)),
+            // Same old data hash, only searched once
+            test_change("b", 100, h256!("0xd1"), capacity(100)),
+            test_change("c", 200, h256!("0xd2"), capacity(100)),
+            // Data unchanged, never searched
+            test_change("d", 100, h256!("0xaa"), capacity(100)),
+            StateChange::NewAdded {
+                data: Bytes::from(vec![0u8; 1000]),
+                data_hash: h256!("0xd3"),
+                config: test_cell("e", false),
+                output_index: 1,
+            },
+        ];
+        let mut searched = Vec::new();
+        let warnings = cell_change_warnings(&lock_script, &cell_changes, |data_hashes| {
+            searched.extend(data_hashes.iter().map(|hash| (*hash).clone()));
+            let mut references = HashMap::default();
+            references.insert(h256!("0xd1"), vec![("data1", "type")]);
+            references.insert(h256!("0xd2"), Vec::new());
+            Ok(references)
+        })
+        .unwrap();
+        assert_eq!(searched, vec![h256!("0xd1"), h256!("0xd2")]);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("cell a old data hash"));
+        assert!(warnings[0].ends_with("referenced by live type scripts with hash_type = data1"));
+        assert!(warnings[1].starts_with("cell b old data hash"));
+        assert!(warnings[2].starts_with("cell c requires more capacity"));
+        assert!(warnings[2].contains("extra 100.0 will be collected"));
+
+        // No search if no data hash is replaced
+        let warnings = cell_change_warnings(&lock_script, &cell_changes[3..], |_| {
+            panic!("unexpected search")
+        })
+        .unwrap();
+        assert!(warnings.is_empty());
+    }
+}
@@ -1,10 +1,12 @@
 pub mod account;
+pub mod address_book;
 pub mod api_server;
 pub mod dao;
 pub mod deploy;
 pub mod mock_tx;
 pub mod molecule;
 pub mod plugin;
+pub mod profile;
 pub mod pubsub;
 pub mod rpc;
 pub mod sudt;
@@ -13,12 +15,14 @@ pub mod util;
 pub mod wallet;
 
 pub use account::AccountSubCommand;
+pub use address_book::AddressBookSubCommand;
 pub use api_server::ApiServerSubCommand;
 pub use dao::DAOSubCommand;
 pub use deploy::DeploySubCommand;
 pub use mock_tx::MockTxSubCommand;
 pub use molecule::MoleculeSubCommand;
 pub use plugin::PluginSubCommand;
+pub use profile::ProfileSubCommand;
 pub use pubsub::PubSubCommand;
 pub use rpc::RpcSubCommand;
 pub use sudt::SudtSubCommand;
@@ -29,7 +33,7 @@ pub use wallet::{TransferArgs, WalletSubCommand};
 use clap::ArgMatches;
 use serde::Serialize;
 
-use crate::utils::printer::{OutputFormat, Printable};
+use crate::utils::printer::{OutputFormat, OutputQuery, Printable};
 
 pub struct Output {
     stdout: Option<serde_json::Value>,
@@ -62,9 +66,19 @@ impl Output {
         }
     }
 
-    pub fn print(&self, format: OutputFormat, color: bool) {
+    pub fn stdout(&self) -> Option<&serde_json::Value> {
+        self.stdout.as_ref()
+    }
+
+    /// The query only applies to stdout
+    pub fn print(
+        &self,
+        format: OutputFormat,
+        color: bool,
+        query: &OutputQuery,
+    ) -> Result<(), String> {
         if let Some(ref stdout) = self.stdout {
-            println!("{}", stdout.render(format, color));
+            println!("{}", query.render(stdout, format, color)?);
         }
         if let Some(ref stderr) = self.stderr {
             eprintln!("{}", stderr.render(format, color));
@@ -75,6 +89,7 @@ impl Output {
             });
             eprintln!("{}", resp.render(OutputFormat::Yaml, color));
         }
+        Ok(())
     }
 }
 
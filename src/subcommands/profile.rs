@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use clap::{App, Arg, ArgMatches};
+
+use super::{CliSubCommand, Output};
+use crate::utils::{
+    arg_parser::{ArgParser, FilePathParser, FromStrParser, UrlParser},
+    config::{parse_network_name, url_with_scheme, GlobalConfig, NetworkProfile},
+    other::get_network_type,
+    rpc::HttpRpcClient,
+};
+
+pub struct ProfileSubCommand<'a> {
+    config: &'a mut GlobalConfig,
+    config_file: &'a Path,
+}
+
+impl<'a> ProfileSubCommand<'a> {
+    pub fn new(config: &'a mut GlobalConfig, config_file: &'a Path) -> ProfileSubCommand<'a> {
+        ProfileSubCommand {
+            config,
+            config_file,
+        }
+    }
+
+    pub fn subcommand(name: &'static str) -> App<'static> {
+        let arg_profile_name = Arg::with_name("name")
+            .long("name")
+            .required(true)
+            .takes_value(true)
+            .about("Profile name");
+        App::new(name)
+            .about("Manage named network profiles (node url, indexer url, network type, cell deps and fee rate)")
+            .subcommands(vec![
+                App::new("add")
+                    .about("Add a profile, use `--force` to replace an existing one")
+                    .arg(arg_profile_name.clone())
+                    .arg(
+                        Arg::with_name("url")
+                            .long("url")
+                            .required(true)
+                            .takes_value(true)
+                            .validator(|input| UrlParser.validate(input))
+                            .about("CKB RPC server url"),
+                    )
+                    .arg(
+                        Arg::with_name("indexer-url")
+                            .long("indexer-url")
+                            .takes_value(true)
+                            .validator(|input| UrlParser.validate(input))
+                            .about("CKB indexer RPC url (default is the node url)"),
+                    )
+                    .arg(
+                        Arg::with_name("network")
+                            .long("network")
+                            .takes_value(true)
+                            .possible_values(&["mainnet", "testnet", "staging", "dev"])
+                            .about("The expected network type, commands fail if the node is in another network"),
+                    )
+                    .arg(
+                        Arg::with_name("cell-deps")
+                            .long("cell-deps")
+                            .takes_value(true)
+                            .validator(|input| FilePathParser::new(true).validate(input))
+                            .about("The default cell deps file of sudt subcommands (the absolute path is saved)"),
+                    )
+                    .arg(
+                        Arg::with_name("fee-rate")
+                            .long("fee-rate")
+                            .takes_value(true)
+                            .validator(|input| FromStrParser::<u64>::default().validate(input))
+                            .about("The default transaction fee rate (unit: shannons/KB)"),
+                    )
+                    .arg(
+                        Arg::with_name("force")
+                            .long("force")
+                            .about("Replace the profile if it already exists"),
+                    ),
+                App::new("use")
+                    .about("Switch to a profile")
+                    .arg(arg_profile_name),
+                App::new("list").about("List all profiles"),
+            ])
+    }
+
+    fn save(&self) -> Result<(), String> {
+        self.config
+            .save(self.config_file)
+            .map_err(|err| format!("save config file failed: {:?}", err))
+    }
+}
+
+impl<'a> CliSubCommand for ProfileSubCommand<'a> {
+    fn process(&mut self, matches: &ArgMatches, _debug: bool) -> Result<Output, String> {
+        match matches.subcommand() {
+            ("add", Some(m)) => {
+                let name = m.value_of("name").unwrap().to_string();
+                if self.config.profiles().contains_key(&name) && !m.is_present("force") {
+                    return Err(format!(
+                        "Profile {} already exists, use --force to replace it",
+                        name
+                    ));
+                }
+                let cell_deps: Option<PathBuf> =
+                    FilePathParser::new(true).from_matches_opt(m, "cell-deps")?;
+                let profile = NetworkProfile {
+                    url: url_with_scheme(m.value_of("url").unwrap().to_string()),
+                    indexer_url: m.value_of("indexer-url").map(ToString::to_string),
+                    network: m.value_of("network").map(ToString::to_string),
+                    cell_deps: cell_deps
+                        .map(|path| path.canonicalize().map_err(|err| err.to_string()))
+                        .transpose()?,
+                    fee_rate: FromStrParser::<u64>::default().from_matches_opt(m, "fee-rate")?,
+                };
+                if let Some(network) = profile.network.as_deref() {
+                    parse_network_name(network)?;
+                }
+                self.config.add_profile(name.clone(), profile.clone());
+                self.save()?;
+                Ok(Output::new_output(serde_json::json!({ name: profile })))
+            }
+            ("use", Some(m)) => {
+                let name = m.value_of("name").unwrap();
+                let profile = self
+                    .config
+                    .profiles()
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("Profile not found: {}", name))?;
+                let mut rpc_client = HttpRpcClient::from_profile(profile.clone());
+                let network = get_network_type(&mut rpc_client).ok();
+                profile.check_network(network)?;
+                if network.is_none() {
+                    eprintln!(
+                        "WARNING: get network type from {} failed, the network is not checked",
+                        profile.url
+                    );
+                }
+                self.config.use_profile(name)?;
+                self.config.set_network(network);
+                self.save()?;
+                Ok(Output::new_output(serde_json::json!({ name: profile })))
+            }
+            ("list", Some(_)) => {
+                let active = self.config.profile();
+                let resp = self
+                    .config
+                    .profiles()
+                    .iter()
+                    .map(|(name, profile)| {
+                        serde_json::json!({
+                            "name": name,
+                            "active": Some(name.as_str()) == active,
+                            "url": profile.url,
+                            "indexer_url": profile.indexer_url,
+                            "network": profile.network,
+                            "cell_deps": profile.cell_deps,
+                            "fee_rate": profile.fee_rate,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(Output::new_output(resp))
+            }
+            _ => Err(Self::subcommand("profile").generate_usage()),
+        }
+    }
+}
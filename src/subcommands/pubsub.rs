@@ -8,16 +8,19 @@ use tokio::net::TcpStream;
 
 use super::{CliSubCommand, Output};
 use crate::utils::arg_parser::{ArgParser, SocketParser};
+use crate::utils::printer::OutputQuery;
 use crate::OutputFormat;
 
 macro_rules! block_on {
-    ($addr:ident, $topic:expr, $output:ty, $format:expr, $color:expr) => {{
+    ($addr:ident, $topic:expr, $output:ty, $format:expr, $color:expr, $query:expr) => {{
         let rt = tokio::runtime::Runtime::new().unwrap();
         let ret: io::Result<Output> = rt.block_on(async {
             let c = new_tcp_client($addr).await?;
             let mut h = c.subscribe_list::<$output, _, _>($topic).await.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "not a subcribe port, please set ckb `tcp_listen_address` to use subcribe rpc feature"))?;
             while let Some(Ok((topic, r))) = h.next().await {
-                Output::new_output(SubOutputFormat::new(topic, r)).print($format, $color);
+                if let Err(err) = Output::new_output(SubOutputFormat::new(topic, r)).print($format, $color, $query) {
+                    eprintln!("{}", err);
+                }
                 println!("");
             }
             Ok(Output::new_success())
@@ -29,11 +32,16 @@ macro_rules! block_on {
 pub struct PubSubCommand {
     format: OutputFormat,
     color: bool,
+    query: OutputQuery,
 }
 
 impl PubSubCommand {
-    pub fn new(format: OutputFormat, color: bool) -> Self {
-        PubSubCommand { format, color }
+    pub fn new(format: OutputFormat, color: bool, query: OutputQuery) -> Self {
+        PubSubCommand {
+            format,
+            color,
+            query,
+        }
     }
 
     pub fn subcommand() -> App<'static> {
@@ -93,7 +101,8 @@ impl CliSubCommand for PubSubCommand {
                     vec!["new_tip_header"].iter(),
                     HeaderView,
                     self.format,
-                    self.color
+                    self.color,
+                    &self.query
                 );
                 ret.map_err(|e| e.to_string())
             }
@@ -104,7 +113,8 @@ impl CliSubCommand for PubSubCommand {
                     vec!["new_tip_block"].iter(),
                     BlockView,
                     self.format,
-                    self.color
+                    self.color,
+                    &self.query
                 );
                 ret.map_err(|e| e.to_string())
             }
@@ -115,7 +125,8 @@ impl CliSubCommand for PubSubCommand {
                     vec!["new_transaction"].iter(),
                     PoolTransactionEntry,
                     self.format,
-                    self.color
+                    self.color,
+                    &self.query
                 );
                 ret.map_err(|e| e.to_string())
             }
@@ -126,7 +137,8 @@ impl CliSubCommand for PubSubCommand {
                     vec!["proposed_transaction"].iter(),
                     PoolTransactionEntry,
                     self.format,
-                    self.color
+                    self.color,
+                    &self.query
                 );
                 ret.map_err(|e| e.to_string())
             }
@@ -137,14 +149,22 @@ impl CliSubCommand for PubSubCommand {
                     vec!["rejected_transaction"].iter(),
                     (PoolTransactionEntry, PoolTransactionReject),
                     self.format,
-                    self.color
+                    self.color,
+                    &self.query
                 );
                 ret.map_err(|e| e.to_string())
             }
             ("list", Some(m)) => {
                 let tcp: SocketAddr = SocketParser.from_matches(m, "tcp")?;
                 let list: Vec<_> = m.values_of("topics").unwrap().collect();
-                let ret = block_on!(tcp, list.iter(), ListOutput, self.format, self.color);
+                let ret = block_on!(
+                    tcp,
+                    list.iter(),
+                    ListOutput,
+                    self.format,
+                    self.color,
+                    &self.query
+                );
                 ret.map_err(|e| e.to_string())
             }
             _ => Err(Self::subcommand().generate_usage()),
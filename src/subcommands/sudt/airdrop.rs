@@ -0,0 +1,610 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use ckb_jsonrpc_types as json_types;
+use ckb_sdk::{
+    constants::SIGHASH_TYPE_HASH,
+    traits::{CellCollector, CellQueryOptions},
+    tx_builder::{
+        udt::{UdtIssueBuilder, UdtTargetReceiver, UdtTransferBuilder},
+        TransferAction, TxBuilder,
+    },
+    types::ScriptId,
+    Address, AddressPayload,
+};
+use ckb_types::{
+    bytes::Bytes,
+    core::{ScriptHashType, TransactionView},
+    packed::Script,
+    prelude::*,
+    H160, H256,
+};
+use serde_derive::{Deserialize, Serialize};
+
+use super::{get_script_id, get_udt_script_id, SudtCommonArgs, SudtSubCommand, UdtTxBuilder};
+use crate::subcommands::Output;
+use crate::utils::{
+    arg_parser::{ArgParser, UdtTargetParser},
+    cell_dep::CellDepName,
+};
+
+/// A conservative transaction size limit, below the block size limit (597,000 bytes)
+pub const MAX_AIRDROP_TX_SIZE: usize = 512_000;
+/// The default `max_tx_verify_cycles` of the tx-pool
+pub const MAX_AIRDROP_TX_CYCLES: u64 = 70_000_000;
+const COMMIT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AirdropRecipient {
+    /// Line number in the recipients file (starts from 1)
+    pub line: usize,
+    pub address: Address,
+    pub amount: u128,
+}
+
+/// Parse the recipients file, one `{address},{amount}` per line.
+///
+/// Empty lines, lines start with `#` and an optional `address,amount` header are skipped. All
+/// invalid lines are reported together, and an address can only appear once.
+pub fn parse_recipients(
+    content: &str,
+    parser: &UdtTargetParser,
+) -> Result<Vec<AirdropRecipient>, String> {
+    let mut recipients = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen: HashMap<Script, usize> = HashMap::new();
+    for (idx, row) in content.lines().enumerate() {
+        let line = idx + 1;
+        let row = row.trim();
+        if row.is_empty() || row.starts_with('#') {
+            continue;
+        }
+        if seen.is_empty()
+            && errors.is_empty()
+            && row.replace(' ', "").eq_ignore_ascii_case("address,amount")
+        {
+            continue;
+        }
+        let target = match row.split_once(',') {
+            Some((address, amount)) => format!("{}:{}", address.trim(), amount.trim()),
+            None => {
+                errors.push(format!("line {}: expected {{address}},{{amount}}", line));
+                continue;
+            }
+        };
+        let (address, amount) = match parser.parse(&target) {
+            Ok(target) => target,
+            Err(err) => {
+                errors.push(format!("line {}: {}", line, err));
+                continue;
+            }
+        };
+        if amount == 0 {
+            errors.push(format!("line {}: amount is zero", line));
+            continue;
+        }
+        let lock_script = Script::from(&address);
+        if let Some(first_line) = seen.get(&lock_script) {
+            errors.push(format!(
+                "line {}: duplicated address, first appears at line {}",
+                line, first_line
+            ));
+            continue;
+        }
+        seen.insert(lock_script, line);
+        recipients.push(AirdropRecipient {
+            line,
+            address,
+            amount,
+        });
+    }
+    if !errors.is_empty() {
+        return Err(format!("Invalid recipients file:\n{}", errors.join("\n")));
+    }
+    if recipients.is_empty() {
+        return Err("No recipient in the recipients file".to_string());
+    }
+    Ok(recipients)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AirdropBatchStatus {
+    /// Sent to the node (or about to be sent) but not committed yet
+    Pending,
+    Committed,
+    /// Rejected or disappeared from the node, the recipients will be sent again
+    Dropped,
+}
+
+/// A transaction sent by `sudt airdrop`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AirdropBatch {
+    pub tx_hash: H256,
+    /// Line numbers of the recipients in the recipients file
+    pub lines: Vec<usize>,
+    pub status: AirdropBatchStatus,
+}
+
+/// The progress of `sudt airdrop`, saved in the state file
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AirdropState {
+    /// Hash of the recipients file, a state file only works with the file it is created for
+    pub file_hash: H256,
+    #[serde(default)]
+    pub batches: Vec<AirdropBatch>,
+}
+
+impl AirdropState {
+    pub fn load(path: &Path, file_hash: &H256) -> Result<AirdropState, String> {
+        if !path.exists() {
+            return Ok(AirdropState {
+                file_hash: file_hash.clone(),
+                batches: Vec::new(),
+            });
+        }
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let state: AirdropState = serde_json::from_str(&content)
+            .map_err(|err| format!("Invalid state file {}: {}", path.display(), err))?;
+        if &state.file_hash != file_hash {
+            return Err(format!(
+                "State file {} is created for another recipients file",
+                path.display()
+            ));
+        }
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        // Write to a temporary file first, so that the state file is never half written
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, content).map_err(|err| err.to_string())?;
+        fs::rename(&tmp_path, path).map_err(|err| err.to_string())
+    }
+
+    /// Lines of the recipients in pending or committed batches
+    fn sent_lines(&self) -> HashSet<usize> {
+        self.batches
+            .iter()
+            .filter(|batch| batch.status != AirdropBatchStatus::Dropped)
+            .flat_map(|batch| batch.lines.iter().cloned())
+            .collect()
+    }
+}
+
+pub(super) struct AirdropArgs {
+    pub owner: Address,
+    /// Issue the UDT by <owner> when no sender is given
+    pub sender: Option<Address>,
+    pub recipients: Vec<AirdropRecipient>,
+    pub file_hash: H256,
+    pub state_file: PathBuf,
+    /// Send to the recipient's anyone-can-pay cell if it exists, otherwise create a cheque cell
+    pub cheque_fallback: bool,
+    pub batch_size: usize,
+}
+
+impl<'a> SudtSubCommand<'a> {
+    pub(super) fn airdrop(
+        &mut self,
+        args: AirdropArgs,
+        common_args: SudtCommonArgs,
+    ) -> Result<Output, String> {
+        let mut state = AirdropState::load(&args.state_file, &args.file_hash)?;
+        // The recipients of a pending batch must not be sent again, and its inputs are not
+        // available before it is committed.
+        for idx in 0..state.batches.len() {
+            if state.batches[idx].status == AirdropBatchStatus::Pending {
+                let tx_hash = state.batches[idx].tx_hash.clone();
+                eprintln!("[airdrop] waiting for pending transaction {:#x}", tx_hash);
+                state.batches[idx].status = self.wait_committed(&tx_hash)?;
+                state.save(&args.state_file)?;
+            }
+        }
+
+        let sent_lines = state.sent_lines();
+        let mut remaining: Vec<&AirdropRecipient> = args
+            .recipients
+            .iter()
+            .filter(|recipient| !sent_lines.contains(&recipient.line))
+            .collect();
+        let mut passwords = HashMap::new();
+        let mut batch_size = args.batch_size;
+        while !remaining.is_empty() {
+            let (count, tx) = build_batch(&mut batch_size, &remaining, |recipients| {
+                // The cells collected for a discarded transaction are still locked in the collector
+                self.cell_collector.reset();
+                let tx = self.build_airdrop_tx(&args, &common_args, recipients, &mut passwords)?;
+                let reason = self.check_tx_limits(&tx)?;
+                Ok((tx, reason))
+            })?;
+
+            let tx_hash: H256 = tx.hash().unpack();
+            state.batches.push(AirdropBatch {
+                tx_hash: tx_hash.clone(),
+                lines: remaining[..count]
+                    .iter()
+                    .map(|recipient| recipient.line)
+                    .collect(),
+                status: AirdropBatchStatus::Pending,
+            });
+            // Record the batch before sending, so a crash will never pay the same recipients twice
+            state.save(&args.state_file)?;
+            let outputs_validator = Some(json_types::OutputsValidator::Passthrough);
+            if let Err(err) = self
+                .rpc_client
+                .send_transaction(tx.data(), outputs_validator)
+            {
+                state.batches.last_mut().unwrap().status = AirdropBatchStatus::Dropped;
+                state.save(&args.state_file)?;
+                return Err(format!("Send transaction error: {}", err));
+            }
+            let status = self.wait_committed(&tx_hash)?;
+            state.batches.last_mut().unwrap().status = status;
+            state.save(&args.state_file)?;
+            if status == AirdropBatchStatus::Dropped {
+                return Err(format!(
+                    "Transaction {:#x} is dropped, run the command again to send the remaining recipients",
+                    tx_hash
+                ));
+            }
+            eprintln!(
+                "[airdrop] transaction {:#x} committed, {} recipients",
+                tx_hash, count
+            );
+            remaining.drain(..count);
+        }
+
+        let batches = state
+            .batches
+            .iter()
+            .map(|batch| {
+                serde_json::json!({
+                    "transaction-hash": batch.tx_hash,
+                    "recipients": batch.lines.len(),
+                    "status": batch.status,
+                })
+            })
+            .collect::<Vec<_>>();
+        let resp = serde_json::json!({
+            "total_recipients": args.recipients.len(),
+            "sent_recipients": state.sent_lines().len(),
+            "batches": batches,
+        });
+        Ok(Output::new_output(resp))
+    }
+
+    fn build_airdrop_tx(
+        &mut self,
+        args: &AirdropArgs,
+        common_args: &SudtCommonArgs,
+        recipients: &[&AirdropRecipient],
+        passwords: &mut HashMap<H160, String>,
+    ) -> Result<TransactionView, String> {
+        let SudtCommonArgs {
+            udt_type,
+            xudt_extension_scripts,
+            privkeys,
+            external_signer,
+            cell_deps,
+            fee_rate,
+            force_small_change_as_fee,
+            ..
+        } = common_args;
+        let udt_script_id = get_udt_script_id(cell_deps, udt_type)?;
+        let owner_account = H160::from_slice(args.owner.payload().args().as_ref()).unwrap();
+        let owner_script = Script::from(&args.owner);
+        let owner_script_hash = owner_script.calc_script_hash();
+        let type_script = udt_type.build_script(&udt_script_id, &owner_script_hash);
+        let sender_account = args
+            .sender
+            .as_ref()
+            .map(|sender| H160::from_slice(&sender.payload().args().as_ref()[0..20]).unwrap());
+        let fallback_script_ids = if args.cheque_fallback {
+            Some((
+                get_script_id(cell_deps, CellDepName::Acp)?,
+                get_script_id(cell_deps, CellDepName::Cheque)?,
+            ))
+        } else {
+            None
+        };
+        // Same as issue/transfer: the owner is the cheque sender when issuing, otherwise it's
+        // the sighash lock of the sender.
+        let cheque_sender_script_hash = match sender_account.as_ref() {
+            Some(account) => sighash_script(account).calc_script_hash(),
+            None => owner_script_hash.clone(),
+        };
+
+        let mut receivers = Vec::with_capacity(recipients.len());
+        for recipient in recipients {
+            let receiver_script = Script::from(&recipient.address);
+            let (action, lock_script) = match fallback_script_ids.as_ref() {
+                Some((acp_script_id, cheque_script_id)) => {
+                    let acp_script = build_script(acp_script_id, receiver_script.args().raw_data());
+                    if self.has_udt_cell(&acp_script, &type_script)? {
+                        (TransferAction::Update, acp_script)
+                    } else {
+                        let mut script_args = vec![0u8; 40];
+                        script_args[0..20]
+                            .copy_from_slice(&receiver_script.calc_script_hash().as_slice()[0..20]);
+                        script_args[20..40]
+                            .copy_from_slice(&cheque_sender_script_hash.as_slice()[0..20]);
+                        (
+                            TransferAction::Create,
+                            build_script(cheque_script_id, Bytes::from(script_args)),
+                        )
+                    }
+                }
+                None => (TransferAction::Create, receiver_script),
+            };
+            receivers.push(UdtTargetReceiver {
+                action,
+                lock_script,
+                capacity: None,
+                amount: recipient.amount,
+                extra_data: None,
+            });
+        }
+
+        let (builder, accounts, capacity_provider, acp_script_id) =
+            match (args.sender.as_ref(), sender_account) {
+                (Some(sender), Some(sender_account)) => {
+                    let sender_sighash = sighash_script(&sender_account);
+                    let sender_sighash_addr = Address::new(
+                        args.owner.network(),
+                        AddressPayload::from(sender_sighash.clone()),
+                        true,
+                    );
+                    let builder = UdtTransferBuilder {
+                        type_script,
+                        sender: Script::from(sender),
+                        receivers,
+                    };
+                    (
+                        Box::new(builder) as Box<dyn TxBuilder>,
+                        vec![(format!("sender({})", sender_sighash_addr), sender_account)],
+                        sender_sighash,
+                        Some(get_script_id(cell_deps, CellDepName::Acp)?),
+                    )
+                }
+                _ => {
+                    let builder = UdtIssueBuilder {
+                        udt_type: udt_type.clone(),
+                        script_id: udt_script_id,
+                        owner: owner_script.clone(),
+                        receivers,
+                    };
+                    (
+                        Box::new(builder) as Box<dyn TxBuilder>,
+                        vec![("owner".to_string(), owner_account)],
+                        owner_script,
+                        fallback_script_ids.map(|(acp_script_id, _)| acp_script_id),
+                    )
+                }
+            };
+        let mut udt_builder = UdtTxBuilder {
+            plugin_mgr: self.plugin_mgr,
+            rpc_client: self.rpc_client,
+            cell_collector: &mut self.cell_collector,
+            cell_dep_resolver: &mut self.cell_dep_resolver,
+            header_dep_resolver: &self.header_dep_resolver,
+            tx_dep_provider: &self.tx_dep_provider,
+            builder: builder.as_ref(),
+            xudt_extension_scripts: xudt_extension_scripts.clone(),
+        };
+        udt_builder.build_with_passwords(
+            accounts,
+            privkeys.clone(),
+            external_signer.as_deref(),
+            cell_deps,
+            capacity_provider,
+            acp_script_id,
+            None,
+            *fee_rate,
+            *force_small_change_as_fee,
+            passwords,
+        )
+    }
+
+    fn has_udt_cell(&mut self, lock_script: &Script, type_script: &Script) -> Result<bool, String> {
+        let mut query = CellQueryOptions::new_lock(lock_script.clone());
+        query.secondary_script = Some(type_script.clone());
+        query.limit = Some(1);
+        let (cells, _) = self
+            .cell_collector
+            .collect_live_cells(&query, false)
+            .map_err(|err| err.to_string())?;
+        Ok(!cells.is_empty())
+    }
+
+    // Returns the reason if the transaction exceeds the size or cycles limit
+    fn check_tx_limits(&mut self, tx: &TransactionView) -> Result<Option<String>, String> {
+        let size = tx.data().serialized_size_in_block();
+        if size > MAX_AIRDROP_TX_SIZE {
+            return Ok(Some(format!(
+                "exceeds the size limit: {} > {} bytes",
+                size, MAX_AIRDROP_TX_SIZE
+            )));
+        }
+        let cycles = self.rpc_client.estimate_cycles(tx.data())?.cycles;
+        if cycles > MAX_AIRDROP_TX_CYCLES {
+            return Ok(Some(format!(
+                "exceeds the cycles limit: {} > {}",
+                cycles, MAX_AIRDROP_TX_CYCLES
+            )));
+        }
+        Ok(None)
+    }
+
+    fn wait_committed(&mut self, tx_hash: &H256) -> Result<AirdropBatchStatus, String> {
+        loop {
+            let status = self
+                .rpc_client
+                .get_transaction(tx_hash.clone())?
+                .map(|tx_with_status| tx_with_status.tx_status.status);
+            match status {
+                Some(json_types::Status::Committed) => return Ok(AirdropBatchStatus::Committed),
+                Some(json_types::Status::Pending) | Some(json_types::Status::Proposed) => {
+                    thread::sleep(COMMIT_POLL_INTERVAL);
+                }
+                _ => return Ok(AirdropBatchStatus::Dropped),
+            }
+        }
+    }
+}
+
+// Build the transaction for the first batch of `remaining`, halve the batch size until it's within
+// the limits. `build` returns the transaction and the reason if it exceeds the limits.
+fn build_batch<T, F>(
+    batch_size: &mut usize,
+    remaining: &[&AirdropRecipient],
+    mut build: F,
+) -> Result<(usize, T), String>
+where
+    F: FnMut(&[&AirdropRecipient]) -> Result<(T, Option<String>), String>,
+{
+    loop {
+        let count = (*batch_size).min(remaining.len());
+        match build(&remaining[..count])? {
+            (tx, None) => return Ok((count, tx)),
+            (_, Some(reason)) if count == 1 => {
+                return Err(format!(
+                    "The transaction for recipient at line {} {}",
+                    remaining[0].line, reason
+                ));
+            }
+            (_, Some(_)) => *batch_size = count / 2,
+        }
+    }
+}
+
+fn sighash_script(account: &H160) -> Script {
+    Script::new_builder()
+        .code_hash(SIGHASH_TYPE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(Bytes::from(account.as_bytes().to_vec()).pack())
+        .build()
+}
+
+fn build_script(script_id: &ScriptId, args: Bytes) -> Script {
+    Script::new_builder()
+        .code_hash(script_id.code_hash.pack())
+        .hash_type(script_id.hash_type.into())
+        .args(args.pack())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::arg_parser::AddressParser;
+    use ckb_sdk::NetworkType;
+
+    #[test]
+    fn test_parse_recipients() {
+        // The payload parsed from a full format address is always the full payload
+        let address = |byte: u8| {
+            let payload = AddressPayload::from_pubkey_hash(H160::from_slice(&[byte; 20]).unwrap());
+            let address = Address::new(NetworkType::Mainnet, payload, true);
+            AddressParser::default()
+                .parse(&address.to_string())
+                .unwrap()
+        };
+        let parser = UdtTargetParser::new(AddressParser::default()).with_decimals(2);
+        let content = format!(
+            "address,amount\n# comment\n\n{},12.5\n {} , 3 \n",
+            address(1),
+            address(2)
+        );
+        assert_eq!(
+            parse_recipients(&content, &parser),
+            Ok(vec![
+                AirdropRecipient {
+                    line: 4,
+                    address: address(1),
+                    amount: 1250,
+                },
+                AirdropRecipient {
+                    line: 5,
+                    address: address(2),
+                    amount: 300,
+                },
+            ])
+        );
+
+        let content = format!(
+            "{},1\n{}\n{},0.001\n{},2\n{},0\n",
+            address(1),
+            address(2),
+            address(3),
+            address(1),
+            address(4)
+        );
+        let err = parse_recipients(&content, &parser).unwrap_err();
+        assert!(err.contains("line 2:"));
+        assert!(err.contains("line 3:"));
+        assert!(err.contains("line 4: duplicated address, first appears at line 1"));
+        assert!(err.contains("line 5: amount is zero"));
+        assert!(!err.contains("line 1:"));
+
+        assert!(parse_recipients("address,amount\n", &parser).is_err());
+    }
+
+    #[test]
+    fn test_build_batch() {
+        let payload = AddressPayload::from_pubkey_hash(H160::default());
+        let recipients = (1..=10)
+            .map(|line| AirdropRecipient {
+                line,
+                address: Address::new(NetworkType::Mainnet, payload.clone(), true),
+                amount: 1,
+            })
+            .collect::<Vec<_>>();
+        let remaining = recipients.iter().collect::<Vec<_>>();
+
+        // Halve the batch size until the transaction is small enough, and keep the smaller size
+        let mut sizes = Vec::new();
+        let mut batch_size = 8;
+        let result = build_batch(&mut batch_size, &remaining, |batch| {
+            sizes.push(batch.len());
+            let reason = if batch.len() > 3 {
+                Some("exceeds the size limit".to_string())
+            } else {
+                None
+            };
+            Ok((batch.iter().map(|r| r.line).collect::<Vec<_>>(), reason))
+        });
+        assert_eq!(result, Ok((2, vec![1, 2])));
+        assert_eq!(sizes, vec![8, 4, 2]);
+        assert_eq!(batch_size, 2);
+
+        // The batch size is capped by the remaining recipients
+        let mut batch_size = 50;
+        let result = build_batch(&mut batch_size, &remaining[8..], |batch| {
+            Ok((batch.len(), None))
+        });
+        assert_eq!(result, Ok((2, 2)));
+
+        let mut batch_size = 4;
+        let result = build_batch(&mut batch_size, &remaining[5..], |_| {
+            Ok(((), Some("exceeds the cycles limit: 2 > 1".to_string())))
+        });
+        assert_eq!(
+            result,
+            Err(
+                "The transaction for recipient at line 6 exceeds the cycles limit: 2 > 1"
+                    .to_string()
+            )
+        );
+
+        let mut batch_size = 4;
+        let result = build_batch(&mut batch_size, &remaining, |_| {
+            Err::<((), Option<String>), _>("build error".to_string())
+        });
+        assert_eq!(result, Err("build error".to_string()));
+    }
+}
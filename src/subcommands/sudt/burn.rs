@@ -0,0 +1,182 @@
+use anyhow::anyhow;
+use ckb_jsonrpc_types as json_types;
+use ckb_sdk::{
+    traits::{
+        CellCollector, CellDepResolver, CellQueryOptions, HeaderDepResolver,
+        TransactionDependencyProvider,
+    },
+    tx_builder::{TxBuilder, TxBuilderError},
+    Address,
+};
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionView},
+    packed::{CellInput, CellOutput, Script},
+    prelude::*,
+    H160, H256,
+};
+
+use super::{get_udt_script_id, SudtCommonArgs, SudtSubCommand, UdtTxBuilder};
+use crate::subcommands::Output;
+
+/// Burn UDT in owner mode: consume the UDT cells of the owner lock, only the remaining amount is
+/// put back to a new UDT cell.
+pub struct UdtBurnBuilder {
+    pub type_script: Script,
+    pub owner: Script,
+    pub amount: u128,
+}
+
+impl TxBuilder for UdtBurnBuilder {
+    fn build_base(
+        &self,
+        cell_collector: &mut dyn CellCollector,
+        cell_dep_resolver: &dyn CellDepResolver,
+        _header_dep_resolver: &dyn HeaderDepResolver,
+        _tx_dep_provider: &dyn TransactionDependencyProvider,
+    ) -> Result<TransactionView, TxBuilderError> {
+        let mut query = CellQueryOptions::new_lock(self.owner.clone());
+        query.secondary_script = Some(self.type_script.clone());
+        query.min_total_capacity = u64::max_value();
+        let (cells, _) = cell_collector.collect_live_cells(&query, false)?;
+        let mut cells = cells
+            .into_iter()
+            .map(|cell| {
+                if cell.output_data.len() < 16 {
+                    return Err(TxBuilderError::Other(anyhow!(
+                        "invalid cell data length: {}, expected: >= 16",
+                        cell.output_data.len()
+                    )));
+                }
+                let mut amount_bytes = [0u8; 16];
+                amount_bytes.copy_from_slice(&cell.output_data.as_ref()[0..16]);
+                Ok((cell, u128::from_le_bytes(amount_bytes)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Use as few cells as possible
+        cells.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut inputs = Vec::new();
+        let mut total_amount: u128 = 0;
+        for (cell, amount) in cells {
+            if total_amount >= self.amount {
+                break;
+            }
+            inputs.push(CellInput::new(cell.out_point, 0));
+            total_amount = total_amount
+                .checked_add(amount)
+                .ok_or_else(|| TxBuilderError::Other(anyhow!("UDT amount overflow")))?;
+        }
+        if total_amount < self.amount {
+            return Err(TxBuilderError::InvalidParameter(anyhow!(
+                "UDT amount of owner is not enough, required: {}, available: {}",
+                self.amount,
+                total_amount
+            )));
+        }
+
+        let mut cell_deps = Vec::new();
+        for script in [&self.type_script, &self.owner] {
+            let cell_dep = cell_dep_resolver
+                .resolve(script)
+                .ok_or_else(|| TxBuilderError::ResolveCellDepFailed(script.clone()))?;
+            cell_deps.push(cell_dep);
+        }
+
+        let mut outputs = Vec::new();
+        let mut outputs_data = Vec::new();
+        if total_amount > self.amount {
+            let data = Bytes::from((total_amount - self.amount).to_le_bytes().to_vec());
+            let output = CellOutput::new_builder()
+                .lock(self.owner.clone())
+                .type_(Some(self.type_script.clone()).pack())
+                .build();
+            let capacity = output
+                .occupied_capacity(Capacity::bytes(data.len()).unwrap())
+                .unwrap();
+            outputs.push(output.as_builder().capacity(capacity.pack()).build());
+            outputs_data.push(data.pack());
+        }
+        let witnesses = vec![Bytes::new().pack(); inputs.len()];
+        Ok(TransactionView::new_advanced_builder()
+            .inputs(inputs)
+            .outputs(outputs)
+            .outputs_data(outputs_data)
+            .cell_deps(cell_deps)
+            .witnesses(witnesses)
+            .build())
+    }
+}
+
+impl<'a> SudtSubCommand<'a> {
+    pub(super) fn burn(
+        &mut self,
+        owner: Address,
+        amount: u128,
+        common_args: SudtCommonArgs,
+    ) -> Result<Output, String> {
+        let SudtCommonArgs {
+            udt_type,
+            xudt_extension_scripts,
+            privkeys,
+            external_signer,
+            cell_deps,
+            fee_rate,
+            force_small_change_as_fee,
+            debug,
+        } = common_args;
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
+        let owner_account = H160::from_slice(owner.payload().args().as_ref()).unwrap();
+        let owner_script = Script::from(&owner);
+        let type_script = udt_type.build_script(&udt_script_id, &owner_script.calc_script_hash());
+        let builder = UdtBurnBuilder {
+            type_script,
+            owner: owner_script.clone(),
+            amount,
+        };
+        let mut udt_builder = UdtTxBuilder {
+            plugin_mgr: self.plugin_mgr,
+            rpc_client: self.rpc_client,
+            cell_collector: &mut self.cell_collector,
+            cell_dep_resolver: &mut self.cell_dep_resolver,
+            header_dep_resolver: &self.header_dep_resolver,
+            tx_dep_provider: &self.tx_dep_provider,
+            builder: &builder,
+            xudt_extension_scripts,
+        };
+        let tx = udt_builder.build(
+            vec![("owner".to_string(), owner_account)],
+            privkeys,
+            external_signer.as_deref(),
+            &cell_deps,
+            owner_script,
+            None,
+            None,
+            fee_rate,
+            force_small_change_as_fee,
+        )?;
+
+        let outputs_validator = Some(json_types::OutputsValidator::Passthrough);
+        let tx_hash = self
+            .rpc_client
+            .send_transaction(tx.data(), outputs_validator)
+            .map_err(|err| format!("Send transaction error: {}", err))?;
+        assert_eq!(tx.hash(), tx_hash.pack());
+
+        if debug {
+            let rpc_tx_view = json_types::TransactionView::from(tx);
+            let resp = serde_json::json!({
+                "transaction": rpc_tx_view,
+                "burned_amount": amount.to_string(),
+            });
+            Ok(Output::new_output(resp))
+        } else {
+            let tx_hash: H256 = tx.hash().unpack();
+            let resp = serde_json::json!({
+                "transaction-hash": tx_hash,
+                "burned_amount": amount.to_string(),
+            });
+            Ok(Output::new_output(resp))
+        }
+    }
+}
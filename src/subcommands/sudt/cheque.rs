@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use ckb_jsonrpc_types as json_types;
+use ckb_sdk::{
+    traits::{CellCollector, CellQueryOptions},
+    Address, HumanCapacity,
+};
+use ckb_types::{
+    bytes::Bytes,
+    core::{EpochNumberWithFraction, HeaderView},
+    packed::Script,
+    prelude::*,
+};
+use faster_hex::hex_string;
+
+use super::{get_script_id, SudtSubCommand};
+use crate::subcommands::dao::util::{epoch_json, epoch_reached};
+use crate::subcommands::Output;
+use crate::utils::{
+    cell_dep::{CellDepName, CellDeps},
+    token_registry::format_decimal_amount,
+};
+
+/// The sender can only withdraw a cheque cell after this many epochs since it's created
+pub const CHEQUE_LOCK_PERIOD_EPOCHS: u64 = 6;
+
+pub(super) struct ChequeListArgs {
+    pub sender: Option<Address>,
+    pub receiver: Address,
+    /// Only list cheque cells of this UDT
+    pub type_script: Option<Script>,
+    pub cell_deps: CellDeps,
+    pub decimals: Option<u8>,
+}
+
+impl<'a> SudtSubCommand<'a> {
+    pub(super) fn cheque_list(&mut self, args: ChequeListArgs) -> Result<Output, String> {
+        let ChequeListArgs {
+            sender,
+            receiver,
+            type_script,
+            cell_deps,
+            decimals,
+        } = args;
+        let cheque_script_id = get_script_id(&cell_deps, CellDepName::Cheque)?;
+        // The cheque lock args: <receiver lock hash[0..20]> <sender lock hash[0..20]>
+        let lock_hash_prefix =
+            |addr: &Address| Script::from(addr).calc_script_hash().as_slice()[0..20].to_vec();
+        // The indexer searches by the args prefix, so the receiver is always required
+        let mut args_prefix = lock_hash_prefix(&receiver);
+        if let Some(sender) = sender.as_ref() {
+            args_prefix.extend(lock_hash_prefix(sender));
+        }
+        let cheque_script = Script::new_builder()
+            .code_hash(cheque_script_id.code_hash.pack())
+            .hash_type(cheque_script_id.hash_type.into())
+            .args(Bytes::from(args_prefix).pack())
+            .build();
+
+        let mut query = CellQueryOptions::new_lock(cheque_script);
+        query.secondary_script = type_script;
+        query.min_total_capacity = u64::max_value();
+        let (cells, _) = self
+            .cell_collector
+            .collect_live_cells(&query, false)
+            .map_err(|err| err.to_string())?;
+
+        let tip_header: HeaderView = self.rpc_client.get_tip_header()?.into();
+        let tip_epoch = tip_header.epoch();
+        let mut epochs: HashMap<u64, EpochNumberWithFraction> = HashMap::new();
+        let mut cheques = Vec::new();
+        for cell in cells {
+            let lock_args = cell.output.lock().args().raw_data();
+            if lock_args.len() != 40 {
+                continue;
+            }
+            let created_epoch = match epochs.get(&cell.block_number) {
+                Some(epoch) => *epoch,
+                None => {
+                    let header: HeaderView = self
+                        .rpc_client
+                        .get_header_by_number(cell.block_number)?
+                        .map(Into::into)
+                        .ok_or_else(|| {
+                            format!("Can not get header of block {}", cell.block_number)
+                        })?;
+                    epochs.insert(cell.block_number, header.epoch());
+                    header.epoch()
+                }
+            };
+            let withdraw_epoch = EpochNumberWithFraction::new(
+                created_epoch.number() + CHEQUE_LOCK_PERIOD_EPOCHS,
+                created_epoch.index(),
+                created_epoch.length(),
+            );
+            let amount = if cell.output_data.len() >= 16 {
+                let mut amount_bytes = [0u8; 16];
+                amount_bytes.copy_from_slice(&cell.output_data.as_ref()[0..16]);
+                Some(u128::from_le_bytes(amount_bytes))
+            } else {
+                None
+            };
+            let capacity: u64 = cell.output.capacity().unpack();
+            let withdrawable = epoch_reached(tip_epoch, withdraw_epoch);
+            let mut cheque = serde_json::json!({
+                "out_point": json_types::OutPoint::from(cell.out_point),
+                "receiver_lock_hash": format!("0x{}", hex_string(&lock_args[0..20])),
+                "sender_lock_hash": format!("0x{}", hex_string(&lock_args[20..40])),
+                "type_script": cell.output.type_().to_opt().map(json_types::Script::from),
+                "capacity": format!("{:#}", HumanCapacity::from(capacity)),
+                // u128 is too large for json
+                "amount": amount.map(|amount| amount.to_string()),
+                "block_number": cell.block_number,
+                "created_epoch": epoch_json(created_epoch),
+                // Claiming is only guaranteed before the sender is allowed to withdraw it
+                "claimable": !withdrawable,
+                "withdrawable": withdrawable,
+                "withdrawable_epoch": epoch_json(withdraw_epoch),
+            });
+            if let (Some(amount), Some(decimals)) = (amount, decimals) {
+                cheque["formatted_amount"] = format_decimal_amount(amount, decimals).into();
+            }
+            cheques.push(cheque);
+        }
+        let resp = serde_json::json!({
+            "tip_epoch": epoch_json(tip_epoch),
+            "cheque_count": cheques.len(),
+            "cheques": cheques,
+        });
+        Ok(Output::new_output(resp))
+    }
+}
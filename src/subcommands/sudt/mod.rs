@@ -1,7 +1,17 @@
-use std::collections::HashMap;
+mod airdrop;
+mod burn;
+mod cheque;
+mod supply;
+mod xudt;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
 
 use clap::{App, Arg, ArgMatches};
+use faster_hex::hex_string;
 
+use ckb_hash::blake2b_256;
 use ckb_jsonrpc_types as json_types;
 use ckb_sdk::{
     constants::SIGHASH_TYPE_HASH,
@@ -18,7 +28,7 @@ use ckb_sdk::{
         udt::{UdtIssueBuilder, UdtTargetReceiver, UdtTransferBuilder, UdtType},
         CapacityBalancer, CapacityProvider, TransferAction, TxBuilder,
     },
-    types::ScriptId,
+    types::{xudt_rce_mol::ScriptVec, ScriptId},
     unlock::{
         AcpScriptSigner, AcpUnlocker, ChequeAction, ChequeScriptSigner, ChequeUnlocker,
         ScriptUnlocker, SecpSighashScriptSigner, SecpSighashUnlocker,
@@ -33,20 +43,30 @@ use ckb_types::{
     H160, H256,
 };
 
+use self::airdrop::{parse_recipients, AirdropArgs};
+use self::cheque::ChequeListArgs;
+use self::xudt::{extension_scripts_hash, XudtExtensionBuilder};
 use crate::{
     plugin::PluginManager,
     subcommands::{CliSubCommand, Output},
     utils::{
         arg,
         arg_parser::{
-            AddressParser, ArgParser, CellDepsParser, FromStrParser, PrivkeyPathParser,
-            PrivkeyWrapper, UdtTargetParser,
+            AddressParser, ArgParser, CellDepsParser, FilePathParser, FromStrParser,
+            PrivkeyPathParser, PrivkeyWrapper, UdtTargetParser, XudtArgsParser,
+            XudtExtensionScriptsParser, XUDT_EXTENSION_SCRIPTS_HASH, XUDT_FLAGS_EXTENSION_MASK,
         },
         cell_dep::{CellDepName, CellDeps},
         genesis_info::GenesisInfo,
-        other::{get_network_type, map_tx_builder_error_2_str, read_password},
+        other::{
+            get_fee_rate, get_network_type, is_unlocked_in_agent, map_tx_builder_error_2_str,
+            read_password,
+        },
         rpc::HttpRpcClient,
-        signer::{CommonSigner, KeyStoreHandlerSigner, PrivkeySigner},
+        signer::{CommonSigner, ExternalSigner, KeyStoreHandlerSigner, PrivkeySigner},
+        token_registry::{
+            format_decimal_amount, parse_decimal_amount, TokenInfo, TokenRegistry, MAX_UDT_DECIMALS,
+        },
     },
 };
 
@@ -57,10 +77,25 @@ pub struct SudtSubCommand<'a> {
     cell_dep_resolver: DefaultCellDepResolver,
     header_dep_resolver: DefaultHeaderDepResolver,
     tx_dep_provider: DefaultTransactionDependencyProvider,
+    ckb_cli_dir: PathBuf,
+}
+
+/// The UDT identified by <token> or by <owner>, <udt-type>, <xudt-args> and <cell-deps>
+struct UdtInfo {
+    owner: Address,
+    udt_type: UdtType,
+    cell_deps: CellDeps,
+    /// Only known when the UDT is given by <token>
+    decimals: Option<u8>,
+    /// Required by transactions when the xUDT args only has the hash of the extension scripts
+    xudt_extension_scripts: Option<ScriptVec>,
 }
 
 struct SudtCommonArgs {
+    udt_type: UdtType,
+    xudt_extension_scripts: Option<ScriptVec>,
     privkeys: Vec<PrivkeyWrapper>,
+    external_signer: Option<String>,
     cell_deps: CellDeps,
     fee_rate: u64,
     force_small_change_as_fee: Option<u64>,
@@ -72,9 +107,10 @@ impl<'a> SudtSubCommand<'a> {
         rpc_client: &'a mut HttpRpcClient,
         plugin_mgr: &'a mut PluginManager,
         genesis_info: GenesisInfo,
+        ckb_cli_dir: PathBuf,
     ) -> Self {
         let tx_dep_provider = DefaultTransactionDependencyProvider::new(rpc_client.url(), 10);
-        let cell_collector = DefaultCellCollector::new(rpc_client.url());
+        let cell_collector = DefaultCellCollector::new(rpc_client.indexer_url());
         let cell_dep_resolver = genesis_info.cell_dep_resolver;
         let header_dep_resolver = DefaultHeaderDepResolver::new(rpc_client.url());
         Self {
@@ -84,6 +120,7 @@ impl<'a> SudtSubCommand<'a> {
             cell_dep_resolver,
             header_dep_resolver,
             tx_dep_provider,
+            ckb_cli_dir,
         }
     }
 
@@ -93,7 +130,7 @@ impl<'a> SudtSubCommand<'a> {
             .takes_value(true)
             .multiple(true)
             .required(true)
-            .validator(|input| UdtTargetParser::new(AddressParser::default()).validate(input));
+            .validator(validate_udt_target);
         let arg_to_cheque_address = Arg::with_name("to-cheque-address").long("to-cheque-address");
         let arg_receiver = Arg::with_name("receiver")
             .long("receiver")
@@ -102,14 +139,18 @@ impl<'a> SudtSubCommand<'a> {
             .validator(|input| AddressParser::new_sighash().validate(input));
 
         App::new(name)
-            .about("SUDT issue/transfer operations (currently only support sudt)")
+            .about("SUDT/xUDT issue/transfer operations (use <udt-type> to choose the UDT kind)")
             .subcommands(vec![
                 App::new("issue")
                     .about("Issue SUDT to multiple addresses")
+                    .arg(arg_token())
                     .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_xudt_extension_scripts())
                     .arg(
                         arg_udt_to.clone()
-                            .about("The issue target, format: {address}:{amount}, the address type can be: [acp, sighash]")
+                            .about("The issue target, format: {address}:{amount}, the address type can be: [acp, sighash], the amount is a decimal number when <token> is given")
                     )
                     .arg(arg_cell_deps())
                     .arg(arg_to_acp_address())
@@ -119,15 +160,20 @@ impl<'a> SudtSubCommand<'a> {
                             .about("Treat all addresses in <udt-to> as cheque receiver (sighash address, and the cheque sender is the <owner>), otherwise the address will be used as the lock script of the SUDT cell")
                     )
                     .arg(arg::privkey_path().multiple(true))
+                    .arg(arg::external_signer())
                     .arg(arg::fee_rate())
                     .arg(arg::max_tx_fee()),
                 App::new("transfer")
                     .about("Transfer SUDT to multiple addresses (all target addresses must have same lock script id)")
+                    .arg(arg_token())
                     .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_xudt_extension_scripts())
                     .arg(arg_sender().about("SUDT sender address, the address type can be: [acp, sighash], when address type is `acp` this address will be used to build a sighash lock script for build cheque address or provide capacity, if <capacity-provider> is not given <sender> will also use as capacity provider."))
                     .arg(
                         arg_udt_to
-                         .about("The transfer target, format: {address}:{amount}, the address type can be: [acp, sighash]")
+                         .about("The transfer target, format: {address}:{amount}, the address type can be: [acp, sighash], the amount is a decimal number when <token> is given")
                     )
                     .arg(arg_cell_deps())
                     .arg(arg_to_acp_address())
@@ -138,11 +184,92 @@ impl<'a> SudtSubCommand<'a> {
                     )
                     .arg(arg_capacity_provider())
                     .arg(arg::privkey_path().multiple(true))
+                    .arg(arg::external_signer())
+                    .arg(arg::fee_rate())
+                    .arg(arg::max_tx_fee()),
+                App::new("airdrop")
+                    .about("Send UDT to all recipients in a CSV file, the recipients are split into transactions within the size and cycles limits, the progress is saved in <state-file> so an interrupted airdrop can be resumed by running the same command again")
+                    .arg(arg_token())
+                    .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_xudt_extension_scripts())
+                    .arg(arg_sender().required(false).about("The UDT sender address, the address type can be: [acp, sighash], the sighash address of <sender> will provide capacity. If not given, the UDT will be issued by <owner>"))
+                    .arg(
+                        Arg::with_name("file")
+                            .long("file")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| FilePathParser::new(true).validate(input))
+                            .about("The recipients file, one `{address},{amount}` per line (lines start with `#` are ignored), the amount is a decimal number when <token> is given")
+                    )
+                    .arg(
+                        Arg::with_name("state-file")
+                            .long("state-file")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| FilePathParser::new(false).validate(input))
+                            .about("The file to save the sent transactions, only recipients not sent yet are sent when resuming")
+                    )
+                    .arg(
+                        Arg::with_name("cheque-fallback")
+                            .long("cheque-fallback")
+                            .about("Send to the anyone-can-pay cell of the recipient if it exists, otherwise create a cheque cell (all recipients must be sighash addresses, <acp> and <cheque> cell_dep items are required)")
+                    )
+                    .arg(
+                        Arg::with_name("batch-size")
+                            .long("batch-size")
+                            .takes_value(true)
+                            .default_value("200")
+                            .validator(|input| FromStrParser::<usize>::default().validate(input))
+                            .about("The maximum number of recipients in one transaction, it will be reduced when the transaction exceeds the size or cycles limit")
+                    )
+                    .arg(arg_cell_deps())
+                    .arg(arg::privkey_path().multiple(true))
+                    .arg(arg::external_signer())
+                    .arg(arg::fee_rate())
+                    .arg(arg::max_tx_fee()),
+                App::new("burn")
+                    .about("Burn SUDT of the owner (owner mode), the UDT cells of <owner> are consumed and only the remaining amount is kept")
+                    .arg(arg_token())
+                    .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_xudt_extension_scripts())
+                    .arg(
+                        Arg::with_name("amount")
+                            .long("amount")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(validate_udt_amount)
+                            .about("The amount to burn, it is a decimal number when <token> is given")
+                    )
+                    .arg(arg_cell_deps())
+                    .arg(arg::privkey_path().multiple(true))
+                    .arg(arg::external_signer())
                     .arg(arg::fee_rate())
                     .arg(arg::max_tx_fee()),
+                App::new("supply")
+                    .about("Report the total supply, holder count and top holders of a SUDT (sum of all live cells)")
+                    .arg(arg_token())
+                    .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_cell_deps())
+                    .arg(
+                        Arg::with_name("top")
+                            .long("top")
+                            .takes_value(true)
+                            .default_value("10")
+                            .validator(|input| FromStrParser::<usize>::default().validate(input))
+                            .about("The number of top holders to show")
+                    ),
                 App::new("get-amount")
                     .about("Get SUDT total amount of an address")
+                    .arg(arg_token())
                     .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
                     .arg(arg_cell_deps())
                     .arg(
                         Arg::with_name("address")
@@ -152,9 +279,30 @@ impl<'a> SudtSubCommand<'a> {
                             .validator(|input| AddressParser::default().validate(input))
                             .about("The target address of those SUDT cells"),
                     ),
+                App::new("balances")
+                    .about("List the amounts of all UDT tokens held by an address (both sighash and anyone-can-pay cells are counted)")
+                    .arg(
+                        Arg::with_name("address")
+                            .long("address")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| AddressParser::default().validate(input))
+                            .about("The holder address (sighash or acp)"),
+                    )
+                    .arg(
+                        Arg::with_name("cell-deps")
+                            .long("cell-deps")
+                            .takes_value(true)
+                            .validator(|input| CellDepsParser.validate(input))
+                            .about("The cell deps information, the sudt/xudt items are used to identify UDT cells, the acp item is used to include anyone-can-pay cells (default is the cell deps of the active profile)"),
+                    ),
                 App::new("new-empty-acp")
                     .about("Create a SUDT cell with 0 amount and an acp lock script")
+                    .arg(arg_token())
                     .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_xudt_extension_scripts())
                     .arg(arg_capacity_provider())
                     .arg(
                         Arg::with_name("to")
@@ -166,11 +314,16 @@ impl<'a> SudtSubCommand<'a> {
                     )
                     .arg(arg_cell_deps())
                     .arg(arg::privkey_path().multiple(true))
+                    .arg(arg::external_signer())
                     .arg(arg::fee_rate())
                     .arg(arg::max_tx_fee()),
                 App::new("cheque-claim")
                     .about("Claim all cheque cells identified by given lock script and type script")
+                    .arg(arg_token())
                     .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_xudt_extension_scripts())
                     .arg(arg_sender().about("The cheque sender address (sighash)"))
                     .arg(
                         arg_receiver
@@ -180,22 +333,48 @@ impl<'a> SudtSubCommand<'a> {
                     .arg(arg_capacity_provider())
                     .arg(arg_cell_deps())
                     .arg(arg::privkey_path().multiple(true))
+                    .arg(arg::external_signer())
                     .arg(arg::fee_rate())
                     .arg(arg::max_tx_fee()),
                 App::new("cheque-withdraw")
                     .about("Withdraw all cheque cells identified by given lock script and type script")
+                    .arg(arg_token())
                     .arg(arg_owner())
+                    .arg(arg_udt_type())
+                    .arg(arg_xudt_args())
+                    .arg(arg_xudt_extension_scripts())
                     .arg(arg_sender().about("The cheque sender address (sighash), if <capacity-provider> not given <sender> will use as capacity provider"))
                     .arg(arg_receiver.clone().about("The cheque receiver address (sighash)"))
                     .arg(arg_capacity_provider())
                     .arg(arg_to_acp_address().about("Withdraw to anyone-can-pay address, will use <sender> to build the anyone-can-pay address, the cell must be already exists"))
                     .arg(arg_cell_deps())
                     .arg(arg::privkey_path().multiple(true))
+                    .arg(arg::external_signer())
                     .arg(arg::fee_rate())
                     .arg(arg::max_tx_fee()),
+                App::new("cheque-list")
+                    .about("List live cheque cells of <receiver> (and <sender>), and whether they are claimable by the receiver (within 6 epochs since created) or withdrawable by the sender (after 6 epochs since created)")
+                    .arg(
+                        Arg::with_name("sender")
+                            .long("sender")
+                            .takes_value(true)
+                            .validator(|input| AddressParser::new_sighash().validate(input))
+                            .about("The cheque sender address (sighash)")
+                    )
+                    .arg(
+                        Arg::with_name("receiver")
+                            .long("receiver")
+                            .takes_value(true)
+                            .required(true)
+                            .validator(|input| AddressParser::new_sighash().validate(input))
+                            .about("The cheque receiver address (sighash)")
+                    )
+                    .arg(arg_token().about("Only list cheque cells of this token (see `sudt registry`)"))
+                    .arg(arg_cell_deps()),
                 // TODO: move this subcommand to `util`
                 App::new("build-acp-address")
                     .about("Build an anyone-can-pay address by sighash address and anyone-can-pay script id.")
+                    .arg(arg_token())
                     .arg(arg_cell_deps())
                     .arg(
                         Arg::with_name("sighash-address")
@@ -207,9 +386,52 @@ impl<'a> SudtSubCommand<'a> {
                     ),
                 App::new("build-cheque-address")
                     .about("Build a cheque address by cheque script id and receiver+sender address")
+                    .arg(arg_token())
                     .arg(arg_cell_deps())
                     .arg(arg_receiver.about("The receiver address"))
                     .arg(arg_sender()),
+                App::new("registry")
+                    .about("Manage the local token registry (symbol => owner, UDT type, decimals, cell deps)")
+                    .subcommands(vec![
+                        App::new("add")
+                            .about("Add a token to the registry")
+                            .arg(arg_symbol())
+                            .arg(
+                                Arg::with_name("owner")
+                                    .long("owner")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .validator(|input| AddressParser::new_sighash().validate(input))
+                                    .about("The owner address of the token (sighash)"),
+                            )
+                            .arg(arg_udt_type())
+                            .arg(arg_xudt_args())
+                            .arg(
+                                Arg::with_name("decimals")
+                                    .long("decimals")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .validator(|input| FromStrParser::<u8>::default().validate(input))
+                                    .about("The number of decimal places of the token amount"),
+                            )
+                            .arg(
+                                Arg::with_name("cell-deps")
+                                    .long("cell-deps")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .validator(|input| CellDepsParser.validate(input))
+                                    .about("The cell deps file, the absolute path is saved in the registry"),
+                            )
+                            .arg(
+                                Arg::with_name("force")
+                                    .long("force")
+                                    .about("Replace the token if the symbol already exists"),
+                            ),
+                        App::new("list").about("List all tokens in the registry"),
+                        App::new("remove")
+                            .about("Remove a token from the registry")
+                            .arg(arg_symbol()),
+                    ]),
             ])
     }
 
@@ -226,14 +448,16 @@ impl<'a> SudtSubCommand<'a> {
             to_acp_address,
         } = args;
         let SudtCommonArgs {
+            udt_type,
+            xudt_extension_scripts,
             privkeys,
+            external_signer,
             cell_deps,
             fee_rate,
             force_small_change_as_fee,
             debug,
         } = common_args;
-        let udt_script_id = get_script_id(&cell_deps, CellDepName::Sudt)?;
-        let udt_type = UdtType::Sudt;
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
         let acp_script_id = if to_acp_address {
             Some(get_script_id(&cell_deps, CellDepName::Acp)?)
         } else {
@@ -301,10 +525,12 @@ impl<'a> SudtSubCommand<'a> {
             header_dep_resolver: &self.header_dep_resolver,
             tx_dep_provider: &self.tx_dep_provider,
             builder: &builder,
+            xudt_extension_scripts,
         };
         let tx = udt_builder.build(
             vec![("owner".to_string(), owner_account)],
             privkeys,
+            external_signer.as_deref(),
             &cell_deps,
             owner_script,
             acp_script_id,
@@ -352,14 +578,16 @@ impl<'a> SudtSubCommand<'a> {
             capacity_provider,
         } = args;
         let SudtCommonArgs {
+            udt_type,
+            xudt_extension_scripts,
             privkeys,
+            external_signer,
             cell_deps,
             fee_rate,
             force_small_change_as_fee,
             debug,
         } = common_args;
-        let udt_script_id = get_script_id(&cell_deps, CellDepName::Sudt)?;
-        let udt_type = UdtType::Sudt;
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
         let acp_script_id = get_script_id(&cell_deps, CellDepName::Acp)?;
         let cheque_script_id = if to_cheque_address {
             Some(get_script_id(&cell_deps, CellDepName::Cheque)?)
@@ -444,10 +672,12 @@ impl<'a> SudtSubCommand<'a> {
             header_dep_resolver: &self.header_dep_resolver,
             tx_dep_provider: &self.tx_dep_provider,
             builder: &builder,
+            xudt_extension_scripts,
         };
         let tx = udt_builder.build(
             accounts,
             privkeys,
+            external_signer.as_deref(),
             &cell_deps,
             capacity_provider,
             Some(acp_script_id),
@@ -485,9 +715,10 @@ impl<'a> SudtSubCommand<'a> {
         owner: Address,
         address: Address,
         cell_deps: CellDeps,
+        udt_type: UdtType,
+        decimals: Option<u8>,
     ) -> Result<Output, String> {
-        let udt_script_id = get_script_id(&cell_deps, CellDepName::Sudt)?;
-        let udt_type = UdtType::Sudt;
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
         let owner_script_hash = Script::from(&owner).calc_script_hash();
         let type_script = udt_type.build_script(&udt_script_id, &owner_script_hash);
 
@@ -511,18 +742,155 @@ impl<'a> SudtSubCommand<'a> {
             amount_bytes.copy_from_slice(&cell.output_data.as_ref()[0..16]);
             let amount = u128::from_le_bytes(amount_bytes);
             total_amount += amount;
-            infos.push(serde_json::json!({
+            let mut info = serde_json::json!({
                 "out_point": json_types::OutPoint::from(cell.out_point),
                 // u128 is too large for json
                 "amount": amount.to_string(),
-            }));
+            });
+            if let Some(decimals) = decimals {
+                info["formatted_amount"] = format_decimal_amount(amount, decimals).into();
+            }
+            infos.push(info);
         }
-        let resp = serde_json::json!({
+        let mut resp = serde_json::json!({
             "cell_count": infos.len(),
             "cells": infos,
             // u128 is too large for json
             "total_amount": total_amount.to_string(),
         });
+        if let Some(decimals) = decimals {
+            resp["decimals"] = decimals.into();
+            resp["formatted_total_amount"] = format_decimal_amount(total_amount, decimals).into();
+        }
+        Ok(Output::new_output(resp))
+    }
+
+    fn balances(
+        &mut self,
+        address: Address,
+        cell_deps: CellDeps,
+        network: NetworkType,
+    ) -> Result<Output, String> {
+        let sighash_script_id = ScriptId::new_type(SIGHASH_TYPE_HASH.clone());
+        let acp_script_id = get_script_id(&cell_deps, CellDepName::Acp).ok();
+        let udt_script_ids: Vec<(&str, ScriptId)> =
+            [(CellDepName::Sudt, "sudt"), (CellDepName::Xudt, "xudt")]
+                .iter()
+                .filter_map(|(name, kind)| {
+                    get_script_id(&cell_deps, *name).ok().map(|id| (*kind, id))
+                })
+                .collect();
+        if udt_script_ids.is_empty() {
+            return Err("no sudt or xudt cell_dep item in cell_deps".to_string());
+        }
+
+        // The sighash lock and the acp lock share the same 20 bytes args
+        let address_script = Script::from(&address);
+        let address_script_id = ScriptId::from(&address_script);
+        let lock_args = address_script.args().raw_data();
+        let mut lock_scripts = vec![address_script.clone()];
+        if let Some(acp_script_id) = acp_script_id.as_ref() {
+            let other_script_id = if address_script_id == sighash_script_id {
+                Some(acp_script_id)
+            } else if &address_script_id == acp_script_id && lock_args.len() >= 20 {
+                Some(&sighash_script_id)
+            } else {
+                None
+            };
+            if let Some(script_id) = other_script_id {
+                lock_scripts.push(
+                    Script::new_builder()
+                        .code_hash(script_id.code_hash.pack())
+                        .hash_type(script_id.hash_type.into())
+                        .args(lock_args.slice(0..20).pack())
+                        .build(),
+                );
+            }
+        }
+
+        // type script args => (udt kind, cell count, total amount)
+        let mut balances: BTreeMap<Bytes, (&str, usize, u128)> = BTreeMap::new();
+        for lock_script in &lock_scripts {
+            let mut query = CellQueryOptions::new_lock(lock_script.clone());
+            query.min_total_capacity = u64::max_value();
+            let (cells, _) = self
+                .cell_collector
+                .collect_live_cells(&query, false)
+                .map_err(|err| err.to_string())?;
+            for cell in cells {
+                let type_script = match cell.output.type_().to_opt() {
+                    Some(script) => script,
+                    None => continue,
+                };
+                let type_script_id = ScriptId::from(&type_script);
+                let kind = match udt_script_ids.iter().find(|(_, id)| id == &type_script_id) {
+                    Some((kind, _)) => *kind,
+                    None => continue,
+                };
+                if cell.output_data.len() < 16 {
+                    return Err(format!(
+                        "invalid cell data length: {}, expected: >= 16",
+                        cell.output_data.len()
+                    ));
+                }
+                let mut amount_bytes = [0u8; 16];
+                amount_bytes.copy_from_slice(&cell.output_data.as_ref()[0..16]);
+                let amount = u128::from_le_bytes(amount_bytes);
+                let entry = balances
+                    .entry(type_script.args().raw_data())
+                    .or_insert((kind, 0, 0));
+                entry.1 += 1;
+                entry.2 = entry
+                    .2
+                    .checked_add(amount)
+                    .ok_or_else(|| "UDT amount overflow".to_string())?;
+            }
+        }
+
+        // type script args => (symbol, decimals)
+        let mut known_tokens: HashMap<Bytes, (String, u8)> = HashMap::new();
+        for (symbol, info) in TokenRegistry::load(&self.ckb_cli_dir)?.tokens {
+            let owner = match AddressParser::default()
+                .set_network(network)
+                .parse(&info.owner)
+            {
+                Ok(owner) => owner,
+                // Tokens of other networks
+                Err(_) => continue,
+            };
+            let mut type_args = Script::from(&owner).calc_script_hash().as_slice().to_vec();
+            if let UdtType::Xudt(xudt_args) = info.udt_type()? {
+                type_args.extend_from_slice(xudt_args.as_ref());
+            }
+            known_tokens.insert(Bytes::from(type_args), (symbol, info.decimals));
+        }
+
+        let tokens = balances
+            .into_iter()
+            .map(|(type_args, (kind, cell_count, amount))| {
+                let mut token = serde_json::json!({
+                    "udt_type": kind,
+                    "type_args": format!("0x{}", hex_string(type_args.as_ref())),
+                    "cell_count": cell_count,
+                    // u128 is too large for json
+                    "amount": amount.to_string(),
+                });
+                if let Some((symbol, decimals)) = known_tokens.get(&type_args) {
+                    token["symbol"] = symbol.clone().into();
+                    token["decimals"] = (*decimals).into();
+                    token["formatted_amount"] = format_decimal_amount(amount, *decimals).into();
+                }
+                token
+            })
+            .collect::<Vec<_>>();
+        let locks = lock_scripts
+            .into_iter()
+            .map(|script| Address::new(network, AddressPayload::from(script), true).to_string())
+            .collect::<Vec<_>>();
+        let resp = serde_json::json!({
+            "locks": locks,
+            "tokens": tokens,
+        });
         Ok(Output::new_output(resp))
     }
 
@@ -538,14 +906,16 @@ impl<'a> SudtSubCommand<'a> {
             capacity_provider,
         } = args;
         let SudtCommonArgs {
+            udt_type,
+            xudt_extension_scripts,
             privkeys,
+            external_signer,
             cell_deps,
             fee_rate,
             force_small_change_as_fee,
             debug,
         } = common_args;
-        let udt_script_id = get_script_id(&cell_deps, CellDepName::Sudt)?;
-        let udt_type = UdtType::Sudt;
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
         let acp_script_id = get_script_id(&cell_deps, CellDepName::Acp)?;
         let owner_script_hash = Script::from(&owner).calc_script_hash();
         let capacity_provider = capacity_provider.unwrap_or_else(|| to.clone());
@@ -588,10 +958,12 @@ impl<'a> SudtSubCommand<'a> {
             header_dep_resolver: &self.header_dep_resolver,
             tx_dep_provider: &self.tx_dep_provider,
             builder: &builder,
+            xudt_extension_scripts,
         };
         let tx = udt_builder.build(
             vec![("capacity provider".to_string(), capacity_provider_account)],
             privkeys,
+            external_signer.as_deref(),
             &cell_deps,
             Script::from(&capacity_provider),
             None,
@@ -635,13 +1007,16 @@ impl<'a> SudtSubCommand<'a> {
             capacity_provider,
         } = args;
         let SudtCommonArgs {
+            udt_type,
+            xudt_extension_scripts,
             privkeys,
+            external_signer,
             cell_deps,
             fee_rate,
             force_small_change_as_fee,
             debug,
         } = common_args;
-        let udt_script_id = get_script_id(&cell_deps, CellDepName::Sudt)?;
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
         let cheque_script_id = get_script_id(&cell_deps, CellDepName::Cheque)?;
         let acp_script_id = get_script_id(&cell_deps, CellDepName::Acp)?;
         let owner_script = Script::from(&owner);
@@ -664,11 +1039,7 @@ impl<'a> SudtSubCommand<'a> {
             .hash_type(acp_script_id.hash_type.into())
             .args(receiver_script.args())
             .build();
-        let type_script = Script::new_builder()
-            .code_hash(udt_script_id.code_hash.pack())
-            .hash_type(udt_script_id.hash_type.into())
-            .args(owner_script.calc_script_hash().as_bytes().pack())
-            .build();
+        let type_script = udt_type.build_script(&udt_script_id, &owner_script.calc_script_hash());
 
         let mut cheque_query = CellQueryOptions::new_lock(cheque_script);
         cheque_query.secondary_script = Some(type_script.clone());
@@ -723,10 +1094,12 @@ impl<'a> SudtSubCommand<'a> {
             header_dep_resolver: &self.header_dep_resolver,
             tx_dep_provider: &self.tx_dep_provider,
             builder: &builder,
+            xudt_extension_scripts,
         };
         let tx = udt_builder.build(
             accounts,
             privkeys,
+            external_signer.as_deref(),
             &cell_deps,
             capacity_provider,
             Some(acp_script_id),
@@ -766,13 +1139,16 @@ impl<'a> SudtSubCommand<'a> {
             to_acp_address,
         } = args;
         let SudtCommonArgs {
+            udt_type,
+            xudt_extension_scripts,
             privkeys,
+            external_signer,
             cell_deps,
             fee_rate,
             force_small_change_as_fee,
             debug,
         } = common_args;
-        let udt_script_id = get_script_id(&cell_deps, CellDepName::Sudt)?;
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
         let cheque_script_id = get_script_id(&cell_deps, CellDepName::Cheque)?;
         let acp_script_id = if to_acp_address {
             Some(get_script_id(&cell_deps, CellDepName::Acp)?)
@@ -794,11 +1170,7 @@ impl<'a> SudtSubCommand<'a> {
                 .args(Bytes::from(script_args).pack())
                 .build()
         };
-        let type_script = Script::new_builder()
-            .code_hash(udt_script_id.code_hash.pack())
-            .hash_type(udt_script_id.hash_type.into())
-            .args(owner_script.calc_script_hash().as_bytes().pack())
-            .build();
+        let type_script = udt_type.build_script(&udt_script_id, &owner_script.calc_script_hash());
 
         let mut cheque_query = CellQueryOptions::new_lock(cheque_script);
         cheque_query.secondary_script = Some(type_script);
@@ -838,10 +1210,12 @@ impl<'a> SudtSubCommand<'a> {
             header_dep_resolver: &self.header_dep_resolver,
             tx_dep_provider: &self.tx_dep_provider,
             builder: &builder,
+            xudt_extension_scripts,
         };
         let tx = udt_builder.build(
             accounts,
             privkeys,
+            external_signer.as_deref(),
             &cell_deps,
             capacity_provider,
             acp_script_id,
@@ -867,25 +1241,135 @@ impl<'a> SudtSubCommand<'a> {
             Ok(Output::new_output(resp))
         }
     }
+
+    fn registry(&self, matches: &ArgMatches) -> Result<Output, String> {
+        let mut registry = TokenRegistry::load(&self.ckb_cli_dir)?;
+        match matches.subcommand() {
+            ("add", Some(m)) => {
+                let symbol = m.value_of("symbol").unwrap().to_string();
+                let owner: Address = AddressParser::new_sighash().from_matches(m, "owner")?;
+                let cell_deps: PathBuf = FilePathParser::new(true).from_matches(m, "cell-deps")?;
+                let info = TokenInfo {
+                    owner: owner.to_string(),
+                    udt_type: m.value_of("udt-type").unwrap().to_string(),
+                    xudt_args: m.value_of("xudt-args").map(ToString::to_string),
+                    decimals: FromStrParser::<u8>::default().from_matches(m, "decimals")?,
+                    // The registry is used from any working directory
+                    cell_deps: cell_deps.canonicalize().map_err(|err| err.to_string())?,
+                };
+                registry.add(symbol.clone(), info.clone(), m.is_present("force"))?;
+                registry.save(&self.ckb_cli_dir)?;
+                Ok(Output::new_output(token_json(&symbol, &info)))
+            }
+            ("list", _) => {
+                let tokens = registry
+                    .tokens
+                    .iter()
+                    .map(|(symbol, info)| token_json(symbol, info))
+                    .collect::<Vec<_>>();
+                Ok(Output::new_output(tokens))
+            }
+            ("remove", Some(m)) => {
+                let symbol = m.value_of("symbol").unwrap();
+                let info = registry.remove(symbol)?;
+                registry.save(&self.ckb_cli_dir)?;
+                Ok(Output::new_output(token_json(symbol, &info)))
+            }
+            _ => Err(Self::subcommand("sudt").generate_usage()),
+        }
+    }
+
+    fn load_token(&self, m: &ArgMatches) -> Result<Option<TokenInfo>, String> {
+        match m.value_of("token") {
+            Some(symbol) => TokenRegistry::load(&self.ckb_cli_dir)?
+                .get(symbol)
+                .map(|info| Some(info.clone())),
+            None => Ok(None),
+        }
+    }
+
+    // <cell-deps> takes precedence over the cell deps file of <token>, then the cell deps file of
+    // the active profile
+    fn cell_deps_from_matches(&self, m: &ArgMatches) -> Result<CellDeps, String> {
+        if m.is_present("cell-deps") {
+            return CellDepsParser.from_matches(m, "cell-deps");
+        }
+        if let Some(info) = self.load_token(m)? {
+            return info.cell_deps();
+        }
+        match self
+            .rpc_client
+            .profile()
+            .and_then(|profile| profile.cell_deps.as_ref())
+        {
+            Some(path) => CellDepsParser.parse(&path.to_string_lossy()),
+            None => Err(
+                "<cell-deps> or <token> is required (or set cell deps in the active profile)"
+                    .to_string(),
+            ),
+        }
+    }
+
+    fn udt_info_from_matches(
+        &self,
+        m: &ArgMatches,
+        owner_parser: &AddressParser,
+    ) -> Result<UdtInfo, String> {
+        let xudt_extension_scripts: Option<ScriptVec> =
+            XudtExtensionScriptsParser.from_matches_opt(m, "xudt-extension-scripts")?;
+        if let Some(info) = self.load_token(m)? {
+            if m.is_present("owner")
+                || m.is_present("xudt-args")
+                || m.occurrences_of("udt-type") > 0
+            {
+                return Err(
+                    "<owner>, <udt-type> and <xudt-args> can not be used with <token>".to_string(),
+                );
+            }
+            let udt_type = info.udt_type()?;
+            check_xudt_extension(&udt_type, xudt_extension_scripts.as_ref())?;
+            Ok(UdtInfo {
+                owner: owner_parser.parse(&info.owner)?,
+                udt_type,
+                cell_deps: self.cell_deps_from_matches(m)?,
+                decimals: Some(info.decimals),
+                xudt_extension_scripts,
+            })
+        } else {
+            let udt_type = udt_type_from_matches(m)?;
+            check_xudt_extension(&udt_type, xudt_extension_scripts.as_ref())?;
+            Ok(UdtInfo {
+                owner: owner_parser.from_matches(m, "owner")?,
+                udt_type,
+                cell_deps: self.cell_deps_from_matches(m)?,
+                decimals: None,
+                xudt_extension_scripts,
+            })
+        }
+    }
 }
 
 impl<'a> CliSubCommand for SudtSubCommand<'a> {
     fn process(&mut self, matches: &ArgMatches, debug: bool) -> Result<Output, String> {
+        // The registry is a local file, no need to connect to the node
+        if let ("registry", Some(m)) = matches.subcommand() {
+            return self.registry(m);
+        }
         let network = get_network_type(self.rpc_client)?;
         match matches.subcommand() {
             ("issue", Some(m)) => {
-                let owner: Address = AddressParser::new_sighash()
-                    .set_network(network)
-                    .from_matches(m, "owner")?;
-                let udt_to_vec: Vec<(Address, u128)> = {
-                    let mut address_parser = AddressParser::default();
-                    address_parser.set_network(network);
-                    UdtTargetParser::new(address_parser).from_matches_vec(m, "udt-to")?
-                };
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    xudt_extension_scripts,
+                    decimals,
+                } = self
+                    .udt_info_from_matches(m, AddressParser::new_sighash().set_network(network))?;
+                let udt_to_vec = udt_targets_from_matches(m, network, decimals)?;
                 let privkeys: Vec<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_vec(m, "privkey-path")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
-                let fee_rate: u64 = FromStrParser::<u64>::default().from_matches(m, "fee-rate")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
                 let force_small_change_as_fee =
                     FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
                 let to_cheque_address = m.is_present("to-cheque-address");
@@ -907,7 +1391,10 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                         to_acp_address,
                     },
                     SudtCommonArgs {
+                        udt_type,
+                        xudt_extension_scripts,
                         privkeys,
+                        external_signer: m.value_of("external-signer").map(|s| s.to_string()),
                         cell_deps,
                         fee_rate,
                         force_small_change_as_fee,
@@ -917,26 +1404,25 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                 )
             }
             ("transfer", Some(m)) => {
-                let owner: Address = AddressParser::default()
-                    .set_network(network)
-                    .from_matches(m, "owner")?;
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    xudt_extension_scripts,
+                    decimals,
+                } = self.udt_info_from_matches(m, AddressParser::default().set_network(network))?;
                 let sender: Address = AddressParser::default()
                     .set_network(network)
                     .from_matches(m, "sender")?;
-                let udt_to_vec: Vec<(Address, u128)> = {
-                    let mut address_parser = AddressParser::default();
-                    address_parser.set_network(network);
-                    UdtTargetParser::new(address_parser).from_matches_vec(m, "udt-to")?
-                };
+                let udt_to_vec = udt_targets_from_matches(m, network, decimals)?;
                 let capacity_provider: Option<Address> = AddressParser::new_sighash()
                     .set_network(network)
                     .from_matches_opt(m, "capacity-provider")?;
                 let privkeys: Vec<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_vec(m, "privkey-path")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
                 let to_cheque_address = m.is_present("to-cheque-address");
                 let to_acp_address = m.is_present("to-acp-address");
-                let fee_rate: u64 = FromStrParser::<u64>::default().from_matches(m, "fee-rate")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
                 let force_small_change_as_fee =
                     FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
 
@@ -958,7 +1444,10 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                         capacity_provider,
                     },
                     SudtCommonArgs {
+                        udt_type,
+                        xudt_extension_scripts,
                         privkeys,
+                        external_signer: m.value_of("external-signer").map(|s| s.to_string()),
                         cell_deps,
                         fee_rate,
                         force_small_change_as_fee,
@@ -967,20 +1456,141 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                     network,
                 )
             }
+            ("airdrop", Some(m)) => {
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    xudt_extension_scripts,
+                    decimals,
+                } = self
+                    .udt_info_from_matches(m, AddressParser::new_sighash().set_network(network))?;
+                let sender: Option<Address> = AddressParser::default()
+                    .set_network(network)
+                    .from_matches_opt(m, "sender")?;
+                let file: PathBuf = FilePathParser::new(true).from_matches(m, "file")?;
+                let state_file: PathBuf =
+                    FilePathParser::new(false).from_matches(m, "state-file")?;
+                let cheque_fallback = m.is_present("cheque-fallback");
+                let batch_size: usize =
+                    FromStrParser::<usize>::default().from_matches(m, "batch-size")?;
+                let privkeys: Vec<PrivkeyWrapper> =
+                    PrivkeyPathParser.from_matches_vec(m, "privkey-path")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
+                let force_small_change_as_fee =
+                    FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
+                if batch_size == 0 {
+                    return Err("<batch-size> must be greater than 0".to_string());
+                }
+
+                let content = fs::read_to_string(&file).map_err(|err| err.to_string())?;
+                let recipients = parse_recipients(&content, &udt_target_parser(network, decimals))?;
+                if cheque_fallback {
+                    let sighash_script_id = ScriptId::new_type(SIGHASH_TYPE_HASH.clone());
+                    if let Some(recipient) = recipients.iter().find(|recipient| {
+                        ScriptId::from(&Script::from(&recipient.address)) != sighash_script_id
+                    }) {
+                        return Err(format!(
+                            "when <cheque-fallback> is presented, all recipients must be sighash address, invalid address at line {}: {}",
+                            recipient.line, recipient.address
+                        ));
+                    }
+                }
+
+                self.airdrop(
+                    AirdropArgs {
+                        owner,
+                        sender,
+                        recipients,
+                        file_hash: H256::from(blake2b_256(content.as_bytes())),
+                        state_file,
+                        cheque_fallback,
+                        batch_size,
+                    },
+                    SudtCommonArgs {
+                        udt_type,
+                        xudt_extension_scripts,
+                        privkeys,
+                        external_signer: m.value_of("external-signer").map(|s| s.to_string()),
+                        cell_deps,
+                        fee_rate,
+                        force_small_change_as_fee,
+                        debug,
+                    },
+                )
+            }
+            ("burn", Some(m)) => {
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    xudt_extension_scripts,
+                    decimals,
+                } = self
+                    .udt_info_from_matches(m, AddressParser::new_sighash().set_network(network))?;
+                let amount = udt_amount_from_matches(m, "amount", decimals)?;
+                let privkeys: Vec<PrivkeyWrapper> =
+                    PrivkeyPathParser.from_matches_vec(m, "privkey-path")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
+                let force_small_change_as_fee =
+                    FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
+                if amount == 0 {
+                    return Err("<amount> must be greater than 0".to_string());
+                }
+                self.burn(
+                    owner,
+                    amount,
+                    SudtCommonArgs {
+                        udt_type,
+                        xudt_extension_scripts,
+                        privkeys,
+                        external_signer: m.value_of("external-signer").map(|s| s.to_string()),
+                        cell_deps,
+                        fee_rate,
+                        force_small_change_as_fee,
+                        debug,
+                    },
+                )
+            }
+            ("supply", Some(m)) => {
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    decimals,
+                    ..
+                } = self.udt_info_from_matches(m, AddressParser::default().set_network(network))?;
+                let top: usize = FromStrParser::<usize>::default().from_matches(m, "top")?;
+                self.supply(owner, udt_type, cell_deps, decimals, top, network)
+            }
             ("get-amount", Some(m)) => {
-                let owner: Address = AddressParser::default()
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    decimals,
+                    ..
+                } = self.udt_info_from_matches(m, AddressParser::default().set_network(network))?;
+                let address: Address = AddressParser::default()
                     .set_network(network)
-                    .from_matches(m, "owner")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
+                    .from_matches(m, "address")?;
+                self.get_amount(owner, address, cell_deps, udt_type, decimals)
+            }
+            ("balances", Some(m)) => {
                 let address: Address = AddressParser::default()
                     .set_network(network)
                     .from_matches(m, "address")?;
-                self.get_amount(owner, address, cell_deps)
+                let cell_deps: CellDeps = self.cell_deps_from_matches(m)?;
+                self.balances(address, cell_deps, network)
             }
             ("new-empty-acp", Some(m)) => {
-                let owner: Address = AddressParser::default()
-                    .set_network(network)
-                    .from_matches(m, "owner")?;
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    xudt_extension_scripts,
+                    ..
+                } = self.udt_info_from_matches(m, AddressParser::default().set_network(network))?;
                 let to: Address = AddressParser::new_sighash()
                     .set_network(network)
                     .from_matches(m, "to")?;
@@ -989,8 +1599,7 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                     .from_matches_opt(m, "capacity-provider")?;
                 let privkeys: Vec<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_vec(m, "privkey-path")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
-                let fee_rate: u64 = FromStrParser::<u64>::default().from_matches(m, "fee-rate")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
                 let force_small_change_as_fee =
                     FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
                 self.new_empty_acp(
@@ -1000,7 +1609,10 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                         capacity_provider,
                     },
                     SudtCommonArgs {
+                        udt_type,
+                        xudt_extension_scripts,
                         privkeys,
+                        external_signer: m.value_of("external-signer").map(|s| s.to_string()),
                         cell_deps,
                         fee_rate,
                         force_small_change_as_fee,
@@ -1010,9 +1622,14 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                 )
             }
             ("cheque-claim", Some(m)) => {
-                let owner: Address = AddressParser::new_sighash()
-                    .set_network(network)
-                    .from_matches(m, "owner")?;
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    xudt_extension_scripts,
+                    ..
+                } = self
+                    .udt_info_from_matches(m, AddressParser::new_sighash().set_network(network))?;
                 let sender: Address = AddressParser::new_sighash()
                     .set_network(network)
                     .from_matches(m, "sender")?;
@@ -1024,8 +1641,7 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                     .from_matches_opt(m, "capacity-provider")?;
                 let privkeys: Vec<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_vec(m, "privkey-path")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
-                let fee_rate: u64 = FromStrParser::<u64>::default().from_matches(m, "fee-rate")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
                 let force_small_change_as_fee =
                     FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
 
@@ -1040,7 +1656,10 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                         capacity_provider,
                     },
                     SudtCommonArgs {
+                        udt_type,
+                        xudt_extension_scripts,
                         privkeys,
+                        external_signer: m.value_of("external-signer").map(|s| s.to_string()),
                         cell_deps,
                         fee_rate,
                         force_small_change_as_fee,
@@ -1049,9 +1668,14 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                 )
             }
             ("cheque-withdraw", Some(m)) => {
-                let owner: Address = AddressParser::new_sighash()
-                    .set_network(network)
-                    .from_matches(m, "owner")?;
+                let UdtInfo {
+                    owner,
+                    udt_type,
+                    cell_deps,
+                    xudt_extension_scripts,
+                    ..
+                } = self
+                    .udt_info_from_matches(m, AddressParser::new_sighash().set_network(network))?;
                 let sender: Address = AddressParser::new_sighash()
                     .set_network(network)
                     .from_matches(m, "sender")?;
@@ -1064,8 +1688,7 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                 let to_acp_address = m.is_present("to-acp-address");
                 let privkeys: Vec<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_vec(m, "privkey-path")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
-                let fee_rate: u64 = FromStrParser::<u64>::default().from_matches(m, "fee-rate")?;
+                let fee_rate: u64 = get_fee_rate(m, self.rpc_client)?;
                 let force_small_change_as_fee =
                     FromStrParser::<HumanCapacity>::default().from_matches_opt(m, "max-tx-fee")?;
                 self.cheque_withdraw(
@@ -1077,7 +1700,10 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                         to_acp_address,
                     },
                     SudtCommonArgs {
+                        udt_type,
+                        xudt_extension_scripts,
                         privkeys,
+                        external_signer: m.value_of("external-signer").map(|s| s.to_string()),
                         cell_deps,
                         fee_rate,
                         force_small_change_as_fee,
@@ -1085,11 +1711,40 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                     },
                 )
             }
+            ("cheque-list", Some(m)) => {
+                let sender: Option<Address> = AddressParser::new_sighash()
+                    .set_network(network)
+                    .from_matches_opt(m, "sender")?;
+                let receiver: Address = AddressParser::new_sighash()
+                    .set_network(network)
+                    .from_matches(m, "receiver")?;
+                let cell_deps = self.cell_deps_from_matches(m)?;
+                let (type_script, decimals) = match self.load_token(m)? {
+                    Some(info) => {
+                        let owner: Address = AddressParser::default()
+                            .set_network(network)
+                            .parse(&info.owner)?;
+                        let udt_type = info.udt_type()?;
+                        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
+                        let type_script = udt_type
+                            .build_script(&udt_script_id, &Script::from(&owner).calc_script_hash());
+                        (Some(type_script), Some(info.decimals))
+                    }
+                    None => (None, None),
+                };
+                self.cheque_list(ChequeListArgs {
+                    sender,
+                    receiver,
+                    type_script,
+                    cell_deps,
+                    decimals,
+                })
+            }
             ("build-acp-address", Some(m)) => {
                 let sighash_addr: Address = AddressParser::new_sighash()
                     .set_network(network)
                     .from_matches(m, "sighash-address")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
+                let cell_deps = self.cell_deps_from_matches(m)?;
                 let acp_script_id = get_script_id(&cell_deps, CellDepName::Acp)?;
                 let acp_script = Script::new_builder()
                     .code_hash(acp_script_id.code_hash.pack())
@@ -1107,7 +1762,7 @@ impl<'a> CliSubCommand for SudtSubCommand<'a> {
                 let receiver: Address = AddressParser::new_sighash()
                     .set_network(network)
                     .from_matches(m, "receiver")?;
-                let cell_deps: CellDeps = CellDepsParser.from_matches(m, "cell-deps")?;
+                let cell_deps = self.cell_deps_from_matches(m)?;
 
                 let cheque_script_id = get_script_id(&cell_deps, CellDepName::Cheque)?;
                 let sender_script_hash = Script::from(&sender).calc_script_hash();
@@ -1136,6 +1791,116 @@ pub fn get_script_id(cell_deps: &CellDeps, name: CellDepName) -> Result<ScriptId
         .ok_or_else(|| format!("no {} cell_dep item in cell_deps", name))
 }
 
+/// The script id of the UDT type script, `xudt` cell_dep item is required for xUDT
+pub fn get_udt_script_id(cell_deps: &CellDeps, udt_type: &UdtType) -> Result<ScriptId, String> {
+    match udt_type {
+        UdtType::Sudt => get_script_id(cell_deps, CellDepName::Sudt),
+        UdtType::Xudt(_) => get_script_id(cell_deps, CellDepName::Xudt),
+    }
+}
+
+fn udt_type_from_matches(m: &ArgMatches) -> Result<UdtType, String> {
+    let xudt_args: Option<Bytes> = XudtArgsParser.from_matches_opt(m, "xudt-args")?;
+    let udt_type = match m.value_of("udt-type") {
+        Some("xudt") => UdtType::Xudt(xudt_args.unwrap_or_default()),
+        _ => {
+            if xudt_args.is_some() {
+                return Err("<xudt-args> is only allowed when <udt-type> is xudt".to_string());
+            }
+            UdtType::Sudt
+        }
+    };
+    Ok(udt_type)
+}
+
+/// <xudt-extension-scripts> is only used when the xUDT args has the hash of the extension scripts
+fn check_xudt_extension(
+    udt_type: &UdtType,
+    extension_scripts: Option<&ScriptVec>,
+) -> Result<(), String> {
+    let scripts = match extension_scripts {
+        Some(scripts) => scripts,
+        None => return Ok(()),
+    };
+    match udt_type {
+        UdtType::Xudt(xudt_args)
+            if XudtArgsParser::flags(xudt_args.as_ref()) & XUDT_FLAGS_EXTENSION_MASK
+                == XUDT_EXTENSION_SCRIPTS_HASH =>
+        {
+            if extension_scripts_hash(scripts) != xudt_args[4..] {
+                return Err(
+                    "The hash of <xudt-extension-scripts> does not match the xUDT args".to_string(),
+                );
+            }
+            Ok(())
+        }
+        _ => Err(
+            "<xudt-extension-scripts> is only allowed when the xUDT flags is 2 (extension scripts hash)"
+                .to_string(),
+        ),
+    }
+}
+
+fn udt_target_parser(network: NetworkType, decimals: Option<u8>) -> UdtTargetParser {
+    let mut address_parser = AddressParser::default();
+    address_parser.set_network(network);
+    let parser = UdtTargetParser::new(address_parser);
+    match decimals {
+        Some(decimals) => parser.with_decimals(decimals),
+        None => parser,
+    }
+}
+
+fn udt_targets_from_matches(
+    m: &ArgMatches,
+    network: NetworkType,
+    decimals: Option<u8>,
+) -> Result<Vec<(Address, u128)>, String> {
+    udt_target_parser(network, decimals).from_matches_vec(m, "udt-to")
+}
+
+// The decimals are unknown before <token> is resolved, so the validators below accept both raw
+// and decimal amounts
+fn validate_udt_target(input: &str) -> Result<(), String> {
+    UdtTargetParser::new(AddressParser::default())
+        .validate(input)
+        .or_else(|_| {
+            UdtTargetParser::new(AddressParser::default())
+                .with_decimals(MAX_UDT_DECIMALS)
+                .validate(input)
+        })
+}
+
+fn validate_udt_amount(input: &str) -> Result<(), String> {
+    FromStrParser::<u128>::default()
+        .validate(input)
+        .or_else(|_| parse_decimal_amount(input, MAX_UDT_DECIMALS).map(|_| ()))
+}
+
+fn udt_amount_from_matches(
+    m: &ArgMatches,
+    name: &str,
+    decimals: Option<u8>,
+) -> Result<u128, String> {
+    let input = m.value_of(name).unwrap();
+    match decimals {
+        Some(decimals) => parse_decimal_amount(input, decimals),
+        None => FromStrParser::<u128>::default().parse(input),
+    }
+    .map_err(|err| format!("invalid amount: {}, error: {}", input, err))
+}
+
+fn token_json(symbol: &str, info: &TokenInfo) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": symbol,
+        "owner": info.owner,
+        "udt_type": info.udt_type,
+        "xudt_args": info.xudt_args,
+        "decimals": info.decimals,
+        "cell_deps": info.cell_deps,
+    })
+}
+
 fn check_udt_args(
     udt_to: &[(Address, u128)],
     to_cheque_address: bool,
@@ -1208,7 +1973,7 @@ pub fn arg_owner<'a>() -> Arg<'a> {
     Arg::with_name("owner")
         .long("owner")
         .takes_value(true)
-        .required(true)
+        .required_unless("token")
         .validator(|input| AddressParser::new_sighash().validate(input))
         .about("The owner address of the SUDT cell (the admin address, only sighash address is supported)")
 }
@@ -1232,13 +1997,47 @@ pub fn arg_to_acp_address<'a>() -> Arg<'a> {
         .long("to-acp-address")
         .about("Treat all addresses in <udt-to> as anyone-can-pay address")
 }
+pub fn arg_udt_type<'a>() -> Arg<'a> {
+    Arg::with_name("udt-type")
+        .long("udt-type")
+        .takes_value(true)
+        .possible_values(&["sudt", "xudt"])
+        .default_value("sudt")
+        .about("The UDT kind, the cell_dep item with the same name is required in <cell-deps>")
+}
+pub fn arg_xudt_args<'a>() -> Arg<'a> {
+    Arg::with_name("xudt-args")
+        .long("xudt-args")
+        .takes_value(true)
+        .validator(|input| XudtArgsParser.validate(input))
+        .about("The xUDT type script args after the owner lock hash (hex format): <4 bytes flags (little endian)> <extension data>, flags 0: no extension, 1: extension data is the extension scripts (molecule ScriptVec), 2: extension data is the blake160 hash of the extension scripts. The cell deps of the extension scripts are required in the `extensions` of <cell-deps> by the transaction commands")
+}
+pub fn arg_xudt_extension_scripts<'a>() -> Arg<'a> {
+    Arg::with_name("xudt-extension-scripts")
+        .long("xudt-extension-scripts")
+        .takes_value(true)
+        .validator(|input| XudtExtensionScriptsParser.validate(input))
+        .about("The xUDT extension scripts (hex format, molecule ScriptVec), required when the xUDT flags is 2, they are put in the witness of the transaction")
+}
 pub fn arg_cell_deps<'a>() -> Arg<'a> {
     Arg::with_name("cell-deps")
         .long("cell-deps")
         .takes_value(true)
-        .required(true)
         .validator(|input| CellDepsParser.validate(input))
-        .about("The cell deps information (for resolve cell_dep by script id or build lock/type script)")
+        .about("The cell deps information (for resolve cell_dep by script id or build lock/type script), overrides the cell deps of <token> and the active profile")
+}
+pub fn arg_token<'a>() -> Arg<'a> {
+    Arg::with_name("token")
+        .long("token")
+        .takes_value(true)
+        .about("The token symbol in the registry (see `sudt registry`), used instead of <owner>, <udt-type>, <xudt-args> and <cell-deps>")
+}
+pub fn arg_symbol<'a>() -> Arg<'a> {
+    Arg::with_name("symbol")
+        .long("symbol")
+        .takes_value(true)
+        .required(true)
+        .about("The token symbol")
 }
 
 pub struct UdtTxBuilder<'a> {
@@ -1249,6 +2048,8 @@ pub struct UdtTxBuilder<'a> {
     pub header_dep_resolver: &'a DefaultHeaderDepResolver,
     pub tx_dep_provider: &'a DefaultTransactionDependencyProvider,
     pub builder: &'a dyn TxBuilder,
+    /// Put in the witness when the xUDT args only has the hash of the extension scripts
+    pub xudt_extension_scripts: Option<ScriptVec>,
 }
 
 impl<'a> UdtTxBuilder<'a> {
@@ -1257,15 +2058,53 @@ impl<'a> UdtTxBuilder<'a> {
         &mut self,
         accounts: Vec<(String, H160)>,
         privkeys: Vec<PrivkeyWrapper>,
+        external_signer: Option<&str>,
+        cell_deps: &CellDeps,
+        capacity_provider: Script,
+        acp_script_id: Option<ScriptId>,
+        cheque_script_id: Option<(ScriptId, ChequeAction)>,
+        fee_rate: u64,
+        force_small_change_as_fee: Option<u64>,
+    ) -> Result<TransactionView, String> {
+        let mut passwords = HashMap::with_capacity(accounts.len());
+        self.build_with_passwords(
+            accounts,
+            privkeys,
+            external_signer,
+            cell_deps,
+            capacity_provider,
+            acp_script_id,
+            cheque_script_id,
+            fee_rate,
+            force_small_change_as_fee,
+            &mut passwords,
+        )
+    }
+
+    /// Same as `build`, the keystore passwords read from the terminal are cached in `passwords`,
+    /// so they are only asked once when building multiple transactions.
+    ///
+    /// The accounts not found in `privkeys` are signed by `external_signer` if it's given,
+    /// otherwise by the keystore.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_with_passwords(
+        &mut self,
+        accounts: Vec<(String, H160)>,
+        privkeys: Vec<PrivkeyWrapper>,
+        external_signer: Option<&str>,
         cell_deps: &CellDeps,
         capacity_provider: Script,
         acp_script_id: Option<ScriptId>,
         cheque_script_id: Option<(ScriptId, ChequeAction)>,
         fee_rate: u64,
         force_small_change_as_fee: Option<u64>,
+        passwords: &mut HashMap<H160, String>,
     ) -> Result<TransactionView, String> {
-        let mut passwords: HashMap<H160, String> = HashMap::with_capacity(accounts.len());
         let sighash_script_id = ScriptId::new_type(SIGHASH_TYPE_HASH.clone());
+        // All the unlockers share one external signer session
+        let external_signer = external_signer.map(|command| {
+            ExternalSigner::from_rpc_url(command.to_string(), self.rpc_client.url())
+        });
         let mut get_signer = || -> Result<Box<dyn Signer>, String> {
             let handler = self.plugin_mgr.keystore_handler();
             let mut keystore_signer = KeyStoreHandlerSigner::new(
@@ -1276,18 +2115,27 @@ impl<'a> UdtTxBuilder<'a> {
                 )),
             );
             let mut privkey_signer = PrivkeySigner::new(privkeys.clone());
+            let mut external_signer = external_signer.clone();
             for (name, account) in accounts.clone() {
                 if privkey_signer.has_account(&account) {
                     if cheque_script_id.is_some() {
                         let _ = privkey_signer
                             .cache_account_lock_hash160(account.clone(), &sighash_script_id);
                     }
+                } else if let Some(external_signer) = external_signer.as_mut() {
+                    external_signer.add_account(account.clone());
+                    if cheque_script_id.is_some() {
+                        let _ = external_signer
+                            .cache_account_lock_hash160(account.clone(), &sighash_script_id);
+                    }
                 } else {
                     if !handler.has_account(account.clone()).unwrap_or_default() {
                         return Err(format!("no such account in keystore: {}", name));
                     }
                     let change_path = handler.root_key_path(account.clone())?;
-                    if self.plugin_mgr.keystore_require_password() {
+                    if self.plugin_mgr.keystore_require_password()
+                        && !is_unlocked_in_agent(&account)
+                    {
                         let password = if let Some(password) = passwords.get(&account) {
                             password.clone()
                         } else {
@@ -1306,10 +2154,12 @@ impl<'a> UdtTxBuilder<'a> {
                     keystore_signer.set_change_path(account, change_path.to_string());
                 }
             }
-            Ok(Box::new(CommonSigner::new(vec![
-                Box::new(privkey_signer),
-                Box::new(keystore_signer),
-            ])))
+            let mut signers: Vec<Box<dyn Signer>> = vec![Box::new(privkey_signer)];
+            if let Some(external_signer) = external_signer {
+                signers.push(Box::new(external_signer));
+            }
+            signers.push(Box::new(keystore_signer));
+            Ok(Box::new(CommonSigner::new(signers)))
         };
 
         let mut unlockers: HashMap<_, Box<dyn ScriptUnlocker>> = HashMap::new();
@@ -1340,8 +2190,14 @@ impl<'a> UdtTxBuilder<'a> {
 
         cell_deps.apply_to_resolver(self.cell_dep_resolver)?;
 
-        let (tx, still_locked_groups) = self
-            .builder
+        let builder = XudtExtensionBuilder {
+            builder: self.builder,
+            xudt_script_id: cell_deps
+                .get_item(CellDepName::Xudt)
+                .map(|item| item.script_id.clone().into()),
+            extension_scripts: self.xudt_extension_scripts.clone(),
+        };
+        let (tx, still_locked_groups) = builder
             .build_unlocked(
                 self.cell_collector,
                 self.cell_dep_resolver,
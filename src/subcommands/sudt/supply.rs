@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use ckb_sdk::{
+    traits::{CellCollector, CellQueryOptions},
+    tx_builder::udt::UdtType,
+    Address, AddressPayload, NetworkType,
+};
+use ckb_types::packed::Script;
+
+use super::{get_udt_script_id, SudtSubCommand};
+use crate::subcommands::Output;
+use crate::utils::{cell_dep::CellDeps, token_registry::format_decimal_amount};
+
+impl<'a> SudtSubCommand<'a> {
+    /// Sum all live cells of the UDT, the holders are grouped by lock script
+    pub(super) fn supply(
+        &mut self,
+        owner: Address,
+        udt_type: UdtType,
+        cell_deps: CellDeps,
+        decimals: Option<u8>,
+        top: usize,
+        network: NetworkType,
+    ) -> Result<Output, String> {
+        let udt_script_id = get_udt_script_id(&cell_deps, &udt_type)?;
+        let owner_script_hash = Script::from(&owner).calc_script_hash();
+        let type_script = udt_type.build_script(&udt_script_id, &owner_script_hash);
+
+        let mut query = CellQueryOptions::new_type(type_script);
+        query.min_total_capacity = u64::max_value();
+        let (cells, _) = self
+            .cell_collector
+            .collect_live_cells(&query, false)
+            .map_err(|err| err.to_string())?;
+        let cell_count = cells.len();
+        let mut total_supply: u128 = 0;
+        let mut holders: HashMap<Script, u128> = HashMap::new();
+        for cell in cells {
+            if cell.output_data.len() < 16 {
+                return Err(format!(
+                    "invalid cell data length: {}, expected: >= 16",
+                    cell.output_data.len()
+                ));
+            }
+            let mut amount_bytes = [0u8; 16];
+            amount_bytes.copy_from_slice(&cell.output_data.as_ref()[0..16]);
+            let amount = u128::from_le_bytes(amount_bytes);
+            total_supply = total_supply
+                .checked_add(amount)
+                .ok_or_else(|| "UDT amount overflow".to_string())?;
+            *holders.entry(cell.output.lock()).or_default() += amount;
+        }
+
+        let holder_count = holders.len();
+        let mut holders = holders.into_iter().collect::<Vec<_>>();
+        holders.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let top_holders = holders
+            .into_iter()
+            .take(top)
+            .map(|(lock_script, amount)| {
+                let address = Address::new(network, AddressPayload::from(lock_script), true);
+                let share = if total_supply == 0 {
+                    0.0
+                } else {
+                    amount as f64 * 100.0 / total_supply as f64
+                };
+                let mut holder = serde_json::json!({
+                    "address": address.to_string(),
+                    // u128 is too large for json
+                    "amount": amount.to_string(),
+                    "share": format!("{:.2}%", share),
+                });
+                if let Some(decimals) = decimals {
+                    holder["formatted_amount"] = format_decimal_amount(amount, decimals).into();
+                }
+                holder
+            })
+            .collect::<Vec<_>>();
+        let mut resp = serde_json::json!({
+            "total_supply": total_supply.to_string(),
+            "cell_count": cell_count,
+            "holder_count": holder_count,
+            "top_holders": top_holders,
+        });
+        if let Some(decimals) = decimals {
+            resp["decimals"] = decimals.into();
+            resp["formatted_total_supply"] = format_decimal_amount(total_supply, decimals).into();
+        }
+        Ok(Output::new_output(resp))
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use ckb_hash::blake2b_256;
+use ckb_sdk::{
+    traits::{CellCollector, CellDepResolver, HeaderDepResolver, TransactionDependencyProvider},
+    tx_builder::{TxBuilder, TxBuilderError},
+    types::{
+        xudt_rce_mol::{ScriptVec, ScriptVecOpt, XudtWitnessInput},
+        ScriptId,
+    },
+};
+use ckb_types::{
+    core::TransactionView,
+    packed::{Script, WitnessArgs},
+    prelude::*,
+};
+
+use crate::utils::arg_parser::{
+    XudtArgsParser, XUDT_EXTENSION_SCRIPTS, XUDT_EXTENSION_SCRIPTS_HASH, XUDT_FLAGS_EXTENSION_MASK,
+};
+
+/// Wraps a UDT transaction builder to run the xUDT extension scripts: their cell deps are added,
+/// and when the xUDT args only has the hash of the extension scripts, the scripts are put in the
+/// witness of the xUDT type script group.
+pub struct XudtExtensionBuilder<'a> {
+    pub builder: &'a dyn TxBuilder,
+    /// The script id of the `xudt` cell_dep item
+    pub xudt_script_id: Option<ScriptId>,
+    /// The extension scripts of the xUDT args with the extension scripts hash
+    pub extension_scripts: Option<ScriptVec>,
+}
+
+impl<'a> XudtExtensionBuilder<'a> {
+    /// The extension scripts of the type script and whether they are put in the witness
+    fn extension_of(&self, type_script: &Script) -> Result<Option<(ScriptVec, bool)>, String> {
+        // <32 bytes owner lock hash> <4 bytes flags> <extension data>
+        let args = type_script.args().raw_data();
+        let xudt_args = if args.len() > 32 { &args[32..] } else { &[] };
+        match XudtArgsParser::flags(xudt_args) & XUDT_FLAGS_EXTENSION_MASK {
+            XUDT_EXTENSION_SCRIPTS => ScriptVec::from_slice(&xudt_args[4..])
+                .map(|scripts| Some((scripts, false)))
+                .map_err(|err| format!("Invalid xUDT extension scripts: {}", err)),
+            XUDT_EXTENSION_SCRIPTS_HASH => {
+                let scripts = self.extension_scripts.clone().ok_or_else(|| {
+                    "<xudt-extension-scripts> is required, the xUDT args only has the hash of the extension scripts".to_string()
+                })?;
+                if extension_scripts_hash(&scripts) != xudt_args[4..] {
+                    return Err(
+                        "The hash of <xudt-extension-scripts> does not match the xUDT args"
+                            .to_string(),
+                    );
+                }
+                Ok(Some((scripts, true)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl<'a> TxBuilder for XudtExtensionBuilder<'a> {
+    fn build_base(
+        &self,
+        cell_collector: &mut dyn CellCollector,
+        cell_dep_resolver: &dyn CellDepResolver,
+        header_dep_resolver: &dyn HeaderDepResolver,
+        tx_dep_provider: &dyn TransactionDependencyProvider,
+    ) -> Result<TransactionView, TxBuilderError> {
+        let tx = self.builder.build_base(
+            cell_collector,
+            cell_dep_resolver,
+            header_dep_resolver,
+            tx_dep_provider,
+        )?;
+        let xudt_script_id = match self.xudt_script_id.as_ref() {
+            Some(script_id) => script_id,
+            None => return Ok(tx),
+        };
+
+        // The first input (or output if there is no input) of each xUDT type script group:
+        // (type script, index, is input)
+        let mut group_heads = Vec::new();
+        for (index, input) in tx.inputs().into_iter().enumerate() {
+            let output = tx_dep_provider.get_cell(&input.previous_output())?;
+            if let Some(type_script) = output.type_().to_opt() {
+                group_heads.push((type_script, index, true));
+            }
+        }
+        for (index, output) in tx.outputs().into_iter().enumerate() {
+            if let Some(type_script) = output.type_().to_opt() {
+                group_heads.push((type_script, index, false));
+            }
+        }
+
+        let mut cell_deps = tx.cell_deps().into_iter().collect::<Vec<_>>();
+        let mut witnesses = tx.witnesses().into_iter().collect::<Vec<_>>();
+        let mut visited = HashSet::new();
+        for (type_script, index, is_input) in group_heads {
+            if ScriptId::from(&type_script) != *xudt_script_id
+                || !visited.insert(type_script.clone())
+            {
+                continue;
+            }
+            let (scripts, in_witness) = match self
+                .extension_of(&type_script)
+                .map_err(|err| TxBuilderError::InvalidParameter(anyhow!(err)))?
+            {
+                Some(extension) => extension,
+                None => continue,
+            };
+            for script in scripts.clone().into_iter() {
+                let cell_dep = cell_dep_resolver
+                    .resolve(&script)
+                    .ok_or(TxBuilderError::ResolveCellDepFailed(script))?;
+                if !cell_deps.contains(&cell_dep) {
+                    cell_deps.push(cell_dep);
+                }
+            }
+            if in_witness {
+                while witnesses.len() <= index {
+                    witnesses.push(Default::default());
+                }
+                let witness_data = witnesses[index].raw_data();
+                let witness = if witness_data.is_empty() {
+                    WitnessArgs::default()
+                } else {
+                    WitnessArgs::from_slice(witness_data.as_ref())
+                        .map_err(|err| TxBuilderError::Other(anyhow!(err)))?
+                };
+                let witness_input = XudtWitnessInput::new_builder()
+                    .raw_extension_data(ScriptVecOpt::new_builder().set(Some(scripts)).build())
+                    .build();
+                let data = Some(witness_input.as_bytes()).pack();
+                let witness = if is_input {
+                    witness.as_builder().input_type(data)
+                } else {
+                    witness.as_builder().output_type(data)
+                };
+                witnesses[index] = witness.build().as_bytes().pack();
+            }
+        }
+        Ok(tx
+            .as_advanced_builder()
+            .set_cell_deps(cell_deps)
+            .set_witnesses(witnesses)
+            .build())
+    }
+}
+
+/// The blake160 hash of the extension scripts in the xUDT args
+pub fn extension_scripts_hash(scripts: &ScriptVec) -> [u8; 20] {
+    let mut hash = [0u8; 20];
+    hash.copy_from_slice(&blake2b_256(scripts.as_slice())[0..20]);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_sdk::traits::default_impls::{
+        DefaultCellCollector, DefaultHeaderDepResolver, DefaultTransactionDependencyProvider,
+    };
+    use ckb_types::{
+        bytes::Bytes,
+        core::ScriptHashType,
+        h256,
+        packed::{CellDep, CellOutput, OutPoint},
+    };
+
+    struct OutputsBuilder(Vec<CellOutput>);
+
+    impl TxBuilder for OutputsBuilder {
+        fn build_base(
+            &self,
+            _cell_collector: &mut dyn CellCollector,
+            _cell_dep_resolver: &dyn CellDepResolver,
+            _header_dep_resolver: &dyn HeaderDepResolver,
+            _tx_dep_provider: &dyn TransactionDependencyProvider,
+        ) -> Result<TransactionView, TxBuilderError> {
+            Ok(TransactionView::new_advanced_builder()
+                .outputs(self.0.clone())
+                .outputs_data(vec![Bytes::from(vec![0u8; 16]).pack(); self.0.len()])
+                .build())
+        }
+    }
+
+    /// Only resolves the extension script
+    struct ExtensionResolver(Script);
+
+    impl CellDepResolver for ExtensionResolver {
+        fn resolve(&self, script: &Script) -> Option<CellDep> {
+            if script.code_hash() == self.0.code_hash() {
+                Some(extension_cell_dep())
+            } else {
+                None
+            }
+        }
+    }
+
+    fn extension_cell_dep() -> CellDep {
+        CellDep::new_builder()
+            .out_point(OutPoint::new(h256!("0xe1").pack(), 0))
+            .build()
+    }
+
+    fn build(
+        builder: &XudtExtensionBuilder,
+        resolver: &ExtensionResolver,
+    ) -> Result<TransactionView, TxBuilderError> {
+        // No rpc request since there is no input
+        let url = "http://127.0.0.1:8114";
+        builder.build_base(
+            &mut DefaultCellCollector::new(url),
+            resolver,
+            &DefaultHeaderDepResolver::new(url),
+            &DefaultTransactionDependencyProvider::new(url, 0),
+        )
+    }
+
+    #[test]
+    fn test_xudt_extension_builder() {
+        let xudt_script_id = ScriptId::new_type(h256!("0x5d"));
+        let extension_script = Script::new_builder()
+            .code_hash(h256!("0xe0").pack())
+            .hash_type(ScriptHashType::Data1.into())
+            .build();
+        let scripts = ScriptVec::new_builder()
+            .push(extension_script.clone())
+            .build();
+        let resolver = ExtensionResolver(extension_script);
+        let xudt_output = |flags: u32, extension_data: &[u8]| {
+            let mut args = vec![0u8; 32];
+            args.extend_from_slice(&flags.to_le_bytes());
+            args.extend_from_slice(extension_data);
+            let type_script = Script::new_builder()
+                .code_hash(xudt_script_id.code_hash.pack())
+                .hash_type(xudt_script_id.hash_type.into())
+                .args(Bytes::from(args).pack())
+                .build();
+            CellOutput::new_builder()
+                .type_(Some(type_script).pack())
+                .build()
+        };
+
+        // The extension scripts hash in args, the scripts are put in the first output witness
+        let outputs = OutputsBuilder(vec![
+            CellOutput::default(),
+            xudt_output(
+                XUDT_EXTENSION_SCRIPTS_HASH,
+                &extension_scripts_hash(&scripts),
+            ),
+        ]);
+        let mut builder = XudtExtensionBuilder {
+            builder: &outputs,
+            xudt_script_id: Some(xudt_script_id.clone()),
+            extension_scripts: Some(scripts.clone()),
+        };
+        let tx = build(&builder, &resolver).unwrap();
+        assert_eq!(
+            tx.cell_deps().into_iter().collect::<Vec<_>>(),
+            vec![extension_cell_dep()]
+        );
+        assert_eq!(tx.witnesses().len(), 2);
+        assert!(tx.witnesses().get(0).unwrap().is_empty());
+        let witness = WitnessArgs::from_slice(&tx.witnesses().get(1).unwrap().raw_data()).unwrap();
+        assert!(witness.input_type().is_none());
+        let witness_input =
+            XudtWitnessInput::from_slice(&witness.output_type().to_opt().unwrap().raw_data())
+                .unwrap();
+        assert_eq!(
+            witness_input.raw_extension_data().as_slice(),
+            ScriptVecOpt::new_builder()
+                .set(Some(scripts.clone()))
+                .build()
+                .as_slice()
+        );
+
+        // The scripts must match the hash
+        builder.extension_scripts = Some(ScriptVec::default());
+        assert!(build(&builder, &resolver).is_err());
+        builder.extension_scripts = None;
+        assert!(build(&builder, &resolver).is_err());
+
+        // The extension scripts in args, only the cell deps are added
+        let outputs = OutputsBuilder(vec![xudt_output(
+            XUDT_EXTENSION_SCRIPTS,
+            scripts.as_slice(),
+        )]);
+        builder.builder = &outputs;
+        let tx = build(&builder, &resolver).unwrap();
+        assert_eq!(tx.cell_deps().len(), 1);
+        assert_eq!(tx.witnesses().len(), 0);
+
+        let unknown_scripts = ScriptVec::new_builder().push(Script::default()).build();
+        let outputs = OutputsBuilder(vec![xudt_output(
+            XUDT_EXTENSION_SCRIPTS,
+            unknown_scripts.as_slice(),
+        )]);
+        builder.builder = &outputs;
+        assert!(matches!(
+            build(&builder, &resolver),
+            Err(TxBuilderError::ResolveCellDepFailed(_))
+        ));
+
+        // No extension or not the xUDT script
+        let outputs = OutputsBuilder(vec![xudt_output(0, &[])]);
+        builder.builder = &outputs;
+        assert_eq!(build(&builder, &resolver).unwrap().cell_deps().len(), 0);
+        let outputs = OutputsBuilder(vec![xudt_output(
+            XUDT_EXTENSION_SCRIPTS,
+            scripts.as_slice(),
+        )]);
+        builder.builder = &outputs;
+        builder.xudt_script_id = None;
+        assert_eq!(build(&builder, &resolver).unwrap().cell_deps().len(), 0);
+    }
+}
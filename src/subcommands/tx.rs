@@ -9,6 +9,7 @@ use ckb_jsonrpc_types as json_types;
 use ckb_jsonrpc_types::JsonBytes;
 use ckb_sdk::{
     constants::{MULTISIG_TYPE_HASH, SECP_SIGNATURE_SIZE},
+    traits::Signer,
     unlock::MultisigConfig,
     Address, AddressPayload, CodeHashIndex, HumanCapacity, NetworkType,
 };
@@ -24,20 +25,21 @@ use clap::{App, Arg, ArgMatches};
 use faster_hex::hex_string;
 use serde_derive::{Deserialize, Serialize};
 
-use super::{CliSubCommand, Output};
+use super::{dao::util::calculate_dao_compensation, CliSubCommand, Output};
 use crate::plugin::{KeyStoreHandler, PluginManager, SignTarget};
 use crate::utils::{
     arg,
     arg_parser::{
-        AddressParser, ArgParser, CapacityParser, FilePathParser, FixedHashParser, FromStrParser,
-        HexParser, PrivkeyPathParser, PrivkeyWrapper,
+        AccountParser, AddressParser, ArgParser, CapacityParser, FilePathParser, FixedHashParser,
+        FromStrParser, HexParser, PrivkeyPathParser, PrivkeyWrapper,
     },
     genesis_info::GenesisInfo,
     other::{
         check_capacity, get_genesis_info, get_live_cell, get_live_cell_with_cache,
-        get_network_type, get_privkey_signer, get_to_data, read_password,
+        get_network_type, get_privkey_signer, get_to_data,
     },
     rpc::HttpRpcClient,
+    signer::ExternalSigner,
     tx_helper::{SignerFn, TxHelper},
 };
 
@@ -205,6 +207,11 @@ impl<'a> TxSubCommand<'a> {
                     .about("Sign all sighash/multisig inputs in this transaction")
                     .arg(arg::privkey_path().required_unless(arg::from_account().get_name()))
                     .arg(arg::from_account().required_unless(arg::privkey_path().get_name()))
+                    .arg(
+                        arg::external_signer()
+                            .requires(arg::from_account().get_name())
+                            .conflicts_with(arg::privkey_path().get_name()),
+                    )
                     .arg(arg_tx_file.clone())
                     .arg(
                         Arg::with_name("add-signatures")
@@ -431,6 +438,9 @@ impl<'a> CliSubCommand for TxSubCommand<'a> {
                         type_script_empty,
                     );
                 }
+                // Prepared NervosDAO cells are withdrawn with compensation
+                let dao_compensation = calculate_dao_compensation(self.rpc_client, tx)?;
+                input_total += dao_compensation;
                 let tx_fee_string = if input_total >= output_total {
                     format!("{:#}", HumanCapacity(input_total - output_total))
                 } else {
@@ -440,6 +450,7 @@ impl<'a> CliSubCommand for TxSubCommand<'a> {
                 let resp = serde_json::json!({
                     "input_total": format!("{:#}", HumanCapacity(input_total)),
                     "output_total": format!("{:#}", HumanCapacity(output_total)),
+                    "dao_compensation": format!("{:#}", HumanCapacity(dao_compensation)),
                     "tx_fee": tx_fee_string,
                 });
                 Ok(Output::new_output(resp))
@@ -448,34 +459,23 @@ impl<'a> CliSubCommand for TxSubCommand<'a> {
                 let tx_file: PathBuf = FilePathParser::new(true).from_matches(m, "tx-file")?;
                 let privkey_opt: Option<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_opt(m, "privkey-path")?;
-                let account_opt: Option<H160> = m
-                    .value_of("from-account")
-                    .map(|input| {
-                        FixedHashParser::<H160>::default()
-                            .parse(input)
-                            .or_else(|err| {
-                                let result: Result<Address, String> = AddressParser::new_sighash()
-                                    .set_network(network)
-                                    .parse(input);
-                                result
-                                    .map(|address| {
-                                        H160::from_slice(&address.payload().args()).unwrap()
-                                    })
-                                    .map_err(|_| err)
-                            })
-                    })
-                    .transpose()?;
+                let account_opt: Option<H160> =
+                    AccountParser::new(Some(network)).from_matches_opt(m, "from-account")?;
                 let skip_check: bool = m.is_present("skip-check");
 
                 let mut signer = if let Some(privkey) = privkey_opt {
                     get_privkey_signer(privkey)
+                } else if let Some(command) = m.value_of("external-signer") {
+                    let account = account_opt.unwrap();
+                    let signer = ExternalSigner::for_account(
+                        command.to_string(),
+                        self.rpc_client.url(),
+                        account.clone(),
+                    );
+                    get_external_signer(signer, account)
                 } else {
-                    let password = if self.plugin_mgr.keystore_require_password() {
-                        Some(read_password(false, None)?)
-                    } else {
-                        None
-                    };
                     let account = account_opt.unwrap();
+                    let password = self.plugin_mgr.keystore_password(&account)?;
                     let keystore = self.plugin_mgr.keystore_handler();
                     let new_client = HttpRpcClient::new(self.rpc_client.url().to_owned());
                     get_keystore_signer(keystore, new_client, account, password)
@@ -518,6 +518,16 @@ impl<'a> CliSubCommand for TxSubCommand<'a> {
                 let max_tx_fee: u64 = CapacityParser.from_matches(m, "max-tx-fee")?;
                 let skip_check: bool = m.is_present("skip-check");
 
+                let file = fs::File::open(tx_file).map_err(|err| err.to_string())?;
+                let repr: ReprTxHelper =
+                    serde_json::from_reader(&file).map_err(|err| err.to_string())?;
+                let helper = TxHelper::try_from(repr)?;
+                let dao_compensation = if skip_check {
+                    0
+                } else {
+                    calculate_dao_compensation(self.rpc_client, helper.transaction())?
+                };
+
                 let mut live_cell_cache: HashMap<(OutPoint, bool), (CellOutput, Bytes)> =
                     Default::default();
                 let mut get_live_cell = |out_point: OutPoint, with_data: bool| {
@@ -530,14 +540,17 @@ impl<'a> CliSubCommand for TxSubCommand<'a> {
                     .map(|(output, _)| output)
                 };
 
-                let file = fs::File::open(tx_file).map_err(|err| err.to_string())?;
-                let repr: ReprTxHelper =
-                    serde_json::from_reader(&file).map_err(|err| err.to_string())?;
-                let helper = TxHelper::try_from(repr)?;
-
                 if !skip_check {
                     let (input_total, output_total) = helper.check_tx(&mut get_live_cell)?;
-                    let tx_fee = input_total - output_total;
+                    let tx_fee = (input_total + dao_compensation)
+                        .checked_sub(output_total)
+                        .ok_or_else(|| {
+                            format!(
+                                "Output capacity is more than input capacity: {:#} > {:#}",
+                                HumanCapacity(output_total),
+                                HumanCapacity(input_total + dao_compensation),
+                            )
+                        })?;
                     if tx_fee > max_tx_fee {
                         return Err(format!(
                             "Too much transaction fee: {:#}, max: {:#}",
@@ -622,6 +635,26 @@ fn print_cell_info(
     );
 }
 
+fn get_external_signer(signer: ExternalSigner, account: H160) -> SignerFn {
+    Box::new(
+        move |lock_args: &HashSet<H160>, message: &H256, tx: &json_types::Transaction| {
+            if !lock_args.contains(&account) {
+                return Ok(None);
+            }
+            if message == &h256!("0x0") {
+                return Ok(Some([0u8; 65]));
+            }
+            let tx_view = packed::Transaction::from(tx.clone()).into_view();
+            let signature = signer
+                .sign(account.as_bytes(), message.as_bytes(), true, &tx_view)
+                .map_err(|err| err.to_string())?;
+            let mut data = [0u8; 65];
+            data.copy_from_slice(signature.as_ref());
+            Ok(Some(data))
+        },
+    )
+}
+
 fn get_keystore_signer(
     keystore: KeyStoreHandler,
     mut client: HttpRpcClient,
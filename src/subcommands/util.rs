@@ -32,11 +32,11 @@ use crate::plugin::{PluginManager, SignTarget};
 use crate::utils::{
     arg,
     arg_parser::{
-        AddressParser, ArgParser, FilePathParser, FixedHashParser, FromStrParser, HexParser,
-        PrivkeyPathParser, PrivkeyWrapper, PubkeyHexParser,
+        AccountParser, AddressParser, ArgParser, FilePathParser, FixedHashParser, FromStrParser,
+        HexParser, PrivkeyPathParser, PrivkeyWrapper, PubkeyHexParser,
     },
     genesis_info::GenesisInfo,
-    other::{address_json, get_address, get_network_type, read_password},
+    other::{address_json, get_address, get_network_type},
     rpc::{ChainInfo, HttpRpcClient},
 };
 use crate::{build_cli, get_version};
@@ -361,26 +361,14 @@ message = "0x"
                 let recoverable = m.is_present("recoverable");
                 let from_privkey_opt: Option<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_opt(m, "privkey-path")?;
-                let from_account_opt: Option<H160> = FixedHashParser::<H160>::default()
-                    .from_matches_opt(m, "from-account")
-                    .or_else(|err| {
-                        let result: Result<Option<Address>, String> =
-                            AddressParser::new_sighash().from_matches_opt(m, "from-account");
-                        result
-                            .map(|address_opt| {
-                                address_opt.map(|address| {
-                                    H160::from_slice(&address.payload().args()).unwrap()
-                                })
-                            })
-                            .map_err(|_| err)
-                    })?;
+                let from_account_opt: Option<H160> =
+                    AccountParser::default().from_matches_opt(m, "from-account")?;
                 let no_magic_bytes = m.is_present("no-magic-bytes");
-                let password =
-                    if self.plugin_mgr.keystore_require_password() && from_account_opt.is_some() {
-                        Some(read_password(false, None)?)
-                    } else {
-                        None
-                    };
+                let password = from_account_opt
+                    .as_ref()
+                    .map(|account| self.plugin_mgr.keystore_password(account))
+                    .transpose()?
+                    .flatten();
                 let extended_address_opt: Option<Address> =
                     AddressParser::new_sighash().from_matches_opt(m, "extended-address")?;
                 let root_path = if let Some(ref account) = from_account_opt {
@@ -436,25 +424,13 @@ message = "0x"
                 let recoverable = m.is_present("recoverable");
                 let from_privkey_opt: Option<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_opt(m, "privkey-path")?;
-                let from_account_opt: Option<H160> = FixedHashParser::<H160>::default()
-                    .from_matches_opt(m, "from-account")
-                    .or_else(|err| {
-                        let result: Result<Option<Address>, String> =
-                            AddressParser::new_sighash().from_matches_opt(m, "from-account");
-                        result
-                            .map(|address_opt| {
-                                address_opt.map(|address| {
-                                    H160::from_slice(&address.payload().args()).unwrap()
-                                })
-                            })
-                            .map_err(|_| err)
-                    })?;
-                let password =
-                    if self.plugin_mgr.keystore_require_password() && from_account_opt.is_some() {
-                        Some(read_password(false, None)?)
-                    } else {
-                        None
-                    };
+                let from_account_opt: Option<H160> =
+                    AccountParser::default().from_matches_opt(m, "from-account")?;
+                let password = from_account_opt
+                    .as_ref()
+                    .map(|account| self.plugin_mgr.keystore_password(account))
+                    .transpose()?
+                    .flatten();
                 let extended_address_opt: Option<Address> =
                     AddressParser::new_sighash().from_matches_opt(m, "extended-address")?;
 
@@ -497,27 +473,15 @@ message = "0x"
                     PubkeyHexParser.from_matches_opt(m, "pubkey")?;
                 let from_privkey_opt: Option<PrivkeyWrapper> =
                     PrivkeyPathParser.from_matches_opt(m, "privkey-path")?;
-                let from_account_opt: Option<H160> = FixedHashParser::<H160>::default()
-                    .from_matches_opt(m, "from-account")
-                    .or_else(|err| {
-                        let result: Result<Option<Address>, String> =
-                            AddressParser::new_sighash().from_matches_opt(m, "from-account");
-                        result
-                            .map(|address_opt| {
-                                address_opt.map(|address| {
-                                    H160::from_slice(&address.payload().args()).unwrap()
-                                })
-                            })
-                            .map_err(|_| err)
-                    })?;
+                let from_account_opt: Option<H160> =
+                    AccountParser::default().from_matches_opt(m, "from-account")?;
                 let extended_address_opt: Option<Address> =
                     AddressParser::new_sighash().from_matches_opt(m, "extended-address")?;
-                let password =
-                    if self.plugin_mgr.keystore_require_password() && from_account_opt.is_some() {
-                        Some(read_password(false, None)?)
-                    } else {
-                        None
-                    };
+                let password = from_account_opt
+                    .as_ref()
+                    .map(|account| self.plugin_mgr.keystore_password(account))
+                    .transpose()?
+                    .flatten();
                 let root_path = if let Some(ref account) = from_account_opt {
                     self.plugin_mgr.root_key_path(account.clone())?
                 } else {
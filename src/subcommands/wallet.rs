@@ -40,16 +40,16 @@ use crate::plugin::PluginManager;
 use crate::utils::{
     arg,
     arg_parser::{
-        AddressParser, ArgParser, CapacityParser, FixedHashParser, FromStrParser,
-        PrivkeyPathParser, PrivkeyWrapper,
+        AccountParser, AddressParser, ArgParser, CapacityParser, FromStrParser, PrivkeyPathParser,
+        PrivkeyWrapper,
     },
     genesis_info::GenesisInfo,
     other::{
-        check_capacity, get_address, get_arg_value, get_genesis_info, get_network_type,
-        get_to_data, map_tx_builder_error_2_str, read_password, to_live_cell_info,
+        check_capacity, get_address, get_arg_value, get_fee_rate, get_genesis_info,
+        get_network_type, get_to_data, map_tx_builder_error_2_str, to_live_cell_info,
     },
     rpc::HttpRpcClient,
-    signer::KeyStoreHandlerSigner,
+    signer::{ExternalSigner, KeyStoreHandlerSigner},
 };
 
 // Max derived change address to search
@@ -91,6 +91,11 @@ impl<'a> WalletSubCommand<'a> {
                             .required_unless(arg::privkey_path().get_name())
                             .conflicts_with(arg::privkey_path().get_name()),
                     )
+                    .arg(
+                        arg::external_signer()
+                            .requires(arg::from_account().get_name())
+                            .conflicts_with(arg::privkey_path().get_name()),
+                    )
                     .arg(arg::from_locked_address())
                     .arg(arg::to_address().required(true))
                     .arg(arg::to_data())
@@ -100,7 +105,10 @@ impl<'a> WalletSubCommand<'a> {
                     .arg(arg::max_tx_fee())
                     .arg(arg::derive_receiving_address_length())
                     .arg(
-                        arg::derive_change_address().conflicts_with(arg::privkey_path().get_name()),
+                        arg::derive_change_address().conflicts_with_all(&[
+                            arg::privkey_path().get_name(),
+                            arg::external_signer().get_name(),
+                        ]),
                     )
                     .arg(
                         Arg::with_name("skip-check-to-address")
@@ -136,6 +144,7 @@ impl<'a> WalletSubCommand<'a> {
         let TransferArgs {
             privkey_path,
             from_account,
+            external_signer,
             from_locked_address,
             password,
             derive_receiving_address_length,
@@ -154,18 +163,7 @@ impl<'a> WalletSubCommand<'a> {
             .map(|input| PrivkeyPathParser.parse(&input))
             .transpose()?;
         let from_account: Option<H160> = from_account
-            .map(|input| {
-                FixedHashParser::<H160>::default()
-                    .parse(&input)
-                    .or_else(|err| {
-                        let result: Result<Address, String> = AddressParser::new_sighash()
-                            .set_network(network_type)
-                            .parse(&input);
-                        result
-                            .map(|address| H160::from_slice(&address.payload().args()).unwrap())
-                            .map_err(|_| err)
-                    })
-            })
+            .map(|input| AccountParser::new(Some(network_type)).parse(&input))
             .transpose()?;
         let from_locked_address: Option<Address> = from_locked_address
             .map(|input| {
@@ -197,18 +195,18 @@ impl<'a> WalletSubCommand<'a> {
         let (from_address_payload, password) = if let Some(from_privkey) = from_privkey.as_ref() {
             let from_pubkey = secp256k1::PublicKey::from_secret_key(&SECP256K1, from_privkey);
             (AddressPayload::from_pubkey(&from_pubkey), None)
-        } else {
-            let password = if let Some(password) = password {
-                Some(password)
-            } else if self.plugin_mgr.keystore_require_password() {
-                Some(read_password(false, None)?)
-            } else {
-                None
-            };
+        } else if external_signer.is_some() {
             (
                 AddressPayload::from_pubkey_hash(from_account.unwrap()),
-                password,
+                None,
             )
+        } else {
+            let from_account = from_account.unwrap();
+            let password = match password {
+                Some(password) => Some(password),
+                None => self.plugin_mgr.keystore_password(&from_account)?,
+            };
+            (AddressPayload::from_pubkey_hash(from_account), password)
         };
         let from_address = Address::new(network_type, from_address_payload.clone(), false);
 
@@ -298,6 +296,9 @@ impl<'a> WalletSubCommand<'a> {
                     last_change_address.payload().clone(),
                     change_path_opt.expect("change path not exists"),
                 )
+            } else if external_signer.is_some() {
+                // The change path is only used by the keystore signer
+                (from_address.payload().clone(), DerivationPath::default())
             } else {
                 (
                     from_address.payload().clone(),
@@ -308,6 +309,12 @@ impl<'a> WalletSubCommand<'a> {
         let get_signer = || -> Result<Box<dyn Signer>, String> {
             if let Some(privkey) = from_privkey.as_ref() {
                 Ok(Box::new(privkey.clone()))
+            } else if let Some(command) = external_signer.as_ref() {
+                Ok(Box::new(ExternalSigner::for_account(
+                    command.clone(),
+                    self.rpc_client.url(),
+                    from_lock_arg.clone(),
+                )))
             } else {
                 let mut signer = KeyStoreHandlerSigner::new(
                     self.plugin_mgr.keystore_handler(),
@@ -381,7 +388,7 @@ impl<'a> WalletSubCommand<'a> {
             force_small_change_as_fee,
         };
         let tx_dep_provider = DefaultTransactionDependencyProvider::new(self.rpc_client.url(), 10);
-        let mut cell_collector = DefaultCellCollector::new(self.rpc_client.url());
+        let mut cell_collector = DefaultCellCollector::new(self.rpc_client.indexer_url());
         let header_dep_resolver = DefaultHeaderDepResolver::new(self.rpc_client.url());
 
         // Add outputs
@@ -469,7 +476,7 @@ impl<'a> WalletSubCommand<'a> {
     }
 
     pub fn get_capacity(&mut self, lock_scripts: Vec<Script>) -> Result<(u64, u64, u64), String> {
-        let mut cell_collector = DefaultCellCollector::new(self.rpc_client.url());
+        let mut cell_collector = DefaultCellCollector::new(self.rpc_client.indexer_url());
         let max_mature_number = get_max_mature_number(self.rpc_client.client())?;
         let mut total_all = 0;
         let mut total_immature = 0;
@@ -509,7 +516,7 @@ impl<'a> WalletSubCommand<'a> {
         to_number: u64,
         limit: u32,
     ) -> Result<Vec<LiveCell>, String> {
-        let mut cell_collector = DefaultCellCollector::new(self.rpc_client.url());
+        let mut cell_collector = DefaultCellCollector::new(self.rpc_client.indexer_url());
 
         let mut query = CellQueryOptions::new(script, script_type);
         query.maturity = MaturityOption::Both;
@@ -543,10 +550,11 @@ impl<'a> CliSubCommand for WalletSubCommand<'a> {
                 let args = TransferArgs {
                     privkey_path: m.value_of("privkey-path").map(|s| s.to_string()),
                     from_account: m.value_of("from-account").map(|s| s.to_string()),
+                    external_signer: m.value_of("external-signer").map(|s| s.to_string()),
                     from_locked_address: m.value_of("from-locked-address").map(|s| s.to_string()),
                     password: None,
                     capacity: get_arg_value(m, "capacity")?,
-                    fee_rate: get_arg_value(m, "fee-rate")?,
+                    fee_rate: get_fee_rate(m, self.rpc_client)?.to_string(),
                     force_small_change_as_fee: m.value_of("max-tx-fee").map(|s| s.to_string()),
                     derive_receiving_address_length: Some(get_arg_value(
                         m,
@@ -665,6 +673,7 @@ impl<'a> CliSubCommand for WalletSubCommand<'a> {
 pub struct TransferArgs {
     pub privkey_path: Option<String>,
     pub from_account: Option<String>,
+    pub external_signer: Option<String>,
     pub from_locked_address: Option<String>,
     pub password: Option<String>,
     pub derive_receiving_address_length: Option<String>,
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ckb_types::H160;
+use serde_derive::{Deserialize, Serialize};
+
+/// Stored in the keystore directory, the keystore json files are not modified so they can still
+/// be imported by other wallets.
+pub const ACCOUNT_META_FILENAME: &str = "accounts-meta.toml";
+
+/// Labels, notes and tags of keystore accounts, keyed by the lock arg (`0x` prefixed hex)
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct AccountMetaStore {
+    #[serde(default)]
+    pub accounts: BTreeMap<String, AccountMeta>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, Eq, PartialEq)]
+pub struct AccountMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// RFC 3339 format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+impl AccountMeta {
+    pub fn is_empty(&self) -> bool {
+        self == &AccountMeta::default()
+    }
+}
+
+fn meta_key(lock_arg: &H160) -> String {
+    format!("{:#x}", lock_arg)
+}
+
+/// A label is used in place of the lock arg, so it must not be confused with a lock arg
+pub fn check_label(label: &str) -> Result<(), String> {
+    if label.is_empty() || label.starts_with("0x") || label.chars().any(char::is_whitespace) {
+        return Err(format!(
+            "Invalid label: {:?}, label can not be empty, start with 0x or contain whitespaces",
+            label
+        ));
+    }
+    Ok(())
+}
+
+impl AccountMetaStore {
+    pub fn path(keystore_dir: &Path) -> PathBuf {
+        keystore_dir.join(ACCOUNT_META_FILENAME)
+    }
+
+    pub fn load(keystore_dir: &Path) -> Result<AccountMetaStore, String> {
+        let path = Self::path(keystore_dir);
+        if !path.exists() {
+            return Ok(AccountMetaStore::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+        toml::from_str(&content)
+            .map_err(|err| format!("Invalid account metadata {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, keystore_dir: &Path) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(Self::path(keystore_dir), content).map_err(|err| err.to_string())
+    }
+
+    pub fn get(&self, lock_arg: &H160) -> Option<&AccountMeta> {
+        self.accounts.get(&meta_key(lock_arg))
+    }
+
+    /// Replace the metadata of the account, the label must be unique
+    pub fn set(&mut self, lock_arg: &H160, meta: AccountMeta) -> Result<(), String> {
+        if let Some(label) = meta.label.as_ref() {
+            check_label(label)?;
+            if let Some(other) = self.find_by_label(label) {
+                if &other != lock_arg {
+                    return Err(format!(
+                        "Label {} is already used by account {:#x}",
+                        label, other
+                    ));
+                }
+            }
+        }
+        if meta.is_empty() {
+            self.accounts.remove(&meta_key(lock_arg));
+        } else {
+            self.accounts.insert(meta_key(lock_arg), meta);
+        }
+        Ok(())
+    }
+
+    pub fn find_by_label(&self, label: &str) -> Option<H160> {
+        self.accounts
+            .iter()
+            .find(|(_, meta)| meta.label.as_deref() == Some(label))
+            .and_then(|(key, _)| key.strip_prefix("0x"))
+            .and_then(|hex| {
+                let mut bytes = [0u8; 20];
+                faster_hex::hex_decode(hex.as_bytes(), &mut bytes).ok()?;
+                Some(H160::from(bytes))
+            })
+    }
+}
+
+/// The creation time encoded in the keystore file name: `UTC--<time>--<lock_arg>`
+pub fn created_at_from_filename(filename: &str) -> Option<String> {
+    let time = filename.strip_prefix("UTC--")?.rsplit_once("--")?.0;
+    let (date, time) = time.split_once('T')?;
+    let time = time.split('.').next()?.replace('-', ":");
+    Some(format!("{}T{}Z", date, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_meta() {
+        let account_a = H160::from_slice(&[1u8; 20]).unwrap();
+        let account_b = H160::from_slice(&[2u8; 20]).unwrap();
+        let mut store = AccountMetaStore::default();
+        let meta = AccountMeta {
+            label: Some("savings".to_string()),
+            tags: vec!["cold".to_string()],
+            ..Default::default()
+        };
+        store.set(&account_a, meta.clone()).unwrap();
+        assert_eq!(store.get(&account_a), Some(&meta));
+        assert_eq!(store.find_by_label("savings"), Some(account_a.clone()));
+        assert_eq!(store.find_by_label("daily"), None);
+        // Label must be unique
+        assert!(store.set(&account_b, meta.clone()).is_err());
+        store.set(&account_a, meta).unwrap();
+
+        let content = toml::to_string(&store).unwrap();
+        let loaded: AccountMetaStore = toml::from_str(&content).unwrap();
+        assert_eq!(loaded.find_by_label("savings"), Some(account_a.clone()));
+
+        store.set(&account_a, AccountMeta::default()).unwrap();
+        assert!(store.accounts.is_empty());
+
+        assert!(check_label("0x1234").is_err());
+        assert!(check_label("my label").is_err());
+        assert_eq!(
+            created_at_from_filename(
+                "UTC--2020-03-20T06-25-48.357093000Z--0101010101010101010101010101010101010101"
+            ),
+            Some("2020-03-20T06:25:48Z".to_string())
+        );
+    }
+}
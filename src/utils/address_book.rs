@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use ckb_sdk::{Address, NetworkType};
+use ckb_types::packed::Script;
+use lazy_static::lazy_static;
+use serde_derive::{Deserialize, Serialize};
+
+use super::config::{network_name, parse_network_name};
+use super::other::ckb_cli_dir;
+
+pub const ADDRESS_BOOK_FILENAME: &str = "address-book.toml";
+
+/// Named addresses stored in `<ckb-cli-dir>/address-book.toml`, `@<alias>` can be used
+/// anywhere an address is expected
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct AddressBook {
+    #[serde(default)]
+    pub entries: BTreeMap<String, AddressEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct AddressEntry {
+    /// Always saved in the full format
+    pub address: String,
+    /// mainnet, testnet, staging or dev
+    pub network: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl AddressEntry {
+    /// The address must be in the network (the address prefix of staging and dev is the same
+    /// as testnet)
+    pub fn new(
+        address: &Address,
+        network: NetworkType,
+        note: Option<String>,
+    ) -> Result<AddressEntry, String> {
+        if address.network().to_prefix() != network.to_prefix() {
+            return Err(format!(
+                "Address {} is not a {} address",
+                address,
+                network_name(network)
+            ));
+        }
+        let full_address = Address::new(network, address.payload().clone(), true);
+        Ok(AddressEntry {
+            address: full_address.to_string(),
+            network: network_name(network).to_string(),
+            note,
+        })
+    }
+
+    pub fn address(&self) -> Result<Address, String> {
+        Address::from_str(&self.address)
+            .map_err(|err| format!("Invalid address {} in address book: {}", self.address, err))
+    }
+
+    pub fn network_type(&self) -> Result<NetworkType, String> {
+        parse_network_name(&self.network)
+    }
+
+    /// Same lock script in the same network, whatever the address format is
+    fn matches(&self, address: &Address) -> bool {
+        self.address()
+            .map(|entry_address| {
+                entry_address.network().to_prefix() == address.network().to_prefix()
+                    && Script::from(entry_address.payload()) == Script::from(address.payload())
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// An alias is used as `@<alias>`, only ascii letters, digits, '-', '_' and '.' are allowed
+pub fn check_alias(alias: &str) -> Result<(), String> {
+    if alias.is_empty()
+        || !alias
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err(format!(
+            "Invalid alias: {:?}, only ascii letters, digits, '-', '_' and '.' are allowed",
+            alias
+        ));
+    }
+    Ok(())
+}
+
+impl AddressBook {
+    pub fn path(ckb_cli_dir: &Path) -> PathBuf {
+        ckb_cli_dir.join(ADDRESS_BOOK_FILENAME)
+    }
+
+    pub fn load(ckb_cli_dir: &Path) -> Result<AddressBook, String> {
+        let path = Self::path(ckb_cli_dir);
+        if !path.exists() {
+            return Ok(AddressBook::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+        toml::from_str(&content)
+            .map_err(|err| format!("Invalid address book {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, ckb_cli_dir: &Path) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(Self::path(ckb_cli_dir), content).map_err(|err| err.to_string())?;
+        // Changed in interactive mode, load it again next time
+        *LOADED_BOOK.lock().expect("lock address book") = None;
+        Ok(())
+    }
+
+    pub fn get(&self, alias: &str) -> Result<&AddressEntry, String> {
+        self.entries
+            .get(alias)
+            .ok_or_else(|| format!("Alias @{} is not in the address book", alias))
+    }
+
+    pub fn add(&mut self, alias: String, entry: AddressEntry, force: bool) -> Result<(), String> {
+        check_alias(&alias)?;
+        entry.address()?;
+        entry.network_type()?;
+        if !force && self.entries.contains_key(&alias) {
+            return Err(format!(
+                "Alias @{} already exists, use --force to replace it",
+                alias
+            ));
+        }
+        self.entries.insert(alias, entry);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, alias: &str) -> Result<AddressEntry, String> {
+        self.entries
+            .remove(alias)
+            .ok_or_else(|| format!("Alias @{} is not in the address book", alias))
+    }
+
+    pub fn find_by_address(&self, address: &Address) -> Option<(&str, &AddressEntry)> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| entry.matches(address))
+            .map(|(alias, entry)| (alias.as_str(), entry))
+    }
+}
+
+lazy_static! {
+    static ref WARNED_ADDRESSES: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    static ref LOADED_BOOK: Mutex<Option<Arc<AddressBook>>> = Mutex::new(None);
+}
+
+/// The address book in the default ckb-cli directory, only loaded once since every address
+/// argument looks it up
+pub fn loaded_address_book() -> Result<Arc<AddressBook>, String> {
+    let mut loaded = LOADED_BOOK.lock().expect("lock address book");
+    if let Some(book) = loaded.as_ref() {
+        return Ok(Arc::clone(book));
+    }
+    let book = Arc::new(AddressBook::load(&ckb_cli_dir())?);
+    *loaded = Some(Arc::clone(&book));
+    Ok(book)
+}
+
+/// Warn (once) if the raw address is the deprecated short/full format of an address in the
+/// address book, it's easy to mistake a similar looking address for a known one.
+pub fn warn_deprecated_format(input: &str, address: &Address) {
+    let book = match loaded_address_book() {
+        Ok(book) => book,
+        Err(_) => return,
+    };
+    if let Some((alias, entry)) = book.find_by_address(address) {
+        if entry.address != input
+            && WARNED_ADDRESSES
+                .lock()
+                .expect("lock warned addresses")
+                .insert(input.to_string())
+        {
+            eprintln!(
+                "WARNING: {} is the deprecated format of @{}, the full format is: {}",
+                input, alias, entry.address
+            );
+        }
+    }
+}
+
+/// Split a csv line (RFC 4180 quoting, a field can not contain line breaks)
+pub fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("Unclosed quote in csv line: {}", line));
+    }
+    fields.push(field);
+    Ok(fields
+        .into_iter()
+        .map(|field| field.trim().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_sdk::AddressPayload;
+    use ckb_types::H160;
+
+    #[test]
+    fn test_address_book() {
+        let payload = AddressPayload::from_pubkey_hash(H160::from_slice(&[3u8; 20]).unwrap());
+        let full = Address::new(NetworkType::Testnet, payload.clone(), true);
+        let short = Address::new(NetworkType::Testnet, payload.clone(), false);
+        let mainnet = Address::new(NetworkType::Mainnet, payload, true);
+
+        let entry = AddressEntry::new(&short, NetworkType::Dev, None).unwrap();
+        assert_eq!(entry.address, full.to_string());
+        assert_eq!(entry.network_type(), Ok(NetworkType::Dev));
+        assert!(AddressEntry::new(&mainnet, NetworkType::Testnet, None).is_err());
+
+        let mut book = AddressBook::default();
+        book.add("alice".to_string(), entry.clone(), false).unwrap();
+        assert!(book.add("alice".to_string(), entry.clone(), false).is_err());
+        assert!(book.add("@bob".to_string(), entry.clone(), false).is_err());
+        assert_eq!(
+            book.find_by_address(&short).map(|(alias, _)| alias),
+            Some("alice")
+        );
+        assert_eq!(
+            book.find_by_address(&full).map(|(alias, _)| alias),
+            Some("alice")
+        );
+        assert!(book.find_by_address(&mainnet).is_none());
+
+        let content = toml::to_string(&book).unwrap();
+        let loaded: AddressBook = toml::from_str(&content).unwrap();
+        assert_eq!(loaded.get("alice"), Ok(&entry));
+        assert_eq!(book.remove("alice"), Ok(entry));
+        assert!(book.get("alice").is_err());
+
+        assert_eq!(
+            split_csv_line(r#"alice, ckt1qyq, testnet,"a ""b"", c""#),
+            Ok(vec![
+                "alice".to_string(),
+                "ckt1qyq".to_string(),
+                "testnet".to_string(),
+                r#"a "b", c"#.to_string()
+            ])
+        );
+        assert!(split_csv_line(r#"alice,"ckt1qyq"#).is_err());
+    }
+}
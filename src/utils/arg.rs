@@ -1,6 +1,6 @@
 use crate::utils::arg_parser::{
-    AddressParser, ArgParser, CapacityParser, FilePathParser, FixedHashParser, FromStrParser,
-    HexParser, OutPointParser, PrivkeyPathParser, PubkeyHexParser,
+    AccountParser, AddressParser, ArgParser, CapacityParser, FilePathParser, FixedHashParser,
+    FromStrParser, HexParser, OutPointParser, PrivkeyPathParser, PubkeyHexParser,
 };
 use ckb_types::H160;
 use clap::Arg;
@@ -75,17 +75,15 @@ pub fn from_account<'a>() -> Arg<'a> {
     Arg::with_name("from-account")
         .long("from-account")
         .takes_value(true)
-        .validator(|input| {
-            FixedHashParser::<H160>::default()
-                .validate(input)
-                .or_else(|err| {
-                    AddressParser::default()
-                        .validate(input)
-                        .and_then(|()| AddressParser::new_sighash().validate(input))
-                        .map_err(|_| err)
-                })
-        })
-        .about("The account's lock-arg or sighash address (transfer from this account)")
+        .validator(|input| AccountParser::default().validate(input))
+        .about("The account's lock-arg, sighash address or label (transfer from this account)")
+}
+
+pub fn external_signer<'a>() -> Arg<'a> {
+    Arg::with_name("external-signer")
+        .long("external-signer")
+        .takes_value(true)
+        .about("Sign with an external command (like a hardware wallet bridge) instead of the keystore. The command is spawned once per transaction, it reads one json request per line from stdin: {id, account, message, recoverable, transaction, inputs}, and writes one json response per line to stdout: {signature} or {error}")
 }
 
 pub fn from_locked_address<'a>() -> Arg<'a> {
@@ -13,13 +13,19 @@ use url::Url;
 
 use ckb_sdk::{
     constants::{MULTISIG_TYPE_HASH, SIGHASH_TYPE_HASH},
+    types::xudt_rce_mol::ScriptVec,
     util::zeroize_privkey,
     Address, AddressPayload, HumanCapacity, NetworkType, OldAddress, ScriptId,
 };
 use ckb_signer::MasterPrivKey;
-use ckb_types::{core::ScriptHashType, packed::OutPoint, prelude::*, H160, H256};
+use ckb_types::{bytes::Bytes, core::ScriptHashType, packed::OutPoint, prelude::*, H160, H256};
 
+use crate::utils::account_meta::AccountMetaStore;
+use crate::utils::address_book::{loaded_address_book, warn_deprecated_format};
 use crate::utils::cell_dep::CellDeps;
+use crate::utils::config::network_name;
+use crate::utils::other::ckb_cli_dir;
+use crate::utils::token_registry::parse_decimal_amount;
 
 #[allow(clippy::wrong_self_convention)]
 pub trait ArgParser<T> {
@@ -404,6 +410,21 @@ impl AddressParser {
 
 impl ArgParser<Address> for AddressParser {
     fn parse(&self, input: &str) -> Result<Address, String> {
+        if let Some(alias) = input.strip_prefix('@') {
+            let book = loaded_address_book()?;
+            let entry = book.get(alias)?;
+            if let Some(network) = self.network {
+                if entry.network_type()? != network {
+                    return Err(format!(
+                        "Alias @{} is a {} address, expected network: {}",
+                        alias,
+                        entry.network,
+                        network_name(network)
+                    ));
+                }
+            }
+            return self.parse(&entry.address);
+        }
         if let Ok(address) = Address::from_str(input) {
             if matches!(address.network(), NetworkType::Staging | NetworkType::Dev)
                 && address.payload().is_short_acp()
@@ -439,6 +460,7 @@ impl ArgParser<Address> for AddressParser {
                     ));
                 }
             }
+            warn_deprecated_format(input, &address);
             return Ok(address);
         }
 
@@ -452,6 +474,55 @@ impl ArgParser<Address> for AddressParser {
     }
 }
 
+/// Parse an account from lock arg, sighash address (or `@alias` of the address book) or account
+/// label
+#[derive(Default, Debug)]
+pub struct AccountParser {
+    network: Option<NetworkType>,
+}
+
+impl AccountParser {
+    pub fn new(network: Option<NetworkType>) -> AccountParser {
+        AccountParser { network }
+    }
+}
+
+impl ArgParser<H160> for AccountParser {
+    fn parse(&self, input: &str) -> Result<H160, String> {
+        FixedHashParser::<H160>::default()
+            .parse(input)
+            .or_else(|err| {
+                let result: Result<Address, String> = AddressParser::new_sighash()
+                    .set_network_opt(self.network)
+                    .parse(input);
+                // Keep the error of the address book alias
+                result
+                    .map(|address| H160::from_slice(&address.payload().args()).unwrap())
+                    .map_err(|address_err| {
+                        if input.starts_with('@') {
+                            address_err
+                        } else {
+                            err
+                        }
+                    })
+            })
+            .or_else(|err| {
+                if input.starts_with("0x") || input.starts_with('@') {
+                    return Err(err);
+                }
+                let keystore_dir = ckb_cli_dir().join("keystore");
+                AccountMetaStore::load(&keystore_dir)?
+                    .find_by_label(input)
+                    .ok_or_else(|| {
+                        format!(
+                            "Invalid account: {}, expected lock arg, sighash address or account label",
+                            input
+                        )
+                    })
+            })
+    }
+}
+
 /// Default unit CKB format: xxx.xxxxx
 pub struct CapacityParser;
 
@@ -480,20 +551,33 @@ impl ArgParser<OutPoint> for OutPointParser {
 
 pub struct UdtTargetParser {
     address_parser: AddressParser,
+    decimals: Option<u8>,
 }
 
 impl UdtTargetParser {
     pub fn new(address_parser: AddressParser) -> UdtTargetParser {
-        UdtTargetParser { address_parser }
+        UdtTargetParser {
+            address_parser,
+            decimals: None,
+        }
+    }
+
+    /// Parse the amount as a decimal number, it will be converted to the raw amount exactly
+    pub fn with_decimals(mut self, decimals: u8) -> UdtTargetParser {
+        self.decimals = Some(decimals);
+        self
     }
 }
 impl ArgParser<(Address, u128)> for UdtTargetParser {
     fn parse(&self, input: &str) -> Result<(Address, u128), String> {
         if let Some((addr_str, amount_str)) = input.split_once(':') {
             let address: Address = self.address_parser.parse(addr_str)?;
-            let amount = FromStrParser::<u128>::default()
-                .parse(amount_str)
-                .map_err(|err| format!("invalid amount: {}, error: {}", amount_str, err))?;
+            let amount = if let Some(decimals) = self.decimals {
+                parse_decimal_amount(amount_str, decimals)
+            } else {
+                FromStrParser::<u128>::default().parse(amount_str)
+            }
+            .map_err(|err| format!("invalid amount: {}, error: {}", amount_str, err))?;
             Ok((address, amount))
         } else {
             Err(format!(
@@ -562,6 +646,73 @@ impl ArgParser<::std::net::SocketAddr> for SocketParser {
     }
 }
 
+/// The xUDT flags mask of extension data kind, the higher bits are for owner mode
+pub const XUDT_FLAGS_EXTENSION_MASK: u32 = 0x1FFF_FFFF;
+/// Extension data is a molecule `ScriptVec`
+pub const XUDT_EXTENSION_SCRIPTS: u32 = 0x1;
+/// Extension data is the blake160 hash of a `ScriptVec` which is provided in witness
+pub const XUDT_EXTENSION_SCRIPTS_HASH: u32 = 0x2;
+
+/// The xUDT args after the owner lock hash: `<4 bytes flags (little endian)> <extension data>`.
+///
+/// An empty args is also valid, it's the same as sUDT args layout.
+pub struct XudtArgsParser;
+
+impl XudtArgsParser {
+    pub fn flags(args: &[u8]) -> u32 {
+        if args.len() < 4 {
+            return 0;
+        }
+        let mut flags_bytes = [0u8; 4];
+        flags_bytes.copy_from_slice(&args[0..4]);
+        u32::from_le_bytes(flags_bytes)
+    }
+}
+
+impl ArgParser<Bytes> for XudtArgsParser {
+    fn parse(&self, input: &str) -> Result<Bytes, String> {
+        if input == "0x" || input.is_empty() {
+            return Ok(Bytes::new());
+        }
+        let args = HexParser.parse(input)?;
+        if args.len() < 4 {
+            return Err(format!(
+                "Invalid xUDT args length: {}, expected: >= 4 (flags)",
+                args.len()
+            ));
+        }
+        let extension_data = &args[4..];
+        match Self::flags(&args) & XUDT_FLAGS_EXTENSION_MASK {
+            0 if extension_data.is_empty() => {}
+            0 => return Err("xUDT extension data must be empty when flags is 0".to_string()),
+            XUDT_EXTENSION_SCRIPTS => {
+                ScriptVec::from_slice(extension_data)
+                    .map_err(|err| format!("Invalid xUDT extension scripts: {}", err))?;
+            }
+            XUDT_EXTENSION_SCRIPTS_HASH if extension_data.len() == 20 => {}
+            XUDT_EXTENSION_SCRIPTS_HASH => {
+                return Err(format!(
+                    "Invalid xUDT extension scripts hash length: {}, expected: 20",
+                    extension_data.len()
+                ))
+            }
+            flags => return Err(format!("Unsupported xUDT flags: {:#x}", flags)),
+        }
+        Ok(Bytes::from(args))
+    }
+}
+
+/// The xUDT extension scripts: a molecule `ScriptVec` in hex format
+pub struct XudtExtensionScriptsParser;
+
+impl ArgParser<ScriptVec> for XudtExtensionScriptsParser {
+    fn parse(&self, input: &str) -> Result<ScriptVec, String> {
+        let data = HexParser.parse(input)?;
+        ScriptVec::from_slice(&data)
+            .map_err(|err| format!("Invalid xUDT extension scripts: {}", err))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ckb_sdk::CodeHashIndex;
@@ -592,6 +743,36 @@ mod tests {
         assert!(HexParser.parse("abcdefghi").is_err());
     }
 
+    #[test]
+    fn test_xudt_args() {
+        assert_eq!(XudtArgsParser.parse("0x"), Ok(Bytes::new()));
+        assert_eq!(
+            XudtArgsParser.parse("0x00000000"),
+            Ok(Bytes::from(vec![0u8; 4]))
+        );
+        // owner mode bits
+        assert!(XudtArgsParser.parse("0x00000080").is_ok());
+        assert!(XudtArgsParser.parse("0x0000").is_err());
+        assert!(XudtArgsParser.parse("0x0000000033").is_err());
+        // extension scripts: empty ScriptVec
+        assert!(XudtArgsParser.parse("0x0100000004000000").is_ok());
+        assert!(XudtArgsParser.parse("0x0100000033").is_err());
+        // extension scripts hash
+        let hash_args = format!("0x02000000{}", "33".repeat(20));
+        assert!(XudtArgsParser.parse(&hash_args).is_ok());
+        assert!(XudtArgsParser.parse("0x0200000033").is_err());
+        assert!(XudtArgsParser.parse("0x03000000").is_err());
+        assert_eq!(XudtArgsParser::flags(&[0x01, 0, 0, 0x80]), 0x8000_0001);
+
+        assert_eq!(
+            XudtExtensionScriptsParser
+                .parse("0x04000000")
+                .map(|scripts| scripts.len()),
+            Ok(0)
+        );
+        assert!(XudtExtensionScriptsParser.parse("0x0400").is_err());
+    }
+
     #[test]
     fn test_fixed_hash() {
         assert_eq!(
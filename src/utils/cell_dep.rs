@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +29,8 @@ pub enum CellDepName {
     Cheque,
     /// Simple UDT
     Sudt,
+    /// Extensible UDT
+    Xudt,
 }
 impl fmt::Display for CellDepName {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -35,11 +38,25 @@ impl fmt::Display for CellDepName {
             CellDepName::Acp => "acp",
             CellDepName::Cheque => "cheque",
             CellDepName::Sudt => "sudt",
+            CellDepName::Xudt => "xudt",
         };
         write!(f, "{}", output)
     }
 }
 
+impl FromStr for CellDepName {
+    type Err = String;
+    fn from_str(input: &str) -> Result<CellDepName, String> {
+        match input.to_lowercase().as_str() {
+            "acp" => Ok(CellDepName::Acp),
+            "cheque" => Ok(CellDepName::Cheque),
+            "sudt" => Ok(CellDepName::Sudt),
+            "xudt" => Ok(CellDepName::Xudt),
+            _ => Err(format!("Invalid cell dep name: {}", input)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CellDepItem {
     pub script_id: ScriptId,
@@ -49,6 +66,9 @@ pub struct CellDepItem {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CellDeps {
     pub items: HashMap<CellDepName, CellDepItem>,
+    /// The cell deps of xUDT extension scripts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<CellDepItem>,
 }
 
 impl CellDeps {
@@ -67,6 +87,13 @@ impl CellDeps {
                 name.to_string(),
             );
         }
+        for item in self.extensions.clone() {
+            resolver.insert(
+                item.script_id.into(),
+                item.cell_dep.into(),
+                "xudt extension".to_string(),
+            );
+        }
         Ok(())
     }
 }
@@ -1,7 +1,9 @@
 use std::borrow::Cow::{self, Owned};
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
+use std::fs;
 use std::iter;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 use ansi_term::Colour::{Green, Red};
 use rustyline::completion::{extract_word, Completer, FilenameCompleter, Pair};
@@ -11,6 +13,10 @@ use rustyline::hint::Hinter;
 use rustyline::validate::{self, MatchingBracketValidator, Validator};
 use rustyline::{CompletionType, Context};
 use rustyline_derive::Helper;
+use serde_json::Value;
+
+use super::account_meta::AccountMetaStore;
+use super::address_book::AddressBook;
 
 #[cfg(unix)]
 static DEFAULT_BREAK_CHARS: [u8; 18] = [
@@ -29,22 +35,263 @@ static DEFAULT_BREAK_CHARS: [u8; 17] = [
 #[cfg(windows)]
 static ESCAPE_CHAR: Option<char> = None;
 
+/// Keep the most recent tx hashes for completion
+const MAX_TX_HASHES: usize = 32;
+
+/// The values for completing option values: the accounts and the address book are loaded
+/// once and cached until the next command, the tx hashes and out points are collected from the
+/// command outputs of this session.
+pub struct CompletionValues {
+    ckb_cli_dir: PathBuf,
+    tx_hashes: VecDeque<String>,
+    out_points: Vec<String>,
+    accounts: Option<Vec<(String, String)>>,
+    aliases: Option<Vec<String>>,
+}
+
+impl CompletionValues {
+    pub fn new(ckb_cli_dir: PathBuf) -> CompletionValues {
+        CompletionValues {
+            ckb_cli_dir,
+            tx_hashes: VecDeque::new(),
+            out_points: Vec::new(),
+            accounts: None,
+            aliases: None,
+        }
+    }
+
+    /// The accounts or the address book may be changed by the command
+    pub fn clear_cache(&mut self) {
+        self.accounts = None;
+        self.aliases = None;
+    }
+
+    /// Collect tx hashes and out points (like the cells of `wallet get-live-cells`) from the
+    /// output of a command, the out points of the last output having any replace the old ones
+    pub fn record_output(&mut self, output: &Value) {
+        let mut tx_hashes = Vec::new();
+        let mut out_points = Vec::new();
+        if let Some(hash) = output.as_str().filter(|s| is_hash(s)) {
+            tx_hashes.push(hash.to_string());
+        }
+        collect_hashes(output, &mut tx_hashes, &mut out_points);
+        for tx_hash in tx_hashes.into_iter().rev() {
+            self.tx_hashes.retain(|item| item != &tx_hash);
+            self.tx_hashes.push_front(tx_hash);
+        }
+        self.tx_hashes.truncate(MAX_TX_HASHES);
+        if !out_points.is_empty() {
+            self.out_points = out_points;
+        }
+    }
+
+    /// (display, replacement) pairs of the option value, `None` if the option value is not
+    /// completed
+    fn candidates(&mut self, option: &str) -> Option<Vec<(String, String)>> {
+        let to_pairs = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| (value.clone(), value.clone()))
+                .collect::<Vec<_>>()
+        };
+        match option {
+            "from-account" | "account" => {
+                let mut pairs = self.accounts().to_vec();
+                pairs.extend(to_pairs(self.aliases()));
+                Some(pairs)
+            }
+            "lock-arg" => Some(
+                self.accounts()
+                    .iter()
+                    .filter(|(_, replacement)| replacement.starts_with("0x"))
+                    .cloned()
+                    .collect(),
+            ),
+            "address" | "owner" | "sender" | "capacity-provider" => Some(to_pairs(self.aliases())),
+            _ if option.ends_with("-address") => Some(to_pairs(self.aliases())),
+            "tx-hash" => Some(to_pairs(self.tx_hashes.make_contiguous())),
+            "out-point" => Some(to_pairs(&self.out_points)),
+            _ => None,
+        }
+    }
+
+    /// Lock args (displayed with labels) and labels of the local keystore, read from the
+    /// keystore file names instead of the key files to be fast.
+    fn accounts(&mut self) -> &[(String, String)] {
+        let keystore_dir = self.ckb_cli_dir.join("keystore");
+        self.accounts.get_or_insert_with(|| {
+            let meta_store = AccountMetaStore::load(&keystore_dir).unwrap_or_default();
+            let mut lock_args = fs::read_dir(&keystore_dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .filter_map(|entry| {
+                            let filename = entry.file_name().into_string().ok()?;
+                            let (_, hex) = filename.strip_prefix("UTC--")?.rsplit_once("--")?;
+                            Some(format!("0x{}", hex))
+                        })
+                        .filter(|lock_arg| lock_arg.len() == 42 && is_hex(lock_arg))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            lock_args.sort();
+            let mut pairs = Vec::new();
+            for lock_arg in lock_args {
+                match meta_store
+                    .accounts
+                    .get(&lock_arg)
+                    .and_then(|meta| meta.label.clone())
+                {
+                    Some(label) => {
+                        pairs.push((format!("{} ({})", lock_arg, label), lock_arg));
+                        pairs.push((label.clone(), label));
+                    }
+                    None => pairs.push((lock_arg.clone(), lock_arg)),
+                }
+            }
+            pairs
+        })
+    }
+
+    fn aliases(&mut self) -> &[String] {
+        let ckb_cli_dir = &self.ckb_cli_dir;
+        self.aliases.get_or_insert_with(|| {
+            AddressBook::load(ckb_cli_dir)
+                .map(|book| {
+                    book.entries
+                        .keys()
+                        .map(|alias| format!("@{}", alias))
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+    }
+}
+
+fn collect_hashes(value: &Value, tx_hashes: &mut Vec<String>, out_points: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            let tx_hash = ["tx_hash", "transaction_hash"]
+                .iter()
+                .find_map(|key| map.get(*key))
+                .and_then(Value::as_str)
+                .filter(|s| is_hash(s));
+            if let Some(tx_hash) = tx_hash {
+                tx_hashes.push(tx_hash.to_string());
+                let index = map
+                    .get("output_index")
+                    .or_else(|| map.get("index"))
+                    .and_then(|index| match index {
+                        Value::Number(number) => number.as_u64(),
+                        // The rpc json format
+                        Value::String(s) => u64::from_str_radix(s.strip_prefix("0x")?, 16).ok(),
+                        _ => None,
+                    });
+                if let Some(index) = index {
+                    out_points.push(format!("{}-{}", tx_hash, index));
+                }
+            }
+            for value in map.values() {
+                collect_hashes(value, tx_hashes, out_points);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_hashes(item, tx_hashes, out_points);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_hex(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .map(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or(false)
+}
+
+fn is_hash(value: &str) -> bool {
+    value.len() == 66 && is_hex(value)
+}
+
 #[derive(Helper)]
 pub struct CkbCompleter<'a> {
     clap_app: Arc<clap::App<'a>>,
     completer: FilenameCompleter,
     validator: MatchingBracketValidator,
+    values: Arc<Mutex<CompletionValues>>,
 }
 
 impl<'a> CkbCompleter<'a> {
-    pub fn new(clap_app: clap::App<'a>) -> Self {
+    pub fn new(clap_app: clap::App<'a>, values: Arc<Mutex<CompletionValues>>) -> Self {
         CkbCompleter {
             clap_app: Arc::new(clap_app),
             completer: FilenameCompleter::new(),
             validator: MatchingBracketValidator::new(),
+            values,
         }
     }
 
+    /// Complete the value of the option before the cursor, `None` if it's not an option value
+    fn complete_value(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &Context,
+        start: usize,
+        args: &[String],
+    ) -> Option<Result<(usize, Vec<Pair>), ReadlineError>> {
+        let ends_with_space = line[..pos].ends_with(char::is_whitespace);
+        let (option, current) = match (args, ends_with_space) {
+            ([.., option], true) => (option, ""),
+            ([.., option, current], false) => (option, current.as_str()),
+            _ => return None,
+        };
+        let option = option.strip_prefix("--")?;
+        if current.starts_with('-') || !self.takes_value(args, option) {
+            return None;
+        }
+        if option.ends_with("-file") || option.ends_with("-path") {
+            return Some(self.completer.complete(line, pos, ctx));
+        }
+        let candidates = self.values.lock().ok()?.candidates(option)?;
+        // `@` is a word break char, the replacement starts after it
+        let skip = if start > 0 && line[..start].ends_with('@') {
+            1
+        } else {
+            0
+        };
+        let current_lower = current.to_lowercase();
+        let pairs = candidates
+            .into_iter()
+            .filter(|(_, replacement)| replacement.to_lowercase().starts_with(&current_lower))
+            .filter(|(_, replacement)| replacement.len() >= skip)
+            .map(|(display, replacement)| Pair {
+                display,
+                replacement: replacement[skip..].to_string(),
+            })
+            .collect();
+        Some(Ok((start, pairs)))
+    }
+
+    /// The option of the current subcommand (or a global option) takes a value, flags like
+    /// `--to-cheque-address` do not.
+    fn takes_value(&self, args: &[String], option: &str) -> bool {
+        let takes_value = |app: &clap::App| {
+            app.get_arguments().iter().any(|arg| {
+                arg.get_long() == Some(option) && arg.is_set(clap::ArgSettings::TakesValue)
+            })
+        };
+        Self::find_subcommand(
+            Arc::clone(&self.clap_app),
+            args.iter().map(String::as_str).peekable(),
+        )
+        .map(|app| takes_value(&app))
+        .unwrap_or(false)
+            || takes_value(&self.clap_app)
+    }
+
     pub fn get_completions(app: &Arc<clap::App<'a>>, args: &[String]) -> Vec<(String, String)> {
         let args_set = args.iter().collect::<HashSet<&String>>();
         let switched_completions =
@@ -130,6 +377,9 @@ impl<'a> Completer for CkbCompleter<'a> {
     ) -> Result<(usize, Vec<Pair>), ReadlineError> {
         let (start, word) = extract_word(line, pos, ESCAPE_CHAR, &DEFAULT_BREAK_CHARS);
         let args = shell_words::split(&line[..pos]).unwrap();
+        if let Some(result) = self.complete_value(line, pos, ctx, start, &args) {
+            return result;
+        }
         let word_lower = word.to_lowercase();
         let tmp_pair = Self::find_subcommand(
             Arc::clone(&self.clap_app),
@@ -258,3 +508,77 @@ pub fn string_include(x: &str, y: &str) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_output() {
+        let hash_a = format!("0x{}", "a".repeat(64));
+        let hash_b = format!("0x{}", "b".repeat(64));
+        let mut values = CompletionValues::new(PathBuf::new());
+        values.record_output(&Value::String(hash_a.clone()));
+        values.record_output(&serde_json::json!({
+            "live_cells": [
+                {"tx_hash": hash_b, "output_index": 1, "capacity": "100.0"},
+                {"out_point": {"tx_hash": hash_a, "index": "0x2"}},
+            ],
+        }));
+        assert_eq!(values.tx_hashes, vec![hash_b.clone(), hash_a.clone()]);
+        assert_eq!(
+            values.candidates("out-point").unwrap(),
+            vec![
+                (format!("{}-1", hash_b), format!("{}-1", hash_b)),
+                (format!("{}-2", hash_a), format!("{}-2", hash_a)),
+            ]
+        );
+        // The out points are kept if the output has none
+        values.record_output(&serde_json::json!({"total": "100.0"}));
+        assert_eq!(values.out_points.len(), 2);
+        assert!(values.candidates("fee-rate").is_none());
+    }
+
+    #[test]
+    fn test_takes_value() {
+        let app = clap::App::new("ckb-cli")
+            .arg(
+                clap::Arg::with_name("output-format")
+                    .long("output-format")
+                    .takes_value(true)
+                    .global(true),
+            )
+            .subcommand(
+                clap::App::new("wallet").subcommand(
+                    clap::App::new("transfer")
+                        .arg(
+                            clap::Arg::with_name("to-address")
+                                .long("to-address")
+                                .takes_value(true),
+                        )
+                        .arg(clap::Arg::with_name("to-cheque-address").long("to-cheque-address")),
+                ),
+            );
+        let values = Arc::new(Mutex::new(CompletionValues::new(PathBuf::new())));
+        let completer = CkbCompleter::new(app, values);
+        let args = |line: &str| shell_words::split(line).unwrap();
+        let takes_value = |line: &str, option: &str| completer.takes_value(&args(line), option);
+        assert!(takes_value("wallet transfer --to-address", "to-address"));
+        assert!(takes_value(
+            "wallet transfer --to-cheque-address --to-address",
+            "to-address"
+        ));
+        assert!(!takes_value(
+            "wallet transfer --to-cheque-address",
+            "to-cheque-address"
+        ));
+        assert!(!takes_value(
+            "wallet transfer --only-mainnet-address",
+            "only-mainnet-address"
+        ));
+        assert!(takes_value(
+            "wallet transfer --output-format",
+            "output-format"
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
 use std::io::{self, Write};
@@ -8,15 +8,85 @@ use std::path::{Path, PathBuf};
 use ansi_term::Colour::Yellow;
 use ckb_sdk::{CkbRpcClient, NetworkType};
 use regex::{Captures, Regex};
+use serde_derive::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::utils::printer::{OutputFormat, Printable};
+use crate::utils::rpc::HttpRpcClient;
 
 pub const DEFAULT_CKB_URL: &str = "http://127.0.0.1:8114";
 
+/// A named network, saved in the `profiles` field of the config file
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NetworkProfile {
+    pub url: String,
+    /// The indexer rpc url, default is the node url (the node has a built-in indexer)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexer_url: Option<String>,
+    /// The expected network type: mainnet, testnet, staging or dev
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// The default cell deps file of `sudt` subcommands
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_deps: Option<PathBuf>,
+    /// The default transaction fee rate (unit: shannons/KB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_rate: Option<u64>,
+}
+
+impl NetworkProfile {
+    pub fn network_type(&self) -> Result<Option<NetworkType>, String> {
+        self.network.as_deref().map(parse_network_name).transpose()
+    }
+
+    /// Return error if the node is not in the expected network
+    pub fn check_network(&self, actual: Option<NetworkType>) -> Result<(), String> {
+        match (self.network_type()?, actual) {
+            (Some(expected), Some(actual)) if expected != actual => Err(format!(
+                "The node {} is in network {:?}, but the profile expects network {:?}",
+                self.url, actual, expected
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn parse_network_name(name: &str) -> Result<NetworkType, String> {
+    match name {
+        "mainnet" => Ok(NetworkType::Mainnet),
+        "testnet" => Ok(NetworkType::Testnet),
+        "staging" => Ok(NetworkType::Staging),
+        "dev" => Ok(NetworkType::Dev),
+        _ => Err(format!(
+            "Invalid network: {}, expected: mainnet, testnet, staging or dev",
+            name
+        )),
+    }
+}
+
+/// The reverse of `parse_network_name`
+pub fn network_name(network: NetworkType) -> &'static str {
+    match network {
+        NetworkType::Mainnet => "mainnet",
+        NetworkType::Testnet => "testnet",
+        NetworkType::Staging => "staging",
+        NetworkType::Dev => "dev",
+    }
+}
+
+pub fn url_with_scheme(value: String) -> String {
+    if value.starts_with("http://") || value.starts_with("https://") {
+        value
+    } else {
+        format!("http://{}", value)
+    }
+}
+
 pub struct GlobalConfig {
     url: Option<String>,
     network: Option<NetworkType>,
+    profile: Option<String>,
+    profiles: BTreeMap<String, NetworkProfile>,
     color: bool,
     debug: bool,
     no_sync: bool,
@@ -32,6 +102,8 @@ impl GlobalConfig {
         GlobalConfig {
             url,
             network: None,
+            profile: None,
+            profiles: BTreeMap::new(),
             color: true,
             debug: false,
             no_sync: false,
@@ -96,6 +168,8 @@ impl GlobalConfig {
                     .map(|value| match value {
                         serde_json::Value::String(s) => s.to_owned(),
                         serde_json::Value::Number(n) => n.to_string(),
+                        serde_json::Value::Bool(b) => b.to_string(),
+                        serde_json::Value::Null => "null".to_string(),
                         _ => String::new(),
                     })
                     .next()
@@ -106,11 +180,7 @@ impl GlobalConfig {
     }
 
     pub fn set_url(&mut self, value: String) {
-        if value.starts_with("http://") || value.starts_with("https://") {
-            self.url = Some(value);
-        } else {
-            self.url = Some(format!("http://{}", value));
-        }
+        self.url = Some(url_with_scheme(value));
     }
     pub fn get_url(&self) -> &str {
         self.url.as_deref().unwrap_or(DEFAULT_CKB_URL)
@@ -123,6 +193,50 @@ impl GlobalConfig {
         self.network
     }
 
+    pub fn profiles(&self) -> &BTreeMap<String, NetworkProfile> {
+        &self.profiles
+    }
+    pub fn set_profiles(&mut self, profiles: BTreeMap<String, NetworkProfile>) {
+        self.profiles = profiles;
+    }
+    pub fn add_profile(&mut self, name: String, profile: NetworkProfile) {
+        self.profiles.insert(name, profile);
+    }
+
+    /// The name of the active profile
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+    pub fn active_profile(&self) -> Option<&NetworkProfile> {
+        self.profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+    }
+
+    /// Switch to the profile, the url is also changed
+    pub fn use_profile(&mut self, name: &str) -> Result<&NetworkProfile, String> {
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| format!("Profile not found: {}", name))?;
+        self.url = Some(profile.url.clone());
+        self.profile = Some(name.to_string());
+        Ok(profile)
+    }
+
+    /// Leave the active profile when the url is changed manually
+    pub fn clear_profile(&mut self) {
+        self.profile = None;
+    }
+
+    /// The rpc client of the active profile, or of the url if no profile is active
+    pub fn rpc_client(&self) -> HttpRpcClient {
+        match self.active_profile() {
+            Some(profile) => HttpRpcClient::from_profile(profile.clone()),
+            None => HttpRpcClient::new(self.get_url().to_string()),
+        }
+    }
+
     pub fn switch_color(&mut self) {
         self.color = !self.color;
     }
@@ -219,10 +333,12 @@ impl GlobalConfig {
             network_string,
             ckb_tip
         );
+        let profile = self.profile().unwrap_or("none");
 
         let values = [
             ("ckb-cli version", version_long.as_str()),
             ("url", url_string.as_str()),
+            ("profile", profile),
             ("pwd", path.deref()),
             ("color", color.as_str()),
             ("debug", debug.as_str()),
@@ -262,6 +378,8 @@ impl GlobalConfig {
             "output_format": self.output_format().to_string(),
             "completion_style": self.completion_style(),
             "edit_style": self.edit_style(),
+            "profile": self.profile(),
+            "profiles": self.profiles(),
         }))
         .unwrap();
         file.write_all(content.as_bytes())?;
@@ -300,3 +418,57 @@ impl<'a> ::std::iter::Iterator for KV<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_profile() {
+        let profile: NetworkProfile = serde_json::from_value(json!({
+            "url": "127.0.0.1:8114",
+            "indexer_url": "http://127.0.0.1:8116",
+            "network": "testnet",
+            "cell_deps": "cell_deps.json",
+            "fee_rate": 2000,
+        }))
+        .unwrap();
+        assert_eq!(
+            profile.indexer_url.as_deref(),
+            Some("http://127.0.0.1:8116")
+        );
+        assert_eq!(profile.cell_deps, Some(PathBuf::from("cell_deps.json")));
+        assert_eq!(profile.fee_rate, Some(2000));
+        assert_eq!(profile.network_type(), Ok(Some(NetworkType::Testnet)));
+        assert!(profile.check_network(Some(NetworkType::Testnet)).is_ok());
+        assert!(profile.check_network(None).is_ok());
+        assert!(profile.check_network(Some(NetworkType::Mainnet)).is_err());
+        // The unset optional fields are skipped when saving
+        let minimal = NetworkProfile {
+            url: "http://127.0.0.1:8114".to_string(),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&minimal).unwrap();
+        assert_eq!(value, json!({"url": "http://127.0.0.1:8114"}));
+        assert_eq!(
+            serde_json::from_value::<NetworkProfile>(value).unwrap(),
+            minimal
+        );
+
+        let invalid = NetworkProfile {
+            network: Some("devnet".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.network_type().is_err());
+        assert!(invalid.check_network(Some(NetworkType::Dev)).is_err());
+        assert!(serde_json::from_value::<NetworkProfile>(json!({"network": "dev"})).is_err());
+        for network in &[
+            NetworkType::Mainnet,
+            NetworkType::Testnet,
+            NetworkType::Staging,
+            NetworkType::Dev,
+        ] {
+            assert_eq!(parse_network_name(network_name(*network)), Ok(*network));
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::prelude::*;
+use ckb_signer::{Crypto, KeyStore, ScryptType};
+use ckb_types::H160;
+use faster_hex::hex_string;
+use serde_derive::{Deserialize, Serialize};
+
+use super::account_meta::{AccountMeta, AccountMetaStore};
+
+pub const BACKUP_VERSION: u32 = 1;
+
+/// All accounts of the local keystore. The bundle is encrypted by the backup password, and the
+/// keystore json of every account is kept as is (still encrypted by the account password).
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BackupBundle {
+    pub accounts: Vec<BackupAccount>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BackupAccount {
+    pub lock_arg: H160,
+    /// The keystore file name: `UTC--<time>--<lock_arg>`
+    pub filename: String,
+    /// The keystore json file content, include the ckb root (derivation metadata)
+    pub keystore: serde_json::Value,
+    /// The label, note and tags of the account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<AccountMeta>,
+}
+
+/// The backup file content
+#[derive(Serialize, Deserialize, Debug)]
+struct BackupFile {
+    version: u32,
+    created_at: String,
+    crypto: serde_json::Value,
+}
+
+impl BackupAccount {
+    pub fn has_ckb_root(&self) -> bool {
+        self.keystore.get("ckb_root").is_some()
+    }
+
+    /// The file name must be recognized by the keystore and must not escape the keystore
+    /// directory.
+    pub fn check_filename(&self) -> Result<(), String> {
+        let is_plain_name = Path::new(&self.filename)
+            .file_name()
+            .map(|name| name == self.filename.as_str())
+            .unwrap_or(false);
+        let suffix = format!("--{}", hex_string(self.lock_arg.as_bytes()));
+        if !is_plain_name || !self.filename.ends_with(&suffix) {
+            return Err(format!(
+                "Invalid keystore file name in backup: {}",
+                self.filename
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl BackupBundle {
+    pub fn from_key_store(
+        key_store: &mut KeyStore,
+        meta_store: &AccountMetaStore,
+    ) -> Result<BackupBundle, String> {
+        key_store.refresh_dir().map_err(|err| err.to_string())?;
+        let mut files = key_store
+            .get_accounts()
+            .iter()
+            .map(|(lock_arg, path)| (lock_arg.clone(), path.clone()))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut accounts = Vec::with_capacity(files.len());
+        for (lock_arg, path) in files {
+            let content = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+            let keystore = serde_json::from_str(&content)
+                .map_err(|err| format!("Invalid keystore file {}: {}", path.display(), err))?;
+            let filename = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .expect("keystore file name")
+                .to_string();
+            let meta = meta_store.get(&lock_arg).cloned();
+            accounts.push(BackupAccount {
+                lock_arg,
+                filename,
+                keystore,
+                meta,
+            });
+        }
+        Ok(BackupBundle { accounts })
+    }
+
+    pub fn encrypt(&self, password: &[u8], scrypt_type: ScryptType) -> Result<String, String> {
+        let plaintext = serde_json::to_vec(self).map_err(|err| err.to_string())?;
+        let crypto = Crypto::encrypt_key_scrypt(&plaintext, password, scrypt_type);
+        let file = BackupFile {
+            version: BACKUP_VERSION,
+            created_at: Utc::now().to_rfc3339(),
+            crypto: crypto.to_json(),
+        };
+        serde_json::to_string_pretty(&file).map_err(|err| err.to_string())
+    }
+
+    pub fn decrypt(content: &str, password: &[u8]) -> Result<BackupBundle, String> {
+        let file: BackupFile =
+            serde_json::from_str(content).map_err(|err| format!("Invalid backup file: {}", err))?;
+        if file.version != BACKUP_VERSION {
+            return Err(format!("Unsupported backup version: {}", file.version));
+        }
+        let crypto = Crypto::from_json(&file.crypto).map_err(|err| err.to_string())?;
+        let plaintext = crypto
+            .decrypt(password)
+            .map_err(|_| "Decrypt backup failed, wrong password?".to_string())?;
+        let bundle: BackupBundle = serde_json::from_slice(&plaintext)
+            .map_err(|err| format!("Invalid backup content: {}", err))?;
+        for account in &bundle.accounts {
+            account.check_filename()?;
+        }
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_encrypt_decrypt() {
+        let lock_arg = H160::from_slice(&[7u8; 20]).unwrap();
+        let bundle = BackupBundle {
+            accounts: vec![BackupAccount {
+                lock_arg: lock_arg.clone(),
+                filename: format!("UTC--2020-01-01T00-00-00.0Z--{:x}", lock_arg),
+                keystore: serde_json::json!({"crypto": {}, "ckb_root": {}}),
+                meta: None,
+            }],
+        };
+        let content = bundle.encrypt(b"backup", ScryptType::Light).unwrap();
+        assert_eq!(
+            BackupBundle::decrypt(&content, b"backup"),
+            Ok(bundle.clone())
+        );
+        assert!(BackupBundle::decrypt(&content, b"wrong").is_err());
+        assert!(bundle.accounts[0].has_ckb_root());
+
+        let mut account = bundle.accounts[0].clone();
+        account.filename = format!("../UTC--2020-01-01T00-00-00.0Z--{:x}", lock_arg);
+        assert!(account.check_filename().is_err());
+        account.filename = "UTC--2020-01-01T00-00-00.0Z--00".to_string();
+        assert!(account.check_filename().is_err());
+    }
+}
@@ -1,3 +1,5 @@
+pub mod account_meta;
+pub mod address_book;
 pub mod arg;
 pub mod arg_parser;
 pub mod cell_dep;
@@ -5,12 +7,17 @@ pub mod completer;
 pub mod config;
 pub mod genesis_info;
 pub mod json_color;
+pub mod keystore_backup;
 pub mod mock_tx_helper;
 pub mod other;
 pub mod printer;
 pub mod rpc;
 pub mod signer;
+pub mod table;
+pub mod token_registry;
 pub mod tx_helper;
+#[cfg(unix)]
+pub mod unlock_agent;
 
 #[allow(clippy::cast_lossless)]
 pub mod yaml_ser;
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Read;
 use std::path::PathBuf;
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use ckb_hash::{blake2b_256, new_blake2b};
@@ -24,18 +25,172 @@ use ckb_types::{
 };
 use clap::ArgMatches;
 use colored::Colorize;
+use lazy_static::lazy_static;
 use plugin_protocol::{CellIndex, LiveCellInfo};
 use rpassword::prompt_password_stdout;
 
 use super::arg_parser::{
-    AddressParser, ArgParser, FixedHashParser, HexParser, PrivkeyWrapper, PubkeyHexParser,
+    AddressParser, ArgParser, FixedHashParser, FromStrParser, HexParser, PrivkeyWrapper,
+    PubkeyHexParser,
 };
 use super::rpc::{AlertMessage, HttpRpcClient};
 use super::tx_helper::SignerFn;
 use crate::plugin::{KeyStoreHandler, SignTarget};
 use crate::utils::genesis_info::GenesisInfo;
 
+/// Where `read_password` reads passwords from instead of the terminal, for cron jobs and CI.
+///
+/// A file or file descriptor may hold several passwords, one per line, they are used in the
+/// order of the prompts. Only keystore passwords are read from it, mnemonic words are always
+/// prompted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PasswordSource {
+    File(PathBuf),
+    Env(String),
+    Fd(i32),
+}
+
+impl PasswordSource {
+    /// <password-file>, <password-env> and <password-fd> are global arguments
+    pub fn from_matches(matches: &ArgMatches) -> Result<Option<PasswordSource>, String> {
+        let mut source = None;
+        let mut current = Some(matches);
+        while let Some(m) = current {
+            if let Some(path) = m.value_of("password-file") {
+                source = Some(PasswordSource::File(PathBuf::from(path)));
+            } else if let Some(name) = m.value_of("password-env") {
+                source = Some(PasswordSource::Env(name.to_string()));
+            } else if let Some(input) = m.value_of("password-fd") {
+                source = Some(PasswordSource::Fd(parse_password_fd(input)?));
+            }
+            current = m.subcommand().1;
+        }
+        Ok(source)
+    }
+
+    fn read(&self) -> Result<Vec<String>, String> {
+        let content = match self {
+            PasswordSource::File(path) => {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = fs::metadata(path)
+                        .map_err(|err| format!("Read password file {:?} failed: {}", path, err))?
+                        .permissions()
+                        .mode();
+                    if mode & 0o077 != 0 {
+                        eprintln!(
+                            "WARNING: password file {:?} is accessible by other users, run `chmod 600` on it",
+                            path
+                        );
+                    }
+                }
+                fs::read_to_string(path)
+                    .map_err(|err| format!("Read password file {:?} failed: {}", path, err))?
+            }
+            PasswordSource::Env(name) => {
+                let value = std::env::var(name)
+                    .map_err(|err| format!("Read password from ${} failed: {}", name, err))?;
+                // Not inherited by the plugins and external signers started later
+                std::env::remove_var(name);
+                return Ok(vec![value]);
+            }
+            PasswordSource::Fd(fd) if *fd < 3 => {
+                return Err(format!("Can not read password from fd {}", fd));
+            }
+            #[cfg(unix)]
+            PasswordSource::Fd(fd) => {
+                use std::os::unix::io::FromRawFd;
+                let mut content = String::new();
+                // The file descriptor is given by the parent process and only read once
+                let mut file = unsafe { fs::File::from_raw_fd(*fd) };
+                file.read_to_string(&mut content)
+                    .map_err(|err| format!("Read password from fd {} failed: {}", fd, err))?;
+                content
+            }
+            #[cfg(not(unix))]
+            PasswordSource::Fd(_) => {
+                return Err("--password-fd is only supported on unix".to_string());
+            }
+        };
+        Ok(content
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect())
+    }
+}
+
+/// The file descriptor is closed after reading, so stdin, stdout and stderr are not allowed
+pub fn parse_password_fd(input: &str) -> Result<i32, String> {
+    let fd: i32 = FromStrParser::<i32>::default().parse(input)?;
+    if fd < 3 {
+        return Err(format!(
+            "Invalid file descriptor {}, it must be 3 or larger",
+            fd
+        ));
+    }
+    Ok(fd)
+}
+
+struct PasswordReader {
+    source: PasswordSource,
+    passwords: Option<Vec<String>>,
+    next: usize,
+}
+
+impl PasswordReader {
+    fn next_password(&mut self) -> Result<String, String> {
+        if self.passwords.is_none() {
+            self.passwords = Some(self.source.read()?);
+        }
+        let passwords = self.passwords.as_ref().expect("read passwords");
+        let password = passwords.get(self.next).cloned().ok_or_else(|| {
+            format!(
+                "No password found in {:?} for prompt #{} (one password per line)",
+                self.source,
+                self.next + 1
+            )
+        })?;
+        self.next += 1;
+        Ok(password)
+    }
+}
+
+lazy_static! {
+    static ref PASSWORD_READER: Mutex<Option<PasswordReader>> = Mutex::new(None);
+}
+
+/// Read passwords from the source instead of prompting in the terminal, `None` restores prompting
+pub fn set_password_source(source: Option<PasswordSource>) {
+    *PASSWORD_READER.lock().expect("lock password reader") = source.map(|source| PasswordReader {
+        source,
+        passwords: None,
+        next: 0,
+    });
+}
+
+/// The current password source set by `set_password_source`
+pub fn password_source() -> Option<PasswordSource> {
+    PASSWORD_READER
+        .lock()
+        .expect("lock password reader")
+        .as_ref()
+        .map(|reader| reader.source.clone())
+}
+
 pub fn read_password(repeat: bool, prompt: Option<&str>) -> Result<String, String> {
+    if let Some(reader) = PASSWORD_READER
+        .lock()
+        .expect("lock password reader")
+        .as_mut()
+    {
+        return reader.next_password();
+    }
+    prompt_password(repeat, prompt)
+}
+
+/// Always prompt in the terminal, for the secrets which are not keystore passwords
+pub fn prompt_password(repeat: bool, prompt: Option<&str>) -> Result<String, String> {
     let prompt = prompt.unwrap_or("Password");
     let pass =
         prompt_password_stdout(format!("{}: ", prompt).as_str()).map_err(|err| err.to_string())?;
@@ -49,6 +204,28 @@ pub fn read_password(repeat: bool, prompt: Option<&str>) -> Result<String, Strin
     Ok(pass)
 }
 
+/// The ckb-cli home directory: `$CKB_CLI_HOME` or `~/.ckb-cli`
+pub fn ckb_cli_dir() -> PathBuf {
+    if let Ok(dir_string) = std::env::var("CKB_CLI_HOME") {
+        PathBuf::from(dir_string)
+    } else {
+        let mut dir = dirs::home_dir().unwrap();
+        dir.push(".ckb-cli");
+        dir
+    }
+}
+
+/// The account of the file based keystore is unlocked in the running unlock agent
+#[cfg(unix)]
+pub fn is_unlocked_in_agent(account: &H160) -> bool {
+    super::unlock_agent::AgentClient::new(&ckb_cli_dir()).is_unlocked(account)
+}
+
+#[cfg(not(unix))]
+pub fn is_unlocked_in_agent(_account: &H160) -> bool {
+    false
+}
+
 pub fn get_key_store(ckb_cli_dir: PathBuf) -> Result<KeyStore, String> {
     let mut keystore_dir = ckb_cli_dir;
     keystore_dir.push("keystore");
@@ -208,6 +385,16 @@ pub fn get_network_type(rpc_client: &mut HttpRpcClient) -> Result<NetworkType, S
         .ok_or_else(|| format!("Unexpected network type: {}", chain_info.chain))
 }
 
+/// Use the fee rate of the active profile when <fee-rate> is not given
+pub fn get_fee_rate(m: &ArgMatches, rpc_client: &HttpRpcClient) -> Result<u64, String> {
+    if m.occurrences_of("fee-rate") == 0 {
+        if let Some(fee_rate) = rpc_client.profile().and_then(|profile| profile.fee_rate) {
+            return Ok(fee_rate);
+        }
+    }
+    FromStrParser::<u64>::default().from_matches(m, "fee-rate")
+}
+
 pub fn check_capacity(capacity: u64, to_data_len: usize) -> Result<(), String> {
     if capacity < MIN_SECP_CELL_CAPACITY {
         return Err(format!(
@@ -343,3 +530,92 @@ pub(crate) fn map_tx_builder_error_2_str(no_max_tx_fee: bool, err: TxBuilderErro
     }
     err.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_file(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("ckb-cli-test-{}-{}", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_password_source() {
+        let path = test_file("password-file", "pass1\r\n\npass 3\n");
+        let mut reader = PasswordReader {
+            source: PasswordSource::File(path.clone()),
+            passwords: None,
+            next: 0,
+        };
+        assert_eq!(reader.next_password(), Ok("pass1".to_string()));
+        assert_eq!(reader.next_password(), Ok(String::new()));
+        assert_eq!(reader.next_password(), Ok("pass 3".to_string()));
+        assert_eq!(
+            reader.next_password(),
+            Err(format!(
+                "No password found in File({:?}) for prompt #4 (one password per line)",
+                path
+            ))
+        );
+        fs::remove_file(&path).unwrap();
+
+        let name = format!("CKB_CLI_TEST_PASSWORD_{}", std::process::id());
+        std::env::set_var(&name, "env pass\n");
+        let source = PasswordSource::Env(name.clone());
+        assert_eq!(source.read(), Ok(vec!["env pass\n".to_string()]));
+        assert!(std::env::var(&name).is_err());
+        assert!(source.read().is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::IntoRawFd;
+            let path = test_file("password-fd", "fd pass\n");
+            let fd = fs::File::open(&path).unwrap().into_raw_fd();
+            assert_eq!(
+                PasswordSource::Fd(fd).read(),
+                Ok(vec!["fd pass".to_string()])
+            );
+            fs::remove_file(&path).unwrap();
+        }
+        for fd in &[-1, 0, 1, 2] {
+            assert!(PasswordSource::Fd(*fd).read().is_err());
+            assert!(parse_password_fd(&fd.to_string()).is_err());
+        }
+        assert_eq!(parse_password_fd("3"), Ok(3));
+    }
+
+    #[test]
+    fn test_get_fee_rate() {
+        use crate::utils::config::NetworkProfile;
+        let app = clap::App::new("test").arg(crate::utils::arg::fee_rate());
+        let get = |args: &[&str], rpc_client: &HttpRpcClient| {
+            let m = app.clone().try_get_matches_from(args).unwrap();
+            get_fee_rate(&m, rpc_client)
+        };
+        let rpc_client = HttpRpcClient::new("http://127.0.0.1:8114".to_string());
+        let profile_client = HttpRpcClient::from_profile(NetworkProfile {
+            url: "http://127.0.0.1:8114".to_string(),
+            fee_rate: Some(2000),
+            ..Default::default()
+        });
+        let no_fee_rate_client = HttpRpcClient::from_profile(NetworkProfile {
+            url: "http://127.0.0.1:8114".to_string(),
+            ..Default::default()
+        });
+        // <fee-rate> > profile fee rate > default value
+        assert_eq!(
+            get(&["test", "--fee-rate", "3000"], &profile_client),
+            Ok(3000)
+        );
+        assert_eq!(
+            get(&["test", "--fee-rate", "1000"], &profile_client),
+            Ok(1000)
+        );
+        assert_eq!(get(&["test"], &profile_client), Ok(2000));
+        assert_eq!(get(&["test"], &no_fee_rate_client), Ok(1000));
+        assert_eq!(get(&["test"], &rpc_client), Ok(1000));
+    }
+}
@@ -1,9 +1,11 @@
 use std::env;
 use std::fmt;
 
+use clap::ArgMatches;
 use colored::Colorize;
 
 use crate::utils::json_color::Colorizer;
+use crate::utils::table::render_table;
 use crate::utils::yaml_ser;
 
 pub fn is_a_tty(stderr: bool) -> bool {
@@ -23,6 +25,9 @@ pub fn is_term_dumb() -> bool {
 pub enum OutputFormat {
     Yaml,
     Json,
+    Table,
+    Csv,
+    Tsv,
 }
 
 impl fmt::Display for OutputFormat {
@@ -33,6 +38,9 @@ impl fmt::Display for OutputFormat {
             match self {
                 OutputFormat::Yaml => "yaml",
                 OutputFormat::Json => "json",
+                OutputFormat::Table => "table",
+                OutputFormat::Csv => "csv",
+                OutputFormat::Tsv => "tsv",
             }
         )
     }
@@ -43,6 +51,9 @@ impl OutputFormat {
         match format {
             "yaml" => Ok(OutputFormat::Yaml),
             "json" => Ok(OutputFormat::Json),
+            "table" => Ok(OutputFormat::Table),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
             _ => Err(format!("Invalid output format: {}", format)),
         }
     }
@@ -108,10 +119,211 @@ where
                     serde_json::to_string_pretty(&value).unwrap()
                 }
             }
+            // Fall back to yaml if the value is not tabular
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {
+                let value = serde_json::to_value(self).unwrap();
+                render_table(&value, format, None, color)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| value.render(OutputFormat::Yaml, color))
+            }
+        }
+    }
+}
+
+/// Select part of the output before rendering, the query is a JSONPath subset: `$.a.b[0]`,
+/// `a.b[*].c`, `['key with.dot']`. With `raw`, scalars are printed without quotes. The
+/// columns select the columns of table, csv and tsv formats.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputQuery {
+    path: Option<(String, Vec<PathSegment>)>,
+    raw: bool,
+    columns: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+impl OutputQuery {
+    pub fn new(path: Option<&str>, raw: bool) -> Result<OutputQuery, String> {
+        let path = path
+            .map(|input| parse_json_path(input).map(|segments| (input.to_string(), segments)))
+            .transpose()?;
+        Ok(OutputQuery {
+            path,
+            raw,
+            columns: None,
+        })
+    }
+
+    /// The columns are separated by comma: `capacity,out_point.tx_hash`
+    pub fn with_columns(mut self, columns: Option<&str>) -> OutputQuery {
+        self.columns = columns.map(|columns| {
+            columns
+                .split(',')
+                .map(|column| column.trim().to_string())
+                .filter(|column| !column.is_empty())
+                .collect()
+        });
+        self
+    }
+
+    /// <query>, <raw> and <columns> are global arguments, they can be given after any subcommand
+    pub fn from_matches(matches: &ArgMatches) -> Result<OutputQuery, String> {
+        let mut path = None;
+        let mut raw = false;
+        let mut columns = None;
+        let mut current = Some(matches);
+        while let Some(m) = current {
+            path = m.value_of("query").or(path);
+            raw = raw || m.is_present("raw");
+            columns = m.value_of("columns").or(columns);
+            current = m.subcommand().1;
+        }
+        OutputQuery::new(path, raw).map(|query| query.with_columns(columns))
+    }
+
+    pub fn select(&self, value: &serde_json::Value) -> Result<serde_json::Value, String> {
+        let (input, segments) = match self.path.as_ref() {
+            Some(path) => path,
+            None => return Ok(value.clone()),
+        };
+        let mut values = vec![value];
+        for segment in segments {
+            values = values
+                .into_iter()
+                .flat_map(|value| match (segment, value) {
+                    (PathSegment::Key(key), value) => value.get(key).into_iter().collect(),
+                    (PathSegment::Index(index), serde_json::Value::Array(items)) => {
+                        let index = if *index < 0 {
+                            items.len() as i64 + index
+                        } else {
+                            *index
+                        };
+                        usize::try_from(index)
+                            .ok()
+                            .and_then(|index| items.get(index))
+                            .into_iter()
+                            .collect()
+                    }
+                    (PathSegment::Wildcard, serde_json::Value::Array(items)) => {
+                        items.iter().collect()
+                    }
+                    (PathSegment::Wildcard, serde_json::Value::Object(map)) => {
+                        map.values().collect()
+                    }
+                    _ => Vec::new(),
+                })
+                .collect();
+        }
+        if segments.contains(&PathSegment::Wildcard) {
+            Ok(serde_json::Value::Array(
+                values.into_iter().cloned().collect(),
+            ))
+        } else {
+            values
+                .pop()
+                .cloned()
+                .ok_or_else(|| format!("Nothing matched the query: {}", input))
+        }
+    }
+
+    pub fn render(
+        &self,
+        value: &serde_json::Value,
+        format: OutputFormat,
+        color: bool,
+    ) -> Result<String, String> {
+        let value = self.select(value)?;
+        if self.raw {
+            let items = match &value {
+                serde_json::Value::Array(items) => items.iter().collect::<Vec<_>>(),
+                value => vec![value],
+            };
+            let scalars = items
+                .iter()
+                .map(|item| match item {
+                    serde_json::Value::String(s) => Some(s.clone()),
+                    serde_json::Value::Number(n) => Some(n.to_string()),
+                    serde_json::Value::Bool(b) => Some(b.to_string()),
+                    serde_json::Value::Null => Some("null".to_string()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(scalars) = scalars {
+                return Ok(scalars.join("\n"));
+            }
+        }
+        match format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {
+                Ok(
+                    render_table(&value, format, self.columns.as_deref(), color)?
+                        .unwrap_or_else(|| value.render(OutputFormat::Yaml, color)),
+                )
+            }
+            _ => Ok(value.render(format, color)),
         }
     }
 }
 
+fn parse_json_path(input: &str) -> Result<Vec<PathSegment>, String> {
+    let invalid = |reason: &str| format!("Invalid query {:?}: {}", input, reason);
+    let mut segments = Vec::new();
+    let mut rest = input.trim();
+    rest = rest.strip_prefix('$').unwrap_or(rest);
+    let mut first = true;
+    while !rest.is_empty() {
+        if let Some(bracket) = rest.strip_prefix('[') {
+            let end = bracket.find(']').ok_or_else(|| invalid("missing `]`"))?;
+            let inner = bracket[..end].trim();
+            let segment = if inner == "*" {
+                PathSegment::Wildcard
+            } else if let Some(key) = inner
+                .strip_prefix('\'')
+                .and_then(|inner| inner.strip_suffix('\''))
+                .or_else(|| {
+                    inner
+                        .strip_prefix('"')
+                        .and_then(|inner| inner.strip_suffix('"'))
+                })
+            {
+                PathSegment::Key(key.to_string())
+            } else {
+                PathSegment::Index(
+                    inner
+                        .parse::<i64>()
+                        .map_err(|_| invalid("expected index, `*` or quoted key in `[]`"))?,
+                )
+            };
+            segments.push(segment);
+            rest = &bracket[end + 1..];
+        } else {
+            let name_start = match rest.strip_prefix('.') {
+                Some(name) => name,
+                None if first => rest,
+                None => return Err(invalid("expected `.` or `[`")),
+            };
+            let end = name_start.find(&['.', '['][..]).unwrap_or(name_start.len());
+            let name = &name_start[..end];
+            if name.is_empty() {
+                return Err(invalid("empty key"));
+            }
+            segments.push(if name == "*" {
+                PathSegment::Wildcard
+            } else {
+                PathSegment::Key(name.to_string())
+            });
+            rest = &name_start[end..];
+        }
+        first = false;
+    }
+    Ok(segments)
+}
+
 #[derive(Clone, Debug)]
 pub enum TypedStr<'a> {
     Null(Option<&'a str>),
@@ -157,3 +369,48 @@ impl<'a> TypedStr<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_query() {
+        let value = serde_json::json!({
+            "total": "100.0 (CKB)",
+            "live_cells": [
+                {"capacity": 61, "out_point": {"index": "0x0"}},
+                {"capacity": 39, "out_point": {"index": "0x1"}},
+            ],
+            "address(deprecated)": "ckt1",
+            "a.b": true,
+        });
+        let select = |path: &str| OutputQuery::new(Some(path), false).unwrap().select(&value);
+        assert_eq!(select("total"), Ok(serde_json::json!("100.0 (CKB)")));
+        assert_eq!(
+            select("$.live_cells[1].capacity"),
+            Ok(serde_json::json!(39))
+        );
+        assert_eq!(select("live_cells[-1].capacity"), Ok(serde_json::json!(39)));
+        assert_eq!(
+            select("$.live_cells[*].out_point.index"),
+            Ok(serde_json::json!(["0x0", "0x1"]))
+        );
+        assert_eq!(select("address(deprecated)"), Ok(serde_json::json!("ckt1")));
+        assert_eq!(select("['a.b']"), Ok(serde_json::json!(true)));
+        assert!(select("missing").is_err());
+        assert!(OutputQuery::new(Some("live_cells[x]"), false).is_err());
+        assert!(OutputQuery::new(Some("total..a"), false).is_err());
+
+        let raw = OutputQuery::new(Some("live_cells[*].capacity"), true).unwrap();
+        assert_eq!(
+            raw.render(&value, OutputFormat::Json, false),
+            Ok("61\n39".to_string())
+        );
+        let raw = OutputQuery::new(Some("total"), true).unwrap();
+        assert_eq!(
+            raw.render(&value, OutputFormat::Yaml, false),
+            Ok("100.0 (CKB)".to_string())
+        );
+    }
+}
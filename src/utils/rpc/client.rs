@@ -6,6 +6,7 @@ use ckb_jsonrpc_types::{
 
 use super::primitive;
 use super::types;
+use crate::utils::config::{url_with_scheme, NetworkProfile};
 use ckb_types::{packed, H256};
 
 pub use ckb_sdk::CkbRpcClient as RawHttpRpcClient;
@@ -13,22 +14,54 @@ pub use ckb_sdk::CkbRpcClient as RawHttpRpcClient;
 pub struct HttpRpcClient {
     url: String,
     client: RawHttpRpcClient,
+    profile: Option<NetworkProfile>,
 }
 
 impl HttpRpcClient {
     pub fn new(url: String) -> HttpRpcClient {
         let client = RawHttpRpcClient::new(url.as_str());
-        HttpRpcClient { url, client }
+        HttpRpcClient {
+            url,
+            client,
+            profile: None,
+        }
+    }
+
+    /// The url of the client is the url of the profile, the indexer url and the default values of
+    /// the profile are used by subcommands.
+    pub fn from_profile(profile: NetworkProfile) -> HttpRpcClient {
+        let mut rpc_client = HttpRpcClient::new(url_with_scheme(profile.url.clone()));
+        rpc_client.profile = Some(profile);
+        rpc_client
     }
 
     pub fn url(&self) -> &str {
         self.url.as_str()
     }
+    pub fn indexer_url(&self) -> &str {
+        self.profile
+            .as_ref()
+            .and_then(|profile| profile.indexer_url.as_deref())
+            .unwrap_or_else(|| self.url())
+    }
+    pub fn profile(&self) -> Option<&NetworkProfile> {
+        self.profile.as_ref()
+    }
     pub fn client(&mut self) -> &mut RawHttpRpcClient {
         &mut self.client
     }
 }
 
+impl Clone for HttpRpcClient {
+    fn clone(&self) -> HttpRpcClient {
+        HttpRpcClient {
+            url: self.url.clone(),
+            client: RawHttpRpcClient::new(self.url.as_str()),
+            profile: self.profile.clone(),
+        }
+    }
+}
+
 impl HttpRpcClient {
     // Chain
     pub fn get_block(&mut self, hash: H256) -> Result<Option<types::BlockView>, String> {
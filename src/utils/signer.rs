@@ -1,4 +1,7 @@
 use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex};
 
 use anyhow::anyhow;
 use bitcoin::util::bip32::DerivationPath;
@@ -6,13 +9,16 @@ use bitcoin::util::bip32::DerivationPath;
 use ckb_hash::blake2b_256;
 use ckb_jsonrpc_types as json_types;
 use ckb_sdk::traits::{
-    Signer, SignerError, TransactionDependencyError, TransactionDependencyProvider,
+    DefaultTransactionDependencyProvider, Signer, SignerError, TransactionDependencyError,
+    TransactionDependencyProvider,
 };
 use ckb_sdk::types::ScriptId;
 use ckb_sdk::util::serialize_signature;
 use ckb_sdk::SECP256K1;
 use ckb_signer::KeyChain;
 use ckb_types::{bytes::Bytes, core::TransactionView, packed::Script, prelude::*, H160, H256};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use serde_derive::{Deserialize, Serialize};
 
 use super::arg_parser::PrivkeyWrapper;
 use crate::plugin::{KeyStoreHandler, SignTarget};
@@ -248,11 +254,10 @@ impl Signer for KeyStoreHandlerSigner {
             .has_account_in_default(account.clone())
             .map_err(|err| SignerError::Other(anyhow!(err)))?
         {
-            let password = self.passwords.get(&account).cloned().ok_or_else(|| {
-                SignerError::Other(anyhow!("no password is set for account: {:x}", account))
-            })?;
+            // Without password the key unlocked in the unlock agent is used
+            let password = self.passwords.get(&account).cloned();
             let target = SignTarget::AnyData(Default::default());
-            (Some(password), target)
+            (password, target)
         } else {
             let inputs = tx
                 .inputs()
@@ -317,3 +322,394 @@ impl Signer for CommonSigner {
         signer.sign(id, message, recoverable, tx)
     }
 }
+
+/// The request sent to the external signer, one json object per line
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExternalSignRequest {
+    /// The signer id to match (lock args or lock script hash160)
+    pub id: H160,
+    /// The sighash lock args (blake160 of the public key) of the key to sign with
+    pub account: H160,
+    pub message: json_types::JsonBytes,
+    pub recoverable: bool,
+    pub transaction: json_types::Transaction,
+    /// The resolved input cells of the transaction, in the same order of the inputs
+    pub inputs: Vec<ExternalSignInput>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExternalSignInput {
+    pub out_point: json_types::OutPoint,
+    pub output: json_types::CellOutput,
+    pub data: json_types::JsonBytes,
+}
+
+/// The response of the external signer, one json object per line
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExternalSignResponse {
+    #[serde(default)]
+    pub signature: Option<json_types::JsonBytes>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A running external signer, requests are answered one by one in order
+trait ExternalSignerSession: Send {
+    fn request(&mut self, request: &ExternalSignRequest) -> Result<ExternalSignResponse, String>;
+}
+
+struct ExternalSignerProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ExternalSignerProcess {
+    fn spawn(command: &str) -> Result<ExternalSignerProcess, String> {
+        let words = shell_words::split(command)
+            .map_err(|err| format!("Invalid external signer command: {}", err))?;
+        let (program, args) = words
+            .split_first()
+            .ok_or_else(|| "External signer command is empty".to_string())?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Spawn external signer {} failed: {}", program, err))?;
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+        Ok(ExternalSignerProcess {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+}
+
+impl ExternalSignerSession for ExternalSignerProcess {
+    fn request(&mut self, request: &ExternalSignRequest) -> Result<ExternalSignResponse, String> {
+        let mut line = serde_json::to_string(request).map_err(|err| err.to_string())?;
+        line.push('\n');
+        let stdin = self.stdin.as_mut().expect("stdin is only closed on drop");
+        stdin
+            .write_all(line.as_bytes())
+            .and_then(|()| stdin.flush())
+            .map_err(|err| format!("Write to external signer failed: {}", err))?;
+        let mut line = String::new();
+        let size = self
+            .stdout
+            .read_line(&mut line)
+            .map_err(|err| format!("Read from external signer failed: {}", err))?;
+        if size == 0 {
+            return Err("External signer exited without response".to_string());
+        }
+        serde_json::from_str(&line)
+            .map_err(|err| format!("Invalid external signer response: {}", err))
+    }
+}
+
+impl Drop for ExternalSignerProcess {
+    fn drop(&mut self) {
+        // Close stdin to tell the external signer the session is finished
+        self.stdin.take();
+        let _ = self.child.wait();
+    }
+}
+
+/// Sign with an external command (for example a hardware wallet bridge). The command is spawned
+/// on the first signing request and is kept running until the signer (and all its clones) is
+/// dropped, each request and response is a line of json through its stdin/stdout:
+///
+///   request:  `ExternalSignRequest`, the full transaction and the resolved inputs are included
+///             so the signer can show the transaction details before signing.
+///   response: `{"signature": "0x.."}` or `{"error": ".."}`
+#[derive(Clone)]
+pub struct ExternalSigner {
+    command: String,
+    tx_dep_provider: Arc<dyn TransactionDependencyProvider>,
+    ids: HashMap<H160, H160>,
+    session: Arc<Mutex<Option<Box<dyn ExternalSignerSession>>>>,
+}
+
+impl ExternalSigner {
+    pub fn new(
+        command: String,
+        tx_dep_provider: Box<dyn TransactionDependencyProvider>,
+    ) -> ExternalSigner {
+        ExternalSigner {
+            command,
+            tx_dep_provider: Arc::from(tx_dep_provider),
+            ids: HashMap::default(),
+            session: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The input cells are resolved from the ckb node
+    pub fn from_rpc_url(command: String, url: &str) -> ExternalSigner {
+        Self::new(
+            command,
+            Box::new(DefaultTransactionDependencyProvider::new(url, 0)),
+        )
+    }
+
+    pub fn for_account(command: String, url: &str, account: H160) -> ExternalSigner {
+        let mut signer = Self::from_rpc_url(command, url);
+        signer.add_account(account);
+        signer
+    }
+
+    /// Add a sighash lock args the external signer holds the key of
+    pub fn add_account(&mut self, account: H160) {
+        self.ids.insert(account.clone(), account);
+    }
+
+    pub fn has_account(&self, account: &H160) -> bool {
+        self.ids.get(account) == Some(account)
+    }
+
+    pub fn cache_account_lock_hash160(&mut self, account: H160, script_id: &ScriptId) -> bool {
+        if self.has_account(&account) {
+            let script_hash = Script::new_builder()
+                .code_hash(script_id.code_hash.pack())
+                .hash_type(script_id.hash_type.into())
+                .args(Bytes::from(account.as_bytes().to_vec()).pack())
+                .build()
+                .calc_script_hash();
+            let lock_hash160 = H160::from_slice(&script_hash.as_slice()[0..20]).unwrap();
+            self.ids.insert(lock_hash160, account);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn resolve_inputs(
+        &self,
+        tx: &TransactionView,
+    ) -> Result<Vec<ExternalSignInput>, TransactionDependencyError> {
+        tx.inputs()
+            .into_iter()
+            .map(|input| {
+                let out_point = input.previous_output();
+                let output = self.tx_dep_provider.get_cell(&out_point)?;
+                let data = self.tx_dep_provider.get_cell_data(&out_point)?;
+                Ok(ExternalSignInput {
+                    out_point: out_point.into(),
+                    output: output.into(),
+                    data: json_types::JsonBytes::from_bytes(data),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn match_id(&self, id: &[u8]) -> bool {
+        if id.len() != 20 {
+            return false;
+        }
+        self.ids.contains_key(&H160::from_slice(id).unwrap())
+    }
+
+    fn sign(
+        &self,
+        id: &[u8],
+        message: &[u8],
+        recoverable: bool,
+        tx: &TransactionView,
+    ) -> Result<Bytes, SignerError> {
+        if id.len() != 20 {
+            return Err(SignerError::IdNotFound);
+        }
+        let hash160 = H160::from_slice(id).unwrap();
+        let account = self.ids.get(&hash160).ok_or(SignerError::IdNotFound)?;
+        if message.len() != 32 {
+            return Err(SignerError::InvalidMessage(format!(
+                "expected length: 32, got: {}",
+                message.len()
+            )));
+        }
+        let inputs = self
+            .resolve_inputs(tx)
+            .map_err(|err| SignerError::Other(err.into()))?;
+        let request = ExternalSignRequest {
+            id: hash160.clone(),
+            account: account.clone(),
+            message: json_types::JsonBytes::from_vec(message.to_vec()),
+            recoverable,
+            transaction: tx.data().into(),
+            inputs,
+        };
+
+        let mut session = self.session.lock().expect("external signer session lock");
+        if session.is_none() {
+            *session = Some(Box::new(
+                ExternalSignerProcess::spawn(&self.command)
+                    .map_err(|err| SignerError::Other(anyhow!(err)))?,
+            ));
+        }
+        let response = session
+            .as_mut()
+            .unwrap()
+            .request(&request)
+            .map_err(|err| SignerError::Other(anyhow!(err)))?;
+        if let Some(err) = response.error {
+            return Err(SignerError::Other(anyhow!(
+                "external signer rejected: {}",
+                err
+            )));
+        }
+        let signature = response
+            .signature
+            .ok_or_else(|| SignerError::Other(anyhow!("external signer returned no signature")))?
+            .into_bytes();
+        let expected_len = if recoverable { 65 } else { 64 };
+        if signature.len() != expected_len {
+            return Err(SignerError::Other(anyhow!(
+                "invalid signature length from external signer, expected: {}, got: {}",
+                expected_len,
+                signature.len()
+            )));
+        }
+        if recoverable {
+            check_recovered_account(message, &signature, account)?;
+        }
+        Ok(signature)
+    }
+}
+
+/// The external signer may hold many keys, make sure the signature is signed by the account
+fn check_recovered_account(
+    message: &[u8],
+    signature: &[u8],
+    account: &H160,
+) -> Result<(), SignerError> {
+    let invalid_signature =
+        |err: secp256k1::Error| SignerError::Other(anyhow!("invalid signature: {}", err));
+    let recov_id = RecoveryId::from_i32(i32::from(signature[64])).map_err(invalid_signature)?;
+    let signature = RecoverableSignature::from_compact(&signature[0..64], recov_id)
+        .map_err(invalid_signature)?;
+    let message = secp256k1::Message::from_slice(message).map_err(invalid_signature)?;
+    let pubkey = SECP256K1
+        .recover_ecdsa(&message, &signature)
+        .map_err(invalid_signature)?;
+    let pubkey_hash = blake2b_256(&pubkey.serialize()[..]);
+    if &pubkey_hash[0..20] != account.as_bytes() {
+        return Err(SignerError::Other(anyhow!(
+            "external signer signed with another key: {:#x}, expected account: {:#x}",
+            H160::from_slice(&pubkey_hash[0..20]).unwrap(),
+            account
+        )));
+    }
+    Ok(())
+}
+
+/// A signer only matches the lock args, used to fill placeholder witnesses of a transaction
+/// which will be signed later.
+#[derive(Clone)]
+pub struct DummySigner {
+    pub args: Vec<Bytes>,
+}
+impl Signer for DummySigner {
+    fn match_id(&self, id: &[u8]) -> bool {
+        self.args.iter().any(|arg| arg.as_ref() == id)
+    }
+    fn sign(&self, _: &[u8], _: &[u8], _: bool, _: &TransactionView) -> Result<Bytes, SignerError> {
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl<F> ExternalSignerSession for F
+    where
+        F: FnMut(&ExternalSignRequest) -> Result<ExternalSignResponse, String> + Send,
+    {
+        fn request(
+            &mut self,
+            request: &ExternalSignRequest,
+        ) -> Result<ExternalSignResponse, String> {
+            self(request)
+        }
+    }
+
+    fn fake_signer<F>(account: &H160, session: F) -> ExternalSigner
+    where
+        F: FnMut(&ExternalSignRequest) -> Result<ExternalSignResponse, String> + Send + 'static,
+    {
+        let signer =
+            ExternalSigner::for_account(String::new(), "http://127.0.0.1:8114", account.clone());
+        *signer.session.lock().unwrap() = Some(Box::new(session));
+        signer
+    }
+
+    fn signed(signature: Vec<u8>) -> Result<ExternalSignResponse, String> {
+        Ok(ExternalSignResponse {
+            signature: Some(json_types::JsonBytes::from_vec(signature)),
+            error: None,
+        })
+    }
+
+    #[test]
+    fn test_external_signer() {
+        let privkey = secp256k1::SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let pubkey = secp256k1::PublicKey::from_secret_key(&SECP256K1, &privkey);
+        let account = H160::from_slice(&blake2b_256(&pubkey.serialize()[..])[0..20]).unwrap();
+        let other = H160::from_slice(&[4u8; 20]).unwrap();
+        let tx = TransactionView::new_advanced_builder().build();
+        let message = [1u8; 32];
+        // The fake signer always answers with the recoverable signature of the key
+        let sign = move |request: &ExternalSignRequest| {
+            let message = secp256k1::Message::from_slice(request.message.as_bytes()).unwrap();
+            let signature = SECP256K1.sign_ecdsa_recoverable(&message, &privkey);
+            signed(serialize_signature(&signature).to_vec())
+        };
+
+        let signer = fake_signer(&account, sign);
+        assert!(signer.match_id(account.as_bytes()));
+        assert!(!signer.match_id(other.as_bytes()));
+        for _ in 0..2 {
+            let result = signer
+                .sign(account.as_bytes(), &message, true, &tx)
+                .unwrap();
+            assert_eq!(result.len(), 65);
+        }
+        assert!(matches!(
+            signer.sign(other.as_bytes(), &message, true, &tx),
+            Err(SignerError::IdNotFound)
+        ));
+        // 65 bytes returned for a non-recoverable request
+        let err = signer
+            .sign(account.as_bytes(), &message, false, &tx)
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid signature length"));
+
+        // Signed by the key of another account
+        let signer = fake_signer(&other, sign);
+        let err = signer
+            .sign(other.as_bytes(), &message, true, &tx)
+            .unwrap_err();
+        assert!(err.to_string().contains("another key"));
+
+        let signer = fake_signer(&account, |_: &ExternalSignRequest| {
+            Ok(ExternalSignResponse {
+                signature: None,
+                error: Some("user canceled".to_string()),
+            })
+        });
+        let err = signer
+            .sign(account.as_bytes(), &message, true, &tx)
+            .unwrap_err();
+        assert!(err.to_string().contains("user canceled"));
+
+        let signer = fake_signer(&account, |_: &ExternalSignRequest| {
+            Err("External signer exited without response".to_string())
+        });
+        assert!(signer
+            .sign(account.as_bytes(), &message, true, &tx)
+            .is_err());
+    }
+}
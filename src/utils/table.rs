@@ -0,0 +1,347 @@
+use serde_json::{Map, Value};
+
+use super::printer::{OutputFormat, Printable, TypedStr};
+
+/// Render an array of objects (or an object holding one) as rows of a table, csv or tsv.
+/// Nested objects are flattened into `parent.child` columns, nested arrays are printed as
+/// compact json. Return `None` if the value is not tabular.
+pub fn render_table(
+    value: &Value,
+    format: OutputFormat,
+    columns: Option<&[String]>,
+    color: bool,
+) -> Result<Option<String>, String> {
+    let (rows, extra) = match tabular_rows(value) {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            let mut cells = Vec::new();
+            flatten("", row, &mut cells);
+            cells
+        })
+        .collect::<Vec<_>>();
+    let mut all_columns: Vec<String> = Vec::new();
+    for (key, _) in rows.iter().flatten() {
+        if !all_columns.contains(key) {
+            all_columns.push(key.clone());
+        }
+    }
+    let columns = match columns {
+        Some(selected) => select_columns(&all_columns, selected)?,
+        None => all_columns,
+    };
+    let cells = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| {
+                    row.iter()
+                        .find(|(key, _)| key == column)
+                        .map(|(_, value)| *value)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let output = match format {
+        OutputFormat::Csv => render_separated(&columns, &cells, ','),
+        OutputFormat::Tsv => render_separated(&columns, &cells, '\t'),
+        _ => {
+            let mut output = render_aligned(&columns, &cells, color);
+            // The other fields of the object (like `total_capacity`) are printed below the table
+            if !extra.is_empty() {
+                output.push_str("\n\n");
+                output.push_str(&Value::Object(extra).render(OutputFormat::Yaml, color));
+            }
+            output
+        }
+    };
+    Ok(Some(output))
+}
+
+type TabularRows<'a> = (Vec<&'a Map<String, Value>>, Map<String, Value>);
+
+fn tabular_rows(value: &Value) -> Option<TabularRows<'_>> {
+    fn as_rows(value: &Value) -> Option<Vec<&Map<String, Value>>> {
+        match value {
+            Value::Array(items) if !items.is_empty() => {
+                items.iter().map(Value::as_object).collect()
+            }
+            _ => None,
+        }
+    }
+    match value {
+        Value::Array(_) => as_rows(value).map(|rows| (rows, Map::new())),
+        Value::Object(map) => {
+            let mut tables = map.iter().filter(|(_, value)| as_rows(value).is_some());
+            let (table_key, table) = tables.next()?;
+            if tables.next().is_some() {
+                return None;
+            }
+            let extra = map
+                .iter()
+                .filter(|(key, _)| *key != table_key)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            as_rows(table).map(|rows| (rows, extra))
+        }
+        _ => None,
+    }
+}
+
+fn flatten<'a>(prefix: &str, map: &'a Map<String, Value>, cells: &mut Vec<(String, &'a Value)>) {
+    for (key, value) in map {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::Object(inner) if !inner.is_empty() => flatten(&key, inner, cells),
+            _ => cells.push((key, value)),
+        }
+    }
+}
+
+/// A selected column also selects its flattened children: `out_point` selects
+/// `out_point.tx_hash` and `out_point.index`
+fn select_columns(all_columns: &[String], selected: &[String]) -> Result<Vec<String>, String> {
+    let mut columns = Vec::new();
+    for name in selected {
+        let prefix = format!("{}.", name);
+        let matched = all_columns
+            .iter()
+            .filter(|column| *column == name || column.starts_with(&prefix))
+            .collect::<Vec<_>>();
+        if matched.is_empty() {
+            return Err(format!(
+                "Unknown column: {}, available columns: {}",
+                name,
+                all_columns.join(", ")
+            ));
+        }
+        for column in matched {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+    }
+    Ok(columns)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ColumnKind {
+    Number,
+    Hash,
+    Bool,
+    Text,
+}
+
+impl ColumnKind {
+    fn of_column(cells: &[Vec<Option<&Value>>], index: usize) -> ColumnKind {
+        let kinds = cells
+            .iter()
+            .filter_map(|row| row[index])
+            .filter(|value| !value.is_null())
+            .map(|value| match value {
+                Value::Number(_) => ColumnKind::Number,
+                Value::Bool(_) => ColumnKind::Bool,
+                Value::String(s) if is_hex(s) => ColumnKind::Hash,
+                Value::String(s) if is_number(s) => ColumnKind::Number,
+                _ => ColumnKind::Text,
+            })
+            .collect::<Vec<_>>();
+        match kinds.first() {
+            Some(first) if kinds.iter().all(|kind| kind == first) => *first,
+            _ => ColumnKind::Text,
+        }
+    }
+
+    fn render(self, content: &str, color: bool) -> String {
+        match self {
+            ColumnKind::Number => TypedStr::Number(content).render(color),
+            ColumnKind::Hash => TypedStr::String(content).render(color),
+            ColumnKind::Bool => TypedStr::Bool(content).render(color),
+            ColumnKind::Text => content.to_string(),
+        }
+    }
+}
+
+fn is_hex(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .map(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or(false)
+}
+
+/// Numbers with an unit suffix like `100.0 (CKB)` are also numbers
+fn is_number(value: &str) -> bool {
+    value
+        .split_whitespace()
+        .next()
+        .map(|number| number.parse::<f64>().is_ok())
+        .unwrap_or(false)
+}
+
+fn cell_text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn render_aligned(columns: &[String], cells: &[Vec<Option<&Value>>], color: bool) -> String {
+    let texts = cells
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|value| cell_text(*value))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let kinds = (0..columns.len())
+        .map(|index| ColumnKind::of_column(cells, index))
+        .collect::<Vec<_>>();
+    let widths = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            texts
+                .iter()
+                .map(|row| row[index].chars().count())
+                .chain(Some(column.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    // The padding is added outside of the color codes
+    let pad = |content: String, width: usize, right_align: bool| {
+        let padding = " ".repeat(width);
+        if right_align {
+            format!("{}{}", padding, content)
+        } else {
+            format!("{}{}", content, padding)
+        }
+    };
+    let line = |cells: Vec<String>| cells.join("  ").trim_end().to_string();
+
+    let mut lines = Vec::with_capacity(texts.len() + 2);
+    lines.push(line(
+        columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, width)| {
+                let padding = width - column.chars().count();
+                pad(TypedStr::Key(column).render(color), padding, false)
+            })
+            .collect(),
+    ));
+    lines.push(line(
+        widths.iter().map(|width| "-".repeat(*width)).collect(),
+    ));
+    for row in texts {
+        lines.push(line(
+            row.iter()
+                .zip(&widths)
+                .zip(&kinds)
+                .map(|((text, width), kind)| {
+                    let padding = width - text.chars().count();
+                    let content = if text.is_empty() {
+                        String::new()
+                    } else {
+                        kind.render(text, color)
+                    };
+                    pad(content, padding, *kind == ColumnKind::Number)
+                })
+                .collect(),
+        ));
+    }
+    lines.join("\n")
+}
+
+fn render_separated(columns: &[String], cells: &[Vec<Option<&Value>>], separator: char) -> String {
+    let escape = |text: &str| {
+        if separator == '\t' {
+            text.replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+        } else if text.contains(&[',', '"', '\n', '\r'][..]) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    };
+    let separator = separator.to_string();
+    let mut lines = Vec::with_capacity(cells.len() + 1);
+    lines.push(
+        columns
+            .iter()
+            .map(|column| escape(column))
+            .collect::<Vec<_>>()
+            .join(&separator),
+    );
+    for row in cells {
+        lines.push(
+            row.iter()
+                .map(|value| escape(&cell_text(*value)))
+                .collect::<Vec<_>>()
+                .join(&separator),
+        );
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_table() {
+        let cells = serde_json::json!([
+            {"capacity": "100.0 (CKB)", "out_point": {"tx_hash": "0xab", "index": 0}, "data": "a,b"},
+            {"capacity": "61.0 (CKB)", "out_point": {"tx_hash": "0xcd", "index": 1}, "type": null},
+        ]);
+        let render = |value: &Value, format, columns: Option<&[String]>| {
+            render_table(value, format, columns, false)
+                .unwrap()
+                .unwrap()
+        };
+        let table = [
+            "capacity     data  out_point.index  out_point.tx_hash  type",
+            "-----------  ----  ---------------  -----------------  ----",
+            "100.0 (CKB)  a,b                 0  0xab",
+            " 61.0 (CKB)                      1  0xcd",
+        ]
+        .join("\n");
+        assert_eq!(render(&cells, OutputFormat::Table, None), table);
+        let value = serde_json::json!({"live_cells": cells, "total_count": 2});
+        assert!(render(&value, OutputFormat::Table, None).starts_with(&format!("{}\n\n", table)));
+
+        let columns = ["out_point".to_string(), "data".to_string()];
+        assert_eq!(
+            render(&value, OutputFormat::Csv, Some(&columns)),
+            "out_point.index,out_point.tx_hash,data\n0,0xab,\"a,b\"\n1,0xcd,"
+        );
+        assert_eq!(
+            render(&value, OutputFormat::Tsv, Some(&columns[1..])),
+            "data\na,b\n"
+        );
+        assert!(
+            render_table(&value, OutputFormat::Csv, Some(&["cap".to_string()]), false).is_err()
+        );
+        assert_eq!(
+            render_table(
+                &serde_json::json!({"a": 1}),
+                OutputFormat::Table,
+                None,
+                false
+            ),
+            Ok(None)
+        );
+    }
+}
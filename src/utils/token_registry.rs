@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ckb_sdk::tx_builder::udt::UdtType;
+use serde_derive::{Deserialize, Serialize};
+
+use super::arg_parser::{ArgParser, CellDepsParser, XudtArgsParser};
+use super::cell_dep::CellDeps;
+
+pub const TOKEN_REGISTRY_FILENAME: &str = "tokens.toml";
+/// 10^38 is the largest power of ten fits in u128
+pub const MAX_UDT_DECIMALS: u8 = 38;
+
+/// Local registry of known UDT tokens, stored in `<ckb-cli-dir>/tokens.toml`
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct TokenRegistry {
+    #[serde(default)]
+    pub tokens: BTreeMap<String, TokenInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct TokenInfo {
+    /// The owner address (sighash), its lock script hash is the UDT type script args
+    pub owner: String,
+    /// sudt or xudt
+    #[serde(default = "default_udt_type")]
+    pub udt_type: String,
+    /// The xUDT type script args after the owner lock hash (hex format)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xudt_args: Option<String>,
+    pub decimals: u8,
+    /// The cell deps file (yaml/json) used by `sudt` subcommands
+    pub cell_deps: PathBuf,
+}
+
+fn default_udt_type() -> String {
+    "sudt".to_string()
+}
+
+impl TokenInfo {
+    pub fn udt_type(&self) -> Result<UdtType, String> {
+        match self.udt_type.as_str() {
+            "sudt" => {
+                if self.xudt_args.is_some() {
+                    return Err("xudt_args is only allowed when udt_type is xudt".to_string());
+                }
+                Ok(UdtType::Sudt)
+            }
+            "xudt" => {
+                let args = match self.xudt_args.as_ref() {
+                    Some(input) => XudtArgsParser.parse(input)?,
+                    None => Default::default(),
+                };
+                Ok(UdtType::Xudt(args))
+            }
+            other => Err(format!("Invalid udt_type: {}", other)),
+        }
+    }
+
+    pub fn cell_deps(&self) -> Result<CellDeps, String> {
+        let path = self.cell_deps.to_str().ok_or_else(|| {
+            format!(
+                "Invalid cell deps path: {}",
+                self.cell_deps.to_string_lossy()
+            )
+        })?;
+        CellDepsParser.parse(path)
+    }
+}
+
+impl TokenRegistry {
+    pub fn path(ckb_cli_dir: &Path) -> PathBuf {
+        ckb_cli_dir.join(TOKEN_REGISTRY_FILENAME)
+    }
+
+    pub fn load(ckb_cli_dir: &Path) -> Result<TokenRegistry, String> {
+        let path = Self::path(ckb_cli_dir);
+        if !path.exists() {
+            return Ok(TokenRegistry::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+        toml::from_str(&content)
+            .map_err(|err| format!("Invalid token registry {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, ckb_cli_dir: &Path) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(Self::path(ckb_cli_dir), content).map_err(|err| err.to_string())
+    }
+
+    pub fn get(&self, symbol: &str) -> Result<&TokenInfo, String> {
+        self.tokens
+            .get(symbol)
+            .ok_or_else(|| format!("Token {} is not in the registry", symbol))
+    }
+
+    pub fn add(&mut self, symbol: String, info: TokenInfo, force: bool) -> Result<(), String> {
+        if symbol.is_empty()
+            || !symbol
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Invalid token symbol: {:?}, only ascii letters, digits, '-' and '_' are allowed",
+                symbol
+            ));
+        }
+        if info.decimals > MAX_UDT_DECIMALS {
+            return Err(format!(
+                "decimals can not be greater than {}",
+                MAX_UDT_DECIMALS
+            ));
+        }
+        info.udt_type()?;
+        if !force && self.tokens.contains_key(&symbol) {
+            return Err(format!(
+                "Token {} already exists, use --force to replace it",
+                symbol
+            ));
+        }
+        self.tokens.insert(symbol, info);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, symbol: &str) -> Result<TokenInfo, String> {
+        self.tokens
+            .remove(symbol)
+            .ok_or_else(|| format!("Token {} is not in the registry", symbol))
+    }
+}
+
+/// Convert a decimal amount string to the raw UDT amount, the conversion is exact (an amount
+/// with more fractional digits than `decimals` is rejected).
+pub fn parse_decimal_amount(input: &str, decimals: u8) -> Result<u128, String> {
+    if decimals > MAX_UDT_DECIMALS {
+        return Err(format!(
+            "decimals can not be greater than {}",
+            MAX_UDT_DECIMALS
+        ));
+    }
+    let (int_part, frac_part) = match input.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, frac_part),
+        None => (input, ""),
+    };
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !is_digits(int_part)
+        || !is_digits(frac_part)
+    {
+        return Err(format!("Invalid amount: {}", input));
+    }
+    if frac_part.len() > decimals as usize {
+        return Err(format!(
+            "Invalid amount: {}, at most {} decimal places are allowed",
+            input, decimals
+        ));
+    }
+    let overflow = || format!("Amount overflow: {}", input);
+    let int_value: u128 = if int_part.is_empty() {
+        0
+    } else {
+        int_part.parse().map_err(|_| overflow())?
+    };
+    let frac_value: u128 = if frac_part.is_empty() {
+        0
+    } else {
+        frac_part.parse().map_err(|_| overflow())?
+    };
+    let frac_scale = 10u128.pow(decimals as u32 - frac_part.len() as u32);
+    int_value
+        .checked_mul(10u128.pow(decimals as u32))
+        .and_then(|value| value.checked_add(frac_value * frac_scale))
+        .ok_or_else(overflow)
+}
+
+/// Format the raw UDT amount as a decimal string, trailing zeros are removed
+pub fn format_decimal_amount(amount: u128, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let base = 10u128.pow(decimals as u32);
+    let frac = format!("{:0width$}", amount % base, width = decimals as usize);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        (amount / base).to_string()
+    } else {
+        format!("{}.{}", amount / base, frac)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_amount() {
+        assert_eq!(parse_decimal_amount("12.5", 8), Ok(1_250_000_000));
+        assert_eq!(parse_decimal_amount("12", 8), Ok(1_200_000_000));
+        assert_eq!(parse_decimal_amount(".00000001", 8), Ok(1));
+        assert_eq!(parse_decimal_amount("7.", 2), Ok(700));
+        assert_eq!(parse_decimal_amount("42", 0), Ok(42));
+        assert!(parse_decimal_amount("0.000000001", 8).is_err());
+        assert!(parse_decimal_amount("1.5", 0).is_err());
+        assert!(parse_decimal_amount(".", 8).is_err());
+        assert!(parse_decimal_amount("-1", 8).is_err());
+        assert!(parse_decimal_amount("1e5", 8).is_err());
+        assert!(parse_decimal_amount("1.2.3", 8).is_err());
+        assert_eq!(
+            parse_decimal_amount(&u128::MAX.to_string(), 0),
+            Ok(u128::MAX)
+        );
+        assert!(parse_decimal_amount(&u128::MAX.to_string(), 1).is_err());
+
+        assert_eq!(format_decimal_amount(1_250_000_000, 8), "12.5");
+        assert_eq!(format_decimal_amount(1_200_000_000, 8), "12");
+        assert_eq!(format_decimal_amount(1, 8), "0.00000001");
+        assert_eq!(format_decimal_amount(0, 8), "0");
+        assert_eq!(format_decimal_amount(42, 0), "42");
+        assert_eq!(
+            format_decimal_amount(u128::MAX, MAX_UDT_DECIMALS),
+            "3.40282366920938463463374607431768211455"
+        );
+        for amount in [0, 1, 99, 123_456_789, u128::MAX] {
+            let formatted = format_decimal_amount(amount, 18);
+            assert_eq!(parse_decimal_amount(&formatted, 18), Ok(amount));
+        }
+    }
+}
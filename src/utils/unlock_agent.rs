@@ -0,0 +1,535 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bitcoin::util::bip32::DerivationPath;
+use ckb_sdk::util::serialize_signature;
+use ckb_signer::{KeyStore, KeyTimeout};
+use ckb_types::{H160, H256};
+use serde_derive::{Deserialize, Serialize};
+
+use super::other::{password_source, PasswordSource};
+use super::rpc::JsonBytes;
+
+pub const AGENT_DIRNAME: &str = "agent";
+const SOCKET_FILENAME: &str = "agent.sock";
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The socket is in a directory only accessible by the current user
+pub fn socket_path(ckb_cli_dir: &Path) -> PathBuf {
+    ckb_cli_dir.join(AGENT_DIRNAME).join(SOCKET_FILENAME)
+}
+
+/// One json request per connection, the response is one json line
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum AgentRequest {
+    Unlock {
+        account: H160,
+        password: String,
+        keep_secs: u64,
+    },
+    Lock {
+        account: H160,
+    },
+    Sign {
+        account: H160,
+        path: String,
+        message: H256,
+        recoverable: bool,
+    },
+    ExtendedPubkey {
+        account: H160,
+        path: String,
+    },
+    Status,
+    /// Lock all accounts and stop the agent
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "result", content = "value", rename_all = "snake_case")]
+pub enum AgentResponse {
+    Ok,
+    Bytes(JsonBytes),
+    Status(AgentStatus),
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentStatus {
+    pub pid: u32,
+    pub idle_timeout_secs: u64,
+    pub accounts: Vec<UnlockedAccount>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnlockedAccount {
+    pub account: H160,
+    /// The account is locked after these seconds
+    pub lock_after_secs: u64,
+}
+
+/// A background ckb-cli process holding keys unlocked by `KeyStore::timed_unlock`, later
+/// ckb-cli invocations sign through its Unix socket without asking for the password.
+pub struct UnlockAgent {
+    key_store: KeyStore,
+    idle_timeout: Duration,
+    last_active: Instant,
+}
+
+impl UnlockAgent {
+    pub fn new(key_store: KeyStore, idle_timeout: Duration) -> UnlockAgent {
+        UnlockAgent {
+            key_store,
+            idle_timeout,
+            last_active: Instant::now(),
+        }
+    }
+
+    /// Serve until there is no request in `idle_timeout` or the agent is stopped. The unlocked
+    /// keys are zeroized once expired, and all of them when the keystore is dropped.
+    pub fn serve(mut self, socket_path: &Path) -> Result<(), String> {
+        let listener = bind(socket_path)?;
+        let result = loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    self.last_active = Instant::now();
+                    match self.handle(stream) {
+                        Ok(true) => break Ok(()),
+                        Ok(false) => {}
+                        Err(err) => log::warn!("Handle unlock agent request error: {}", err),
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    self.lock_expired();
+                    if self.last_active.elapsed() >= self.idle_timeout {
+                        break Ok(());
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(err) => break Err(err.to_string()),
+            }
+        };
+        let _ = fs::remove_file(socket_path);
+        result
+    }
+
+    fn lock_expired(&mut self) {
+        for account in self.key_store.lock_expired() {
+            log::info!("Account {:#x} is locked", account);
+        }
+    }
+
+    /// Returns true if the agent is stopped
+    fn handle(&mut self, stream: UnixStream) -> Result<bool, String> {
+        stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(CLIENT_TIMEOUT)))
+            .map_err(|err| err.to_string())?;
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .map_err(|err| err.to_string())?;
+        let (response, stop) = match serde_json::from_str::<AgentRequest>(&line) {
+            Ok(request) => self.process(request),
+            Err(err) => (
+                AgentResponse::Error(format!("Invalid unlock agent request: {}", err)),
+                false,
+            ),
+        };
+        let mut content = serde_json::to_string(&response).expect("Serialize response error");
+        content.push('\n');
+        (&stream)
+            .write_all(content.as_bytes())
+            .map_err(|err| err.to_string())?;
+        Ok(stop)
+    }
+
+    fn process(&mut self, request: AgentRequest) -> (AgentResponse, bool) {
+        let result = match request {
+            AgentRequest::Unlock {
+                account,
+                password,
+                keep_secs,
+            } => {
+                // The account may be created after the agent started
+                self.key_store.refresh_dir().ok();
+                self.key_store
+                    .timed_unlock(
+                        &account,
+                        password.as_bytes(),
+                        Duration::from_secs(keep_secs),
+                    )
+                    .map(|_| AgentResponse::Ok)
+                    .map_err(|err| err.to_string())
+            }
+            AgentRequest::Lock { account } => {
+                self.key_store.lock(&account);
+                Ok(AgentResponse::Ok)
+            }
+            AgentRequest::Sign {
+                account,
+                path,
+                message,
+                recoverable,
+            } => DerivationPath::from_str(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|path| {
+                    if recoverable {
+                        self.key_store
+                            .sign_recoverable(&account, &path, &message)
+                            .map(|sig| serialize_signature(&sig).to_vec())
+                    } else {
+                        self.key_store
+                            .sign(&account, &path, &message)
+                            .map(|sig| sig.serialize_compact().to_vec())
+                    }
+                    .map_err(|err| err.to_string())
+                })
+                .map(|data| AgentResponse::Bytes(JsonBytes::from_vec(data))),
+            AgentRequest::ExtendedPubkey { account, path } => DerivationPath::from_str(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|path| {
+                    self.key_store
+                        .extended_pubkey(&account, &path)
+                        .map_err(|err| err.to_string())
+                })
+                .map(|pubkey| {
+                    AgentResponse::Bytes(JsonBytes::from_vec(
+                        pubkey.public_key.serialize().to_vec(),
+                    ))
+                }),
+            AgentRequest::Status => Ok(AgentResponse::Status(self.status())),
+            AgentRequest::Stop => return (AgentResponse::Ok, true),
+        };
+        (result.unwrap_or_else(AgentResponse::Error), false)
+    }
+
+    fn status(&mut self) -> AgentStatus {
+        let now = Instant::now();
+        let mut accounts = self
+            .key_store
+            .get_accounts()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        accounts.sort();
+        let accounts = accounts
+            .into_iter()
+            .filter_map(|account| match self.key_store.get_lock_timeout(&account) {
+                Some(KeyTimeout::Timeout(timeout)) if timeout > now => Some(UnlockedAccount {
+                    account,
+                    lock_after_secs: (timeout - now).as_secs(),
+                }),
+                _ => None,
+            })
+            .collect();
+        AgentStatus {
+            pid: std::process::id(),
+            idle_timeout_secs: self.idle_timeout.as_secs(),
+            accounts,
+        }
+    }
+}
+
+fn bind(socket_path: &Path) -> Result<UnixListener, String> {
+    let dir = socket_path.parent().expect("socket path has parent");
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::set_permissions(dir, fs::Permissions::from_mode(0o700)))
+        .map_err(|err| format!("Create unlock agent directory {:?} failed: {}", dir, err))?;
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).is_ok() {
+            return Err(format!(
+                "The unlock agent is already running: {:?}",
+                socket_path
+            ));
+        }
+        // Left by an agent which is killed
+        fs::remove_file(socket_path).map_err(|err| err.to_string())?;
+    }
+    let listener = UnixListener::bind(socket_path)
+        .map_err(|err| format!("Bind {:?} failed: {}", socket_path, err))?;
+    fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600))
+        .and_then(|_| listener.set_nonblocking(true))
+        .map_err(|err| err.to_string())?;
+    Ok(listener)
+}
+
+pub struct AgentClient {
+    socket_path: PathBuf,
+}
+
+impl AgentClient {
+    pub fn new(ckb_cli_dir: &Path) -> AgentClient {
+        AgentClient {
+            socket_path: socket_path(ckb_cli_dir),
+        }
+    }
+
+    /// Start the agent in a background ckb-cli process, fail if it is already running
+    pub fn start(ckb_cli_dir: &Path, idle_timeout: Duration) -> Result<AgentClient, String> {
+        let client = AgentClient::new(ckb_cli_dir);
+        if client.is_running() {
+            return Err(format!(
+                "The unlock agent is already running: {:?}",
+                client.socket_path
+            ));
+        }
+        let exe = env::current_exe().map_err(|err| err.to_string())?;
+        let mut command = Command::new(exe);
+        command
+            .env("CKB_CLI_HOME", ckb_cli_dir)
+            .args([
+                "--local-only",
+                "account",
+                "agent",
+                "serve",
+                "--idle-timeout",
+            ])
+            .arg(format!("{}s", idle_timeout.as_secs()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // The password is not read yet, the agent must not inherit it
+        if let Some(PasswordSource::Env(name)) = password_source() {
+            command.env_remove(name);
+        }
+        // Run in a new session, so it's not killed with the terminal or by Ctrl-C
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(())
+                }
+            });
+        }
+        command
+            .spawn()
+            .map_err(|err| format!("Start unlock agent failed: {}", err))?;
+        let start_time = Instant::now();
+        while !client.is_running() {
+            if start_time.elapsed() > START_TIMEOUT {
+                return Err("Start unlock agent timeout".to_string());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(client)
+    }
+
+    pub fn is_running(&self) -> bool {
+        UnixStream::connect(&self.socket_path).is_ok()
+    }
+
+    pub fn call(&self, request: &AgentRequest) -> Result<AgentResponse, String> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .map_err(|err| format!("Connect to unlock agent failed: {}", err))?;
+        stream
+            .set_read_timeout(Some(CLIENT_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(CLIENT_TIMEOUT)))
+            .map_err(|err| err.to_string())?;
+        let mut content = serde_json::to_string(request).expect("Serialize request error");
+        content.push('\n');
+        (&stream)
+            .write_all(content.as_bytes())
+            .map_err(|err| err.to_string())?;
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .map_err(|err| err.to_string())?;
+        match serde_json::from_str(&line) {
+            Ok(AgentResponse::Error(err)) => Err(err),
+            Ok(response) => Ok(response),
+            Err(err) => Err(format!("Invalid unlock agent response: {}", err)),
+        }
+    }
+
+    pub fn unlock(&self, account: H160, password: String, keep: Duration) -> Result<(), String> {
+        self.call(&AgentRequest::Unlock {
+            account,
+            password,
+            keep_secs: keep.as_secs(),
+        })
+        .map(|_| ())
+    }
+
+    pub fn lock(&self, account: H160) -> Result<(), String> {
+        self.call(&AgentRequest::Lock { account }).map(|_| ())
+    }
+
+    pub fn status(&self) -> Result<AgentStatus, String> {
+        match self.call(&AgentRequest::Status)? {
+            AgentResponse::Status(status) => Ok(status),
+            _ => Err("Mismatch unlock agent response".to_string()),
+        }
+    }
+
+    /// Returns false if the agent is not running
+    pub fn is_unlocked(&self, account: &H160) -> bool {
+        self.is_running()
+            && self
+                .status()
+                .map(|status| status.accounts.iter().any(|item| &item.account == account))
+                .unwrap_or(false)
+    }
+
+    pub fn sign(
+        &self,
+        account: H160,
+        path: String,
+        message: H256,
+        recoverable: bool,
+    ) -> Result<Vec<u8>, String> {
+        self.call_bytes(&AgentRequest::Sign {
+            account,
+            path,
+            message,
+            recoverable,
+        })
+    }
+
+    pub fn extended_pubkey(&self, account: H160, path: String) -> Result<Vec<u8>, String> {
+        self.call_bytes(&AgentRequest::ExtendedPubkey { account, path })
+    }
+
+    pub fn stop(&self) -> Result<(), String> {
+        self.call(&AgentRequest::Stop).map(|_| ())
+    }
+
+    fn call_bytes(&self, request: &AgentRequest) -> Result<Vec<u8>, String> {
+        match self.call(request)? {
+            AgentResponse::Bytes(data) => Ok(data.into_bytes().to_vec()),
+            _ => Err("Mismatch unlock agent response".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_signer::ScryptType;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ckb-cli-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn test_key_store(dir: &Path) -> (KeyStore, H160) {
+        let keystore_dir = dir.join("keystore");
+        fs::create_dir_all(&keystore_dir).unwrap();
+        let mut key_store =
+            KeyStore::from_dir(keystore_dir, ScryptType::Custom { log_n: 10, p: 1 }).unwrap();
+        let account = key_store.new_account(b"123").unwrap();
+        (key_store, account)
+    }
+
+    #[test]
+    fn test_unlock_agent() {
+        let dir = test_dir("unlock-agent");
+        let (key_store, account) = test_key_store(&dir);
+        let path = socket_path(&dir);
+        let handle = {
+            let path = path.clone();
+            thread::spawn(move || UnlockAgent::new(key_store, Duration::from_secs(2)).serve(&path))
+        };
+        let client = AgentClient::new(&dir);
+        let start_time = Instant::now();
+        while !client.is_running() {
+            assert!(start_time.elapsed() < START_TIMEOUT);
+            thread::sleep(POLL_INTERVAL);
+        }
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(mode(&dir.join(AGENT_DIRNAME)), 0o700);
+
+        let message = H256::from([1u8; 32]);
+        let sign = |recoverable| {
+            client.sign(
+                account.clone(),
+                "m".to_string(),
+                message.clone(),
+                recoverable,
+            )
+        };
+        assert!(sign(true).is_err());
+        assert!(client
+            .unlock(account.clone(), "321".to_string(), Duration::from_secs(60))
+            .is_err());
+        assert!(!client.is_unlocked(&account));
+
+        client
+            .unlock(account.clone(), "123".to_string(), Duration::from_secs(60))
+            .unwrap();
+        assert!(client.is_unlocked(&account));
+        assert_eq!(sign(true).unwrap().len(), 65);
+        assert_eq!(sign(false).unwrap().len(), 64);
+        assert!(client
+            .sign(
+                account.clone(),
+                "invalid".to_string(),
+                message.clone(),
+                true
+            )
+            .is_err());
+        client.lock(account.clone()).unwrap();
+        assert!(!client.is_unlocked(&account));
+        assert!(sign(true).is_err());
+
+        // Locked by the agent once the unlock time is over
+        client
+            .unlock(account.clone(), "123".to_string(), Duration::from_secs(1))
+            .unwrap();
+        assert!(client.is_unlocked(&account));
+        thread::sleep(Duration::from_millis(1500));
+        assert!(!client.is_unlocked(&account));
+        assert!(sign(true).is_err());
+
+        // Exit and remove the socket when there is no request in the idle timeout
+        assert_eq!(handle.join().unwrap(), Ok(()));
+        assert!(!path.exists());
+        assert!(!client.is_running());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lock_expired() {
+        let dir = test_dir("lock-expired");
+        let (key_store, account) = test_key_store(&dir);
+        let mut agent = UnlockAgent::new(key_store, Duration::from_secs(60));
+        let unlock = |keep_secs| AgentRequest::Unlock {
+            account: account.clone(),
+            password: "123".to_string(),
+            keep_secs,
+        };
+        assert!(matches!(
+            agent.process(unlock(0)),
+            (AgentResponse::Ok, false)
+        ));
+        agent.lock_expired();
+        assert!(agent.key_store.get_lock_timeout(&account).is_none());
+        assert!(matches!(
+            agent.process(unlock(60)),
+            (AgentResponse::Ok, false)
+        ));
+        agent.lock_expired();
+        assert!(agent.key_store.get_lock_timeout(&account).is_some());
+        assert!(matches!(
+            agent.process(AgentRequest::Stop),
+            (AgentResponse::Ok, true)
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}